@@ -1,32 +1,226 @@
 
 use std::collections::HashMap;
+use std::fmt::Write;
 
-use crate::parser::ast::Ast;
-use super::symbol::Symbol;
-use super::datatype::Function;
-use super::datatype::Struct;
-
-pub struct Compiler<'a> {
-    pub ast: &'a Ast,
-    pub code: &'a mut String,
-    pub symbols: HashMap<&'a String, Symbol<'a>>,
-    pub functions: Vec<Function<'a>>,
-    pub structs: Vec<Struct<'a>>,
-    // TODO: Add types in the future.
+use crate::generator::instructions::*;
+use crate::mangle::demangle;
+
+// Each symbol is given a slot in the stack frame, sized and aligned for its
+// datatype. rbp is 16 byte aligned, so that is the most a slot can be.
+// Variables are identified by name and declaration, so a shadowing variable
+// gets a slot of its own. For temporaries, each version is a separate value.
+// Global symbols are addressed relative to the instruction pointer instead.
+// Since every version of a variable shares its slot, a pointer to it sees
+// every write, so variables whose address is taken need no special handling.
+
+pub struct Frame {
+    slots: HashMap<String, usize>,
+    pub size: usize,
 }
 
-impl<'a> Compiler<'a> {
-    pub fn new(ast: &'a Ast, code: &'a mut String) -> Compiler<'a> {
+impl Frame {
+    pub fn new() -> Frame {
+        Frame {
+            slots: HashMap::new(),
+            size: 0,
+        }
+    }
+
+    fn key(symbol: &Symbol) -> String {
+        if symbol.name.starts_with("__") {
+            format!("{}.{}", symbol.name, symbol.version)
+        } else {
+            format!("{}@{}", symbol.name, symbol.declaration)
+        }
+    }
+
+    pub fn location(&mut self, symbol: &Symbol) -> String {
+        if symbol.global {
+            return format!("{}(%rip)", symbol.name);
+        }
+        let key = Self::key(symbol);
+        let offset = match self.slots.get(&key) {
+            Some(offset) => *offset,
+            None => {
+                if symbol.alignment > 16 {
+                    panic!("{} can't be aligned to {} bytes on the stack, only up to 16",
+                           symbol.name, symbol.alignment);
+                }
+                let alignment = symbol.alignment.max(8);
+                self.size += (symbol.size + 7) / 8 * 8;
+                self.size = (self.size + alignment - 1) / alignment * alignment;
+                self.slots.insert(key, self.size);
+                self.size
+            },
+        };
+        return format!("-{}(%rbp)", offset);
+    }
+}
+
+// Writes the message given by address in rdi and length in rsi to stderr,
+// then exits with status 101. Only uses system calls, so doesn't need libc.
+const PANIC_ROUTINE: &str = "\
+__panic:
+    movq %rsi, %rdx
+    movq %rdi, %rsi
+    movq $2, %rdi
+    movq $1, %rax
+    syscall
+    movq $101, %rdi
+    movq $60, %rax
+    syscall
+";
+
+pub struct Compiler {
+    pub text: String,
+    pub data: String,
+    pub rodata: String,
+    pub bss: String,
+    pub body: String,
+    pub frame: Frame,
+    pub function: Option<String>,
+    // Floating point constants are loaded from read only data, labelled by
+    // their size and bits
+    pub float_constants: HashMap<(usize, i64), String>,
+    // Arguments of the current function read so far, of each register class
+    pub integer_arguments: usize,
+    pub float_arguments: usize,
+    // and of those passed on the stack, whichever class they are
+    pub stack_arguments: usize,
+    // Number of checked operations, each labelled after its check
+    pub overflow_checks: usize,
+    // The panic routine is only added if it is called
+    pub panic: bool,
+    // Entry point of the program, if it has one
+    pub start: String,
+    // Labels defined outside the program, in declaration order
+    pub externs: Vec<String>,
+}
+
+impl Compiler {
+    pub fn new() -> Compiler {
         Compiler {
-            ast: ast,
-            code: code,
-            symbols: HashMap::new(),
-            functions: Vec::new(),
-            structs: Vec::new(),
+            text: String::new(),
+            data: String::new(),
+            rodata: String::new(),
+            bss: String::new(),
+            body: String::new(),
+            frame: Frame::new(),
+            function: None,
+            float_constants: HashMap::new(),
+            integer_arguments: 0,
+            float_arguments: 0,
+            stack_arguments: 0,
+            overflow_checks: 0,
+            panic: false,
+            start: String::new(),
+            externs: Vec::new(),
+        }
+    }
+
+    pub fn is_extern(&self, label: &String) -> bool {
+        return self.externs.contains(label);
+    }
+
+    pub fn emit(&mut self, line: &str) {
+        writeln!(self.body, "    {}", line).expect("Failed to write instruction");
+    }
+
+    pub fn start_function(&mut self, name: &String) {
+        self.end_function();
+        self.function = Some(String::clone(name));
+        self.integer_arguments = 0;
+        self.float_arguments = 0;
+        self.stack_arguments = 0;
+    }
+
+    // Function bodies are written before the frame size is known, so the
+    // prologue is added once the whole function has been compiled.
+    pub fn end_function(&mut self) {
+        let name = match self.function.take() {
+            Some(name) => name,
+            None => return,
+        };
+        let frame_size = (self.frame.size + 15) / 16 * 16;
+        // Mangled labels are only used within the program, any other
+        // function is called from outside it
+        match demangle(&name) {
+            Some(demangled) => writeln!(self.text, "# {}", demangled).unwrap(),
+            None => writeln!(self.text, "    .globl {}", name).unwrap(),
         }
+        writeln!(self.text, "{}:", name).unwrap();
+        writeln!(self.text, "    pushq %rbp").unwrap();
+        writeln!(self.text, "    movq %rsp, %rbp").unwrap();
+        if frame_size > 0 {
+            writeln!(self.text, "    subq ${}, %rsp", frame_size).unwrap();
+        }
+        self.text.push_str(&self.body);
+        self.body.clear();
+        self.frame = Frame::new();
     }
 
-    pub fn add_symbol(&mut self, name: &'a String, symbol: Symbol<'a>) {
-        self.symbols.insert(name, symbol);
+    pub fn output(mut self) -> String {
+        self.end_function();
+        let mut code = String::new();
+        for label in &self.externs {
+            writeln!(code, "    .extern {}", label).unwrap();
+        }
+        if !self.data.is_empty() {
+            code.push_str("    .data\n");
+            code.push_str(&self.data);
+        }
+        if !self.rodata.is_empty() {
+            code.push_str("    .section .rodata\n");
+            code.push_str(&self.rodata);
+        }
+        if !self.bss.is_empty() {
+            code.push_str("    .bss\n");
+            code.push_str(&self.bss);
+        }
+        code.push_str("    .text\n");
+        code.push_str(&self.text);
+        code.push_str(&self.start);
+        if self.panic {
+            code.push_str(PANIC_ROUTINE);
+        }
+        return code;
+    }
+}
+
+// ===== Register and operand formatting =====
+
+pub fn suffix(size: usize) -> &'static str {
+    match size {
+        1 => "b",
+        2 => "w",
+        4 => "l",
+        8 => "q",
+        _ => panic!("No instruction suffix for operand size {}", size),
+    }
+}
+
+pub fn register(name: &str, size: usize) -> String {
+    // name is the 64 bit register without the "r", eg: "ax", "di", "8"
+    let numbered = name.chars().all(|c| c.is_ascii_digit());
+    if numbered {
+        return match size {
+            1 => format!("%r{}b", name),
+            2 => format!("%r{}w", name),
+            4 => format!("%r{}d", name),
+            8 => format!("%r{}", name),
+            _ => panic!("No register for operand size {}", size),
+        };
+    }
+    let legacy = name.ends_with('x');
+    match size {
+        1 => if legacy {
+            format!("%{}l", &name[..1])
+        } else {
+            format!("%{}l", name)
+        },
+        2 => format!("%{}", name),
+        4 => format!("%e{}", name),
+        8 => format!("%r{}", name),
+        _ => panic!("No register for operand size {}", size),
     }
 }
@@ -0,0 +1,88 @@
+
+use std::fmt::Write;
+
+use crate::generator::instructions::*;
+use crate::mangle::demangle;
+use super::compiler::Compiler;
+
+fn get_directive(size: usize) -> &'static str {
+    match size {
+        1 => ".byte",
+        2 => ".short",
+        4 => ".long",
+        8 => ".quad",
+        _ => panic!("Can't initialise static data of size {} with a constant", size),
+    }
+}
+
+pub fn compile_static(compiler: &mut Compiler, operands: &[Operand]) {
+    // label , size , alignment , [ constant ]
+    let label = match &operands[0] {
+        Operand::Label(label) => label,
+        _ => panic!("Expected label for static data"),
+    };
+    let size = match &operands[1] {
+        Operand::Integer(size) => *size as usize,
+        _ => panic!("Expected size for static data"),
+    };
+    let alignment = match &operands[2] {
+        Operand::Integer(alignment) => *alignment as usize,
+        _ => panic!("Expected alignment for static data"),
+    };
+    let value = match operands.get(3) {
+        Some(Operand::Constant(Constant::Int(value))) => *value,
+        Some(Operand::Constant(Constant::Float(value))) => match size {
+            4 => (*value as f32).to_bits() as i64,
+            _ => value.to_bits() as i64,
+        },
+        Some(operand) => panic!("Invalid initial value {} for static data", operand),
+        None => 0,
+    };
+
+    // Zero initialised data doesn't need to be stored in the executable
+    let section = if value == 0 {
+        &mut compiler.bss
+    } else {
+        &mut compiler.data
+    };
+    // Static data is only used within the program
+    if let Some(demangled) = demangle(label) {
+        writeln!(section, "# {}", demangled).unwrap();
+    }
+    writeln!(section, "    .balign {}", alignment).unwrap();
+    writeln!(section, "{}:", label).unwrap();
+    if value == 0 {
+        writeln!(section, "    .zero {}", size).unwrap();
+    } else {
+        writeln!(section, "    {} {}", get_directive(size), value).unwrap();
+    }
+}
+
+pub fn compile_read_only(compiler: &mut Compiler, operands: &[Operand]) {
+    // label , bytes
+    let label = match &operands[0] {
+        Operand::Label(label) => label,
+        _ => panic!("Expected label for read only data"),
+    };
+    let bytes = match &operands[1] {
+        Operand::Bytes(bytes) => bytes,
+        _ => panic!("Expected bytes for read only data"),
+    };
+    let values: Vec<String> = bytes.iter().map(|byte| byte.to_string()).collect();
+    writeln!(compiler.rodata, "{}:", label).unwrap();
+    writeln!(compiler.rodata, "    .byte {}", values.join(", ")).unwrap();
+}
+
+// Label of a floating point constant in read only data, given its bits.
+// Each value is only stored once.
+pub fn get_float_constant(compiler: &mut Compiler, bits: i64, size: usize) -> String {
+    if let Some(label) = compiler.float_constants.get(&(size, bits)) {
+        return String::clone(label);
+    }
+    let label = format!(".LF{}", compiler.float_constants.len());
+    writeln!(compiler.rodata, "    .balign {}", size).unwrap();
+    writeln!(compiler.rodata, "{}:", label).unwrap();
+    writeln!(compiler.rodata, "    {} {}", get_directive(size), bits).unwrap();
+    compiler.float_constants.insert((size, bits), String::clone(&label));
+    return label;
+}
@@ -0,0 +1,805 @@
+
+use std::fmt::Write;
+
+use crate::generator::instructions::*;
+use super::compiler::*;
+use super::data::compile_static;
+use super::data::compile_read_only;
+use super::data::get_float_constant;
+
+// Integer arguments and return values, in order of PassLocation index
+const ARGUMENT_REGISTERS: [&str; 6] = ["di", "si", "dx", "cx", "8", "9"];
+// Floating point arguments are passed in xmm0 to xmm7, counted separately
+// from integer arguments
+const FLOAT_ARGUMENT_REGISTERS: usize = 8;
+// System call arguments, which use r10 in place of rcx
+const SYSCALL_REGISTERS: [&str; 6] = ["di", "si", "dx", "10", "8", "9"];
+
+fn expect_symbol(operand: &Operand) -> &Symbol {
+    match operand {
+        Operand::Symbol(symbol) => symbol,
+        _ => panic!("Expected symbol operand, found {}", operand),
+    }
+}
+
+fn expect_label(operand: &Operand) -> &String {
+    match operand {
+        Operand::Label(label) => label,
+        _ => panic!("Expected label operand, found {}", operand),
+    }
+}
+
+fn constant_bits(constant: &Constant, size: usize) -> i64 {
+    match constant {
+        Constant::Int(value) => *value,
+        Constant::Float(value) => match size {
+            4 => (*value as f32).to_bits() as i64,
+            8 => value.to_bits() as i64,
+            _ => panic!("Invalid size {} for floating point constant", size),
+        },
+    }
+}
+
+// Read a symbol or constant of the given size into a register.
+// Values narrower than 32 bits are zero extended, so the whole register is
+// defined. Constants hold the exact value, which is extended by its sign.
+fn load_operand(compiler: &mut Compiler, operand: &Operand, reg: &str, size: usize) {
+    match operand {
+        Operand::Symbol(symbol) => {
+            let location = compiler.frame.location(symbol);
+            if size < 4 {
+                compiler.emit(&format!("movz{}l {}, {}", suffix(size), location, register(reg, 4)));
+            } else {
+                compiler.emit(&format!("mov{} {}, {}", suffix(size), location, register(reg, size)));
+            }
+        },
+        Operand::Constant(constant) => {
+            let value = constant_bits(constant, size);
+            if size < 8 {
+                compiler.emit(&format!("movl ${}, {}", value, register(reg, 4)));
+            } else if value < i32::MIN as i64 || value > i32::MAX as i64 {
+                compiler.emit(&format!("movabsq ${}, {}", value, register(reg, 8)));
+            } else {
+                compiler.emit(&format!("movq ${}, {}", value, register(reg, 8)));
+            }
+        },
+        _ => panic!("Can't load operand {}", operand),
+    }
+}
+
+// Read an integer into a register like load_operand, but sign extending
+// narrow symbols if their datatype is signed
+fn load_integer(compiler: &mut Compiler, operand: &Operand, reg: &str, size: usize, signed: bool) {
+    match operand {
+        Operand::Symbol(symbol) if size < 4 && signed => {
+            let symbol_location = compiler.frame.location(symbol);
+            compiler.emit(&format!("movs{}l {}, {}", suffix(size), symbol_location, register(reg, 4)));
+        },
+        _ => load_operand(compiler, operand, reg, size),
+    }
+}
+
+// Narrow integers are passed and returned extended to 32 bits, by the
+// signedness of their datatype
+fn load_extended(compiler: &mut Compiler, operand: &Operand, reg: &str, location: &PassLocation) {
+    load_integer(compiler, operand, reg, location.size, location.signed);
+}
+
+fn store_symbol(compiler: &mut Compiler, reg: &str, symbol: &Symbol) {
+    let location = compiler.frame.location(symbol);
+    compiler.emit(&format!("mov{} {}, {}", suffix(symbol.size), register(reg, symbol.size), location));
+}
+
+// Suffix of scalar SSE instructions for a floating point size
+fn float_suffix(size: usize) -> &'static str {
+    match size {
+        4 => "ss",
+        8 => "sd",
+        _ => panic!("No floating point instructions for operand size {}", size),
+    }
+}
+
+// Read a floating point symbol or constant into an xmm register. Constants
+// can't be immediates, so are read from read only data.
+fn load_float(compiler: &mut Compiler, operand: &Operand, xmm: usize, size: usize) {
+    let location = match operand {
+        Operand::Symbol(symbol) => compiler.frame.location(symbol),
+        Operand::Constant(constant) => {
+            let bits = constant_bits(constant, size);
+            format!("{}(%rip)", get_float_constant(compiler, bits, size))
+        },
+        _ => panic!("Can't load operand {}", operand),
+    };
+    compiler.emit(&format!("mov{} {}, %xmm{}", float_suffix(size), location, xmm));
+}
+
+fn store_float(compiler: &mut Compiler, xmm: usize, symbol: &Symbol) {
+    let location = compiler.frame.location(symbol);
+    compiler.emit(&format!("mov{} %xmm{}, {}", float_suffix(symbol.size), xmm, location));
+}
+
+// Structs don't fit in a register, so are copied byte by byte.
+// Expects source and destination addresses in rsi and rdi.
+fn copy_bytes(compiler: &mut Compiler, size: usize) {
+    compiler.emit(&format!("movq ${}, %rcx", size));
+    compiler.emit("rep movsb");
+}
+
+// Copies a struct like copy_bytes, but from the address in r11 to the
+// address in r10, only using rax, so argument registers are kept
+fn copy_words(compiler: &mut Compiler, size: usize) {
+    let mut offset = 0;
+    while offset < size {
+        let mut width = 8;
+        while width > size - offset {
+            width /= 2;
+        }
+        compiler.emit(&format!("mov{} {}(%r11), {}", suffix(width), offset, register("ax", width)));
+        compiler.emit(&format!("mov{} {}, {}(%r10)", suffix(width), register("ax", width), offset));
+        offset += width;
+    }
+}
+
+fn compile_move(compiler: &mut Compiler, operands: &[Operand]) {
+    // src , dst
+    let dst = expect_symbol(&operands[1]);
+    if dst.size > 8 {
+        let src = expect_symbol(&operands[0]);
+        let src_location = compiler.frame.location(src);
+        let dst_location = compiler.frame.location(dst);
+        compiler.emit(&format!("leaq {}, %rsi", src_location));
+        compiler.emit(&format!("leaq {}, %rdi", dst_location));
+        copy_bytes(compiler, dst.size);
+        return;
+    }
+    load_operand(compiler, &operands[0], "ax", dst.size);
+    store_symbol(compiler, "ax", dst);
+}
+
+// Narrow values are extended to 32 bits when loaded, which also clears the
+// upper half of the register, so only sign extension to 64 bits is left.
+// A narrower dst takes the low bytes.
+fn compile_convert(compiler: &mut Compiler, signed: bool, operands: &[Operand]) {
+    // src , dst
+    let src = expect_symbol(&operands[0]);
+    let dst = expect_symbol(&operands[1]);
+    load_integer(compiler, &operands[0], "ax", src.size, signed);
+    if signed && src.size < 8 && dst.size == 8 {
+        compiler.emit("cltq");
+    }
+    store_symbol(compiler, "ax", dst);
+}
+
+fn compile_load(compiler: &mut Compiler, operands: &[Operand]) {
+    // src (pointer) , dst
+    let dst = expect_symbol(&operands[1]);
+    load_operand(compiler, &operands[0], "si", 8);
+    if dst.size > 8 {
+        let dst_location = compiler.frame.location(dst);
+        compiler.emit(&format!("leaq {}, %rdi", dst_location));
+        copy_bytes(compiler, dst.size);
+        return;
+    }
+    compiler.emit(&format!("mov{} (%rsi), {}", suffix(dst.size), register("ax", dst.size)));
+    store_symbol(compiler, "ax", dst);
+}
+
+fn compile_store(compiler: &mut Compiler, operands: &[Operand]) {
+    // src , dst (pointer)
+    load_operand(compiler, &operands[1], "di", 8);
+    let size = match &operands[0] {
+        Operand::Symbol(symbol) => symbol.size,
+        // Constants are only stored through pointers to primitives
+        _ => 8,
+    };
+    if size > 8 {
+        let src_location = compiler.frame.location(expect_symbol(&operands[0]));
+        compiler.emit(&format!("leaq {}, %rsi", src_location));
+        copy_bytes(compiler, size);
+        return;
+    }
+    load_operand(compiler, &operands[0], "ax", size);
+    compiler.emit(&format!("mov{} {}, (%rdi)", suffix(size), register("ax", size)));
+}
+
+fn compile_address(compiler: &mut Compiler, operands: &[Operand]) {
+    // src , dst
+    // Taking the address of a function gives a label as the source
+    // Extern functions may be in a shared library, so their address is
+    // read from the global offset table
+    match &operands[0] {
+        Operand::Label(label) if compiler.is_extern(label) => {
+            compiler.emit(&format!("movq {}@GOTPCREL(%rip), %rax", label));
+        },
+        Operand::Label(label) => compiler.emit(&format!("leaq {}(%rip), %rax", label)),
+        operand => {
+            let src_location = compiler.frame.location(expect_symbol(operand));
+            compiler.emit(&format!("leaq {}, %rax", src_location));
+        },
+    }
+    store_symbol(compiler, "ax", expect_symbol(&operands[1]));
+}
+
+fn compile_get_argument(compiler: &mut Compiler, operands: &[Operand]) {
+    // pass location , dst
+    // Arguments are read in order at the start of the function, so the
+    // register for each is given by the arguments of its class before it.
+    let location = match &operands[0] {
+        Operand::PassLocation(location) => location,
+        _ => panic!("Expected pass location for GetArgument"),
+    };
+    let dst = expect_symbol(&operands[1]);
+    let (index, in_register) = match location.regtype {
+        Regtype::Float => {
+            compiler.float_arguments += 1;
+            (compiler.float_arguments - 1, compiler.float_arguments <= FLOAT_ARGUMENT_REGISTERS)
+        },
+        _ => {
+            compiler.integer_arguments += 1;
+            (compiler.integer_arguments - 1, compiler.integer_arguments <= ARGUMENT_REGISTERS.len())
+        },
+    };
+    // Remaining arguments are pushed by the caller, in order above the
+    // return address, in 8 byte slots
+    let src = match (location.regtype, in_register) {
+        (Regtype::Float, true) => {
+            store_float(compiler, index, dst);
+            return;
+        },
+        (_, true) => register(ARGUMENT_REGISTERS[index], 8),
+        (_, false) => {
+            compiler.stack_arguments += 1;
+            format!("{}(%rbp)", 16 + 8 * (compiler.stack_arguments - 1))
+        },
+    };
+    match location.regtype {
+        // Structs are passed by the address of the caller's value, which is
+        // copied so the function has its own
+        Regtype::Struct => {
+            let dst_location = compiler.frame.location(dst);
+            compiler.emit(&format!("movq {}, %r11", src));
+            compiler.emit(&format!("leaq {}, %r10", dst_location));
+            copy_words(compiler, dst.size);
+        },
+        _ if in_register => store_symbol(compiler, ARGUMENT_REGISTERS[index], dst),
+        // Floating point values are moved as their bits
+        _ => {
+            compiler.emit(&format!("movq {}, %rax", src));
+            store_symbol(compiler, "ax", dst);
+        },
+    }
+}
+
+fn compile_set_returned(compiler: &mut Compiler, operands: &[Operand]) {
+    // src , pass location
+    let location = match &operands[1] {
+        Operand::PassLocation(location) => location,
+        _ => panic!("Expected pass location for SetReturned"),
+    };
+    if location.index != 0 {
+        panic!("Multiple return values not implemented yet");
+    }
+    match location.regtype {
+        Regtype::Integer | Regtype::Pointer => load_extended(compiler, &operands[0], "ax", location),
+        Regtype::Float => load_float(compiler, &operands[0], 0, location.size),
+        _ => panic!("Returning {} values not implemented yet", location.regtype),
+    }
+}
+
+fn compile_get_returned(compiler: &mut Compiler, operands: &[Operand]) {
+    // pass location , dst
+    let location = match &operands[0] {
+        Operand::PassLocation(location) => location,
+        _ => panic!("Expected pass location for GetReturned"),
+    };
+    if location.index != 0 {
+        panic!("Multiple return values not implemented yet");
+    }
+    match location.regtype {
+        Regtype::Integer | Regtype::Pointer => store_symbol(compiler, "ax", expect_symbol(&operands[1])),
+        Regtype::Float => store_float(compiler, 0, expect_symbol(&operands[1])),
+        _ => panic!("Returning {} values not implemented yet", location.regtype),
+    }
+}
+
+// Floating point argument in xmm<index>, promoted to f64 if it is passed
+// wider than its value
+fn load_float_argument(compiler: &mut Compiler, argument: &Operand, index: usize, location: &PassLocation) {
+    let size = match argument {
+        Operand::Symbol(symbol) => symbol.size,
+        _ => location.size,
+    };
+    load_float(compiler, argument, index, size);
+    if size < location.size {
+        compiler.emit(&format!("cvtss2sd %xmm{}, %xmm{}", index, index));
+    }
+}
+
+// Push an argument that doesn't fit in the registers of its class, in an
+// 8 byte slot
+fn compile_push_argument(compiler: &mut Compiler, argument: &Operand, location: &PassLocation) {
+    match location.regtype {
+        Regtype::Integer | Regtype::Pointer => load_extended(compiler, argument, "ax", location),
+        Regtype::Float => {
+            load_float_argument(compiler, argument, 0, location);
+            compiler.emit("movq %xmm0, %rax");
+        },
+        Regtype::Struct => {
+            let src_location = compiler.frame.location(expect_symbol(argument));
+            compiler.emit(&format!("leaq {}, %rax", src_location));
+        },
+    }
+    compiler.emit("pushq %rax");
+}
+
+fn compile_call(compiler: &mut Compiler, operands: &[Operand], variadic: bool) {
+    // ( label | function pointer ) , { argument , pass location }
+    // A function pointer is kept in r10, which isn't used to pass arguments
+    // Extern functions may be in a shared library, so are called through
+    // the procedure linkage table
+    // Variadic functions are told how many vector registers hold arguments
+    // in al, and f32 arguments passed to them are promoted to f64
+    let target = match &operands[0] {
+        Operand::Label(label) if compiler.is_extern(label) => format!("{}@PLT", label),
+        Operand::Label(label) => String::clone(label),
+        operand => {
+            load_operand(compiler, operand, "10", 8);
+            String::from("*%r10")
+        },
+    };
+    // Structs are passed to C in a different way, which isn't supported
+    let c_function = variadic || match &operands[0] {
+        Operand::Label(label) => compiler.is_extern(label),
+        _ => false,
+    };
+
+    // Each argument is given the next register of its class, and once they
+    // run out, the next slot on the stack
+    let mut integer_index = 0;
+    let mut float_index = 0;
+    let mut registers: Vec<(&Operand, &PassLocation, usize)> = Vec::new();
+    let mut stack: Vec<(&Operand, &PassLocation)> = Vec::new();
+    for pair in operands[1..].chunks(2) {
+        let location = match &pair[1] {
+            Operand::PassLocation(location) => location,
+            _ => panic!("Expected pass location for Call argument"),
+        };
+        if c_function && location.regtype == Regtype::Struct {
+            panic!("Passing structs to C functions not implemented yet, pass a pointer instead");
+        }
+        match location.regtype {
+            Regtype::Float if float_index < FLOAT_ARGUMENT_REGISTERS => {
+                registers.push((&pair[0], location, float_index));
+                float_index += 1;
+            },
+            Regtype::Float => stack.push((&pair[0], location)),
+            _ if integer_index < ARGUMENT_REGISTERS.len() => {
+                registers.push((&pair[0], location, integer_index));
+                integer_index += 1;
+            },
+            _ => stack.push((&pair[0], location)),
+        }
+    }
+
+    // The first slot is pushed last, so it is just above the return address.
+    // The stack is 16 byte aligned at the call, so is padded for an odd
+    // number of slots.
+    let padding = stack.len() % 2 * 8;
+    if padding > 0 {
+        compiler.emit(&format!("subq ${}, %rsp", padding));
+    }
+    for (argument, location) in stack.iter().rev() {
+        compile_push_argument(compiler, argument, location);
+    }
+    for (argument, location, index) in registers {
+        match location.regtype {
+            Regtype::Integer | Regtype::Pointer => load_extended(compiler, argument, ARGUMENT_REGISTERS[index], location),
+            Regtype::Float => load_float_argument(compiler, argument, index, location),
+            // Structs are passed by address, and copied by the function
+            Regtype::Struct => {
+                let src_location = compiler.frame.location(expect_symbol(argument));
+                compiler.emit(&format!("leaq {}, {}", src_location, register(ARGUMENT_REGISTERS[index], 8)));
+            },
+        }
+    }
+    if variadic {
+        compiler.emit(&format!("movl ${}, %eax", float_index));
+    }
+    compiler.emit(&format!("call {}", target));
+    if !stack.is_empty() {
+        compiler.emit(&format!("addq ${}, %rsp", stack.len() * 8 + padding));
+    }
+}
+
+// Set %al to whether the condition holds between two floating point values.
+// Comparisons with NaN are unordered, which only makes != true.
+fn compile_float_compare(compiler: &mut Compiler, condition: &Condition, operands: &[Operand]) {
+    let size = operand_size(operands);
+    load_float(compiler, &operands[0], 0, size);
+    load_float(compiler, &operands[1], 1, size);
+    let compare = format!("ucomi{}", float_suffix(size));
+    match condition {
+        Condition::Equal => {
+            compiler.emit(&format!("{} %xmm1, %xmm0", compare));
+            compiler.emit("sete %al");
+            compiler.emit("setnp %cl");
+            compiler.emit("andb %cl, %al");
+        },
+        Condition::NotEqual => {
+            compiler.emit(&format!("{} %xmm1, %xmm0", compare));
+            compiler.emit("setne %al");
+            compiler.emit("setp %cl");
+            compiler.emit("orb %cl, %al");
+        },
+        // Unordered sets the carry flag, so only above and above or equal
+        // are false for NaN. Less than is checked as greater than with the
+        // operands swapped.
+        Condition::GreaterThan | Condition::Above => {
+            compiler.emit(&format!("{} %xmm1, %xmm0", compare));
+            compiler.emit("seta %al");
+        },
+        Condition::GreaterEqual | Condition::AboveEqual => {
+            compiler.emit(&format!("{} %xmm1, %xmm0", compare));
+            compiler.emit("setae %al");
+        },
+        Condition::LessThan | Condition::Below => {
+            compiler.emit(&format!("{} %xmm0, %xmm1", compare));
+            compiler.emit("seta %al");
+        },
+        Condition::LessEqual | Condition::BelowEqual => {
+            compiler.emit(&format!("{} %xmm0, %xmm1", compare));
+            compiler.emit("setae %al");
+        },
+    }
+}
+
+fn compile_float_alu_op(compiler: &mut Compiler, op: &ALUOp, operands: &[Operand]) {
+    // { operand } , result
+    let result = expect_symbol(operands.last().expect("ALUOp has no operands"));
+    let s = float_suffix(result.size);
+    match op {
+        ALUOp::Add | ALUOp::Sub | ALUOp::Multiply | ALUOp::Divide(_) => {
+            load_float(compiler, &operands[0], 0, result.size);
+            load_float(compiler, &operands[1], 1, result.size);
+            let name = match op {
+                ALUOp::Add => "add",
+                ALUOp::Sub => "sub",
+                ALUOp::Multiply => "mul",
+                _ => "div",
+            };
+            compiler.emit(&format!("{}{} %xmm1, %xmm0", name, s));
+        },
+        ALUOp::Negate => {
+            // Flip the sign bit
+            load_float(compiler, &operands[0], 0, result.size);
+            load_float(compiler, &Operand::Constant(Constant::Float(-0.0)), 1, result.size);
+            compiler.emit("xorps %xmm1, %xmm0");
+        },
+        ALUOp::Compare(_) => panic!("Floating point comparisons give an integer"),
+        _ => panic!("{} can't be applied to floating point numbers", op),
+    }
+    store_float(compiler, 0, result);
+}
+
+// Floating point values are compared or calculated with SSE instructions
+fn is_float_op(op: &ALUOp, operands: &[Operand]) -> bool {
+    match op {
+        ALUOp::Compare(_) => operand_regtype(operands) == Regtype::Float,
+        _ => expect_symbol(operands.last().expect("ALUOp has no operands")).regtype == Regtype::Float,
+    }
+}
+
+fn compile_alu_op(compiler: &mut Compiler, op: &ALUOp, operands: &[Operand]) {
+    // { operand } , result
+    let result = expect_symbol(operands.last().expect("ALUOp has no operands"));
+    let s = suffix(result.size);
+    if is_float_op(op, operands) {
+        if let ALUOp::Compare(condition) = op {
+            compile_float_compare(compiler, condition, operands);
+            if result.size > 1 {
+                compiler.emit(&format!("movzb{} %al, {}", s, register("ax", result.size)));
+            }
+            store_symbol(compiler, "ax", result);
+        } else {
+            compile_float_alu_op(compiler, op, operands);
+        }
+        return;
+    }
+    // Narrow arithmetic is done on 32 bit registers. The low bits of the
+    // result are the same, and only they are stored, so it wraps at the
+    // width of the result.
+    let width = result.size.max(4);
+    match op {
+        ALUOp::Add | ALUOp::Sub | ALUOp::Multiply => {
+            load_operand(compiler, &operands[0], "ax", result.size);
+            load_operand(compiler, &operands[1], "cx", result.size);
+            let name = match op {
+                ALUOp::Add => "add",
+                ALUOp::Sub => "sub",
+                _ => "imul",
+            };
+            compiler.emit(&format!("{}{} {}, {}", name, suffix(width), register("cx", width), register("ax", width)));
+        },
+        ALUOp::Divide(signed) | ALUOp::Modulo(signed) => {
+            load_integer(compiler, &operands[0], "ax", result.size, *signed);
+            load_integer(compiler, &operands[1], "cx", result.size, *signed);
+            compile_divide(compiler, op, width, *signed);
+        },
+        ALUOp::ShiftLeft | ALUOp::ShiftRight(_) => {
+            let (name, signed) = match op {
+                ALUOp::ShiftLeft => ("shl", false),
+                ALUOp::ShiftRight(true) => ("sar", true),
+                _ => ("shr", false),
+            };
+            load_integer(compiler, &operands[0], "ax", result.size, signed);
+            load_operand(compiler, &operands[1], "cx", result.size);
+            // The amount is taken modulo the width of the value, which the
+            // hardware only does for 32 and 64 bits
+            compiler.emit(&format!("andl ${}, %ecx", result.size * 8 - 1));
+            compiler.emit(&format!("{}{} %cl, {}", name, suffix(width), register("ax", width)));
+        },
+        ALUOp::And | ALUOp::Or => {
+            load_operand(compiler, &operands[0], "ax", result.size);
+            load_operand(compiler, &operands[1], "cx", result.size);
+            let name = match op {
+                ALUOp::And => "and",
+                _ => "or",
+            };
+            compiler.emit(&format!("{}{} {}, {}", name, suffix(width), register("cx", width), register("ax", width)));
+        },
+        ALUOp::Negate => {
+            load_operand(compiler, &operands[0], "ax", result.size);
+            compiler.emit(&format!("neg{} {}", suffix(width), register("ax", width)));
+        },
+        ALUOp::Compare(condition) => {
+            // Operands can be a different size to the result
+            let size = operand_size(operands);
+            load_operand(compiler, &operands[0], "ax", size);
+            load_operand(compiler, &operands[1], "cx", size);
+            compiler.emit(&format!("cmp{} {}, {}", suffix(size), register("cx", size), register("ax", size)));
+            compiler.emit(&format!("set{} %al", condition_code(condition)));
+            if result.size > 1 {
+                compiler.emit(&format!("movzb{} %al, {}", s, register("ax", result.size)));
+            }
+        },
+    }
+    store_symbol(compiler, "ax", result);
+}
+
+// Divide rax by rcx, leaving the quotient or the remainder in rax
+fn compile_divide(compiler: &mut Compiler, op: &ALUOp, width: usize, signed: bool) {
+    if signed {
+        compiler.emit(if width == 8 { "cqto" } else { "cltd" });
+        compiler.emit(&format!("idiv{} {}", suffix(width), register("cx", width)));
+    } else {
+        compiler.emit("xorl %edx, %edx");
+        compiler.emit(&format!("div{} {}", suffix(width), register("cx", width)));
+    }
+    if let ALUOp::Modulo(_) = op {
+        compiler.emit(&format!("mov{} {}, {}", suffix(width), register("dx", width), register("ax", width)));
+    }
+}
+
+// Integer arithmetic that calls the panic routine if the result doesn't fit
+// in its datatype. 32 and 64 bit results overflow if the flags say so.
+// Narrow operands are extended to 32 bits, where the result is exact, so it
+// overflows if it changes when extended from its own width.
+fn compile_checked_alu_op(compiler: &mut Compiler, op: &ALUOp, signed: bool, operands: &[Operand]) {
+    // { operand } , result , message label , message length
+    let count = operands.len() - 2;
+    let result = expect_symbol(&operands[count - 1]);
+    let size = result.size;
+    let width = size.max(4);
+    let s = suffix(width);
+    load_integer(compiler, &operands[0], "ax", size, signed);
+    match op {
+        ALUOp::Add | ALUOp::Sub => {
+            load_integer(compiler, &operands[1], "cx", size, signed);
+            let name = match op {
+                ALUOp::Add => "add",
+                _ => "sub",
+            };
+            compiler.emit(&format!("{}{} {}, {}", name, s, register("cx", width), register("ax", width)));
+        },
+        ALUOp::Multiply => {
+            load_integer(compiler, &operands[1], "cx", size, signed);
+            // Unsigned multiplication sets the carry flag if the high half
+            // of the result in rdx isn't zero
+            if signed || size < 4 {
+                compiler.emit(&format!("imul{} {}, {}", s, register("cx", width), register("ax", width)));
+            } else {
+                compiler.emit(&format!("mul{} {}", s, register("cx", width)));
+            }
+        },
+        // Negating any unsigned value but zero sets the carry flag
+        ALUOp::Negate => compiler.emit(&format!("neg{} {}", s, register("ax", width))),
+        // Only signed division overflows, and only for the most negative
+        // value divided by -1. At 32 and 64 bits that would trap, so it is
+        // checked before dividing.
+        ALUOp::Divide(true) | ALUOp::Modulo(true) => {
+            load_integer(compiler, &operands[1], "cx", size, signed);
+            if size < 4 {
+                compile_divide(compiler, op, width, signed);
+            } else {
+                let divide = format!(".LO{}", compiler.overflow_checks);
+                compiler.overflow_checks += 1;
+                compiler.emit(&format!("cmp{} $-1, {}", s, register("cx", width)));
+                compiler.emit(&format!("jne {}", divide));
+                load_operand(compiler, &Operand::Constant(Constant::Int(i64::MIN >> (64 - size * 8))), "dx", size);
+                compiler.emit(&format!("cmp{} {}, {}", s, register("dx", width), register("ax", width)));
+                compiler.emit(&format!("jne {}", divide));
+                compile_panic(compiler, &operands[count..]);
+                compiler.body.push_str(&divide);
+                compiler.body.push_str(":\n");
+                compile_divide(compiler, op, width, signed);
+                store_symbol(compiler, "ax", result);
+                return;
+            }
+        },
+        _ => panic!("{} can't be checked for overflow", op),
+    }
+
+    let end = format!(".LO{}", compiler.overflow_checks);
+    compiler.overflow_checks += 1;
+    if size < 4 {
+        let extend = if signed { "movs" } else { "movz" };
+        compiler.emit(&format!("{}{}l {}, %ecx", extend, suffix(size), register("ax", size)));
+        compiler.emit("cmpl %ecx, %eax");
+        compiler.emit(&format!("je {}", end));
+    } else if signed {
+        compiler.emit(&format!("jno {}", end));
+    } else {
+        compiler.emit(&format!("jnc {}", end));
+    }
+    compile_panic(compiler, &operands[count..]);
+    compiler.body.push_str(&end);
+    compiler.body.push_str(":\n");
+    store_symbol(compiler, "ax", result);
+}
+
+fn compile_panic(compiler: &mut Compiler, operands: &[Operand]) {
+    // message label , message length
+    let message = expect_label(&operands[0]);
+    let length = match &operands[1] {
+        Operand::Integer(length) => *length,
+        operand => panic!("Expected message length, found {}", operand),
+    };
+    compiler.emit(&format!("leaq {}(%rip), %rdi", message));
+    compiler.emit(&format!("movq ${}, %rsi", length));
+    compiler.emit("call __panic");
+    compiler.panic = true;
+}
+
+// Linux takes the system call number in rax, and returns the result there
+fn compile_syscall(compiler: &mut Compiler, operands: &[Operand]) {
+    // number , { argument } , result
+    let (result, rest) = operands.split_last().unwrap();
+    let result = expect_symbol(result);
+    for (operand, reg) in rest[1..].iter().zip(SYSCALL_REGISTERS.iter()) {
+        load_operand(compiler, operand, reg, 8);
+    }
+    load_operand(compiler, &rest[0], "ax", 8);
+    compiler.emit("syscall");
+    store_symbol(compiler, "ax", result);
+}
+
+// Entry point of the program. The kernel starts it with argc on top of the
+// stack, followed by the argv pointers. The stack is aligned for the call,
+// then the returned status is passed to the exit system call.
+fn compile_start(compiler: &mut Compiler, operands: &[Operand]) {
+    // label , [ pass location ]
+    let main = expect_label(&operands[0]);
+    let start = &mut compiler.start;
+    writeln!(start, "    .globl _start").unwrap();
+    writeln!(start, "_start:").unwrap();
+    writeln!(start, "    xorl %ebp, %ebp").unwrap();
+    writeln!(start, "    movl (%rsp), %edi").unwrap();
+    writeln!(start, "    leaq 8(%rsp), %rsi").unwrap();
+    writeln!(start, "    andq $-16, %rsp").unwrap();
+    writeln!(start, "    call {}", main).unwrap();
+    match operands.get(1) {
+        Some(Operand::PassLocation(_)) => writeln!(start, "    movl %eax, %edi").unwrap(),
+        Some(operand) => panic!("Expected pass location for Start, found {}", operand),
+        None => writeln!(start, "    xorl %edi, %edi").unwrap(),
+    }
+    writeln!(start, "    movq $60, %rax").unwrap();
+    writeln!(start, "    syscall").unwrap();
+}
+
+// Size of the values compared by lhs , rhs. Constants take the size of the
+// other operand.
+fn operand_size(operands: &[Operand]) -> usize {
+    match (&operands[0], &operands[1]) {
+        (Operand::Symbol(symbol), _) => symbol.size,
+        (_, Operand::Symbol(symbol)) => symbol.size,
+        _ => 8,
+    }
+}
+
+// Register class of the values compared by lhs , rhs
+fn operand_regtype(operands: &[Operand]) -> Regtype {
+    match (&operands[0], &operands[1]) {
+        (Operand::Symbol(symbol), _) => symbol.regtype,
+        (_, Operand::Symbol(symbol)) => symbol.regtype,
+        (Operand::Constant(Constant::Float(_)), _) => Regtype::Float,
+        _ => Regtype::Integer,
+    }
+}
+
+// Suffix of the jcc and setcc instructions for a condition
+fn condition_code(condition: &Condition) -> &'static str {
+    match condition {
+        Condition::Equal => "e",
+        Condition::NotEqual => "ne",
+        Condition::LessThan => "l",
+        Condition::LessEqual => "le",
+        Condition::GreaterThan => "g",
+        Condition::GreaterEqual => "ge",
+        Condition::Below => "b",
+        Condition::BelowEqual => "be",
+        Condition::Above => "a",
+        Condition::AboveEqual => "ae",
+    }
+}
+
+fn compile_branch(compiler: &mut Compiler, condition: &Condition, operands: &[Operand]) {
+    // lhs , rhs , label
+    if operand_regtype(operands) == Regtype::Float {
+        compile_float_compare(compiler, condition, operands);
+        compiler.emit("testb %al, %al");
+        compiler.emit(&format!("jnz {}", expect_label(&operands[2])));
+        return;
+    }
+    let size = operand_size(operands);
+    load_operand(compiler, &operands[0], "ax", size);
+    load_operand(compiler, &operands[1], "cx", size);
+    compiler.emit(&format!("cmp{} {}, {}", suffix(size), register("cx", size), register("ax", size)));
+    compiler.emit(&format!("j{} {}", condition_code(condition), expect_label(&operands[2])));
+}
+
+fn compile_label(compiler: &mut Compiler, operands: &[Operand]) {
+    let label = expect_label(&operands[0]);
+    // Local labels (within a function) start with ".", any other label
+    // starts a new function.
+    if label.starts_with('.') {
+        compiler.body.push_str(label);
+        compiler.body.push_str(":\n");
+    } else {
+        compiler.start_function(label);
+    }
+}
+
+pub fn compile_instruction(compiler: &mut Compiler, instruction: &Instruction, operands: &[Operand]) {
+    match instruction {
+        Instruction::Move => compile_move(compiler, operands),
+        Instruction::Convert(signed) => compile_convert(compiler, *signed, operands),
+        Instruction::Load => compile_load(compiler, operands),
+        Instruction::Store => compile_store(compiler, operands),
+        Instruction::Address => compile_address(compiler, operands),
+        Instruction::GetArgument => compile_get_argument(compiler, operands),
+        Instruction::SetReturned => compile_set_returned(compiler, operands),
+        Instruction::GetReturned => compile_get_returned(compiler, operands),
+        Instruction::ALUOp(op) => compile_alu_op(compiler, op, operands),
+        Instruction::CheckedALUOp(op, signed) => compile_checked_alu_op(compiler, op, *signed, operands),
+        Instruction::Jump => {
+            let label = expect_label(&operands[0]);
+            compiler.emit(&format!("jmp {}", label));
+        },
+        Instruction::Branch(condition) => compile_branch(compiler, condition, operands),
+        Instruction::Call => compile_call(compiler, operands, false),
+        Instruction::VariadicCall => compile_call(compiler, operands, true),
+        Instruction::Return => {
+            compiler.emit("leave");
+            compiler.emit("ret");
+        },
+        Instruction::Label => compile_label(compiler, operands),
+        Instruction::Static => compile_static(compiler, operands),
+        Instruction::ReadOnly => compile_read_only(compiler, operands),
+        Instruction::Panic => compile_panic(compiler, operands),
+        Instruction::Start => compile_start(compiler, operands),
+        Instruction::Syscall => compile_syscall(compiler, operands),
+        // Extern labels are found before compiling, as they can be used
+        // before being declared
+        Instruction::Extern => (),
+    }
+}
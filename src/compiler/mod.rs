@@ -1,15 +1,49 @@
+mod compiler;
+mod instruction;
+mod data;
 
-// mod symbol;
-// mod datatype;
-// mod compiler;
-// mod program;
+use crate::generator::instructions::*;
+use compiler::Compiler;
+use instruction::compile_instruction;
 
-use crate::parser::ast::Ast;
-// use compiler::Compiler;
+// Compiles instructions to x86-64 assembly (AT&T syntax)
+pub fn compile_instructions(instructions: &Vec<Element>) -> String {
+    let mut compiler = Compiler::new();
 
-pub fn compile_ast(ast: &Ast) -> String {
-    let mut code = String::new();
-    // let mut compiler = Compiler::new(ast, &mut code);
-    // program::compile_program(&mut compiler);
-    code
+    // Extern labels are called differently, so are found first
+    let mut elements = instructions.iter();
+    while let Some(element) = elements.next() {
+        if let Element::Instruction(Instruction::Extern) = element {
+            match elements.next() {
+                Some(Element::Operand(Operand::Label(label))) => {
+                    if !compiler.is_extern(label) {
+                        compiler.externs.push(String::clone(label));
+                    }
+                },
+                _ => panic!("Expected label operand for Extern"),
+            }
+        }
+    }
+
+    // Each instruction is followed by its operands
+    let mut current: Option<Instruction> = None;
+    let mut operands: Vec<Operand> = Vec::new();
+    for element in instructions {
+        match element {
+            Element::Instruction(instruction) => {
+                if let Some(prev) = current {
+                    compile_instruction(&mut compiler, &prev, &operands);
+                }
+                current = Some(*instruction);
+                operands.clear();
+            },
+            Element::Operand(operand) => operands.push(Operand::clone(operand)),
+            Element::Blank => (),
+        }
+    }
+    if let Some(prev) = current {
+        compile_instruction(&mut compiler, &prev, &operands);
+    }
+
+    return compiler.output();
 }
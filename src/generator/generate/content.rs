@@ -4,14 +4,21 @@ use super::generator::Generator;
 
 use super::function::generate_function;
 use super::statement::generate_statement;
+use super::variable::generate_variable;
+use super::structure::generate_structure;
 
 
 pub fn generate_content(generator: &mut Generator) {
-    generator.down();
+    if !generator.down() {
+        // Empty body
+        return;
+    }
     loop {
         match generator.current() {
-            Construct::Function(_) => generate_function(generator),
+            Construct::Function(..) => generate_function(generator),
             Construct::Statement(_) => generate_statement(generator),
+            Construct::Variable(_) => generate_variable(generator),
+            Construct::Structure(..) => generate_structure(generator),
             _ => (),
         }
         if !generator.next() {
@@ -13,17 +13,23 @@ pub fn create_pass_location(generator: &mut Generator, index: usize) -> PassLoca
         index: index,
         size: info.size,
         regtype: info.regtype,
+        signed: info.signed,
     };
     generator.up();
     return pass_location;
 }
 
 pub fn generate_argument_get(generator: &mut Generator, argument: &PassLocation, name: &String) {
+    let symbol_i = generator.find_symbol(name).unwrap();
+    let (_, info) = get_symbol_datatype(generator, symbol_i);
     let symbol = Symbol {
         name: String::clone(name),
         version: 0,
+        declaration: symbol_i,
         size: argument.size,
+        alignment: info.alignment,
         regtype: Regtype::clone(&argument.regtype),
+        global: false,
     };
     generator.add_element(Element::Instruction(Instruction::GetArgument));
     generator.add_element(Element::Operand(Operand::PassLocation(PassLocation::clone(argument))));
@@ -42,7 +48,7 @@ pub fn generate_return_set(generator: &mut Generator, symbol: &Symbol, return_in
     // generator.add_element(Element::Operand(Operand::Symbol(symbol)));
 }
 
-fn skip_qualifiers(generator: &mut Generator) {
+pub fn skip_qualifiers(generator: &mut Generator) {
     loop {
         match generator.current() {
             Construct::Qualifier(_) => {
@@ -58,62 +64,80 @@ fn skip_qualifiers(generator: &mut Generator) {
 #[derive(Clone, Copy)]
 pub struct DatatypeInfo {
     pub size: usize,
+    pub alignment: usize,
     pub regtype: Regtype,
+    pub signed: bool, // Signed integer, which is sign extended when widened
 }
 
 pub fn get_datatype_info(generator: &mut Generator) -> DatatypeInfo {
     // Current node = Datatype
-    let (size, regtype) = match generator.current() {
+    let (size, alignment, regtype, signed) = match generator.current() {
         Construct::Datatype(datatype) => match datatype {
             Datatype::Terminal => {
                 generator.down();
                 skip_qualifiers(generator);
                 let result = match generator.current() {
                     Construct::Primitive(primitive) => match primitive {
-                        Primitive::U8 => (1, Regtype::Integer),
-                        Primitive::U16 => (2, Regtype::Integer),
-                        Primitive::U32 => (4, Regtype::Integer),
-                        Primitive::U64 => (8, Regtype::Integer),
-                        Primitive::I8 => (1, Regtype::Integer),
-                        Primitive::I16 => (2, Regtype::Integer),
-                        Primitive::I32 => (4, Regtype::Integer),
-                        Primitive::I64 => (8, Regtype::Integer),
-                        Primitive::F32 => (4, Regtype::Float),
-                        Primitive::F64 => (8, Regtype::Float),
-                        Primitive::C8 => (1, Regtype::Integer),
+                        Primitive::U8 => (1, 1, Regtype::Integer, false),
+                        Primitive::U16 => (2, 2, Regtype::Integer, false),
+                        Primitive::U32 => (4, 4, Regtype::Integer, false),
+                        Primitive::U64 => (8, 8, Regtype::Integer, false),
+                        Primitive::I8 => (1, 1, Regtype::Integer, true),
+                        Primitive::I16 => (2, 2, Regtype::Integer, true),
+                        Primitive::I32 => (4, 4, Regtype::Integer, true),
+                        Primitive::I64 => (8, 8, Regtype::Integer, true),
+                        Primitive::F32 => (4, 4, Regtype::Float, false),
+                        Primitive::F64 => (8, 8, Regtype::Float, false),
+                        Primitive::C8 => (1, 1, Regtype::Integer, false),
+                        Primitive::Bool => (1, 1, Regtype::Integer, false),
                     },
-                    Construct::Structure(_, size) => (*size, Regtype::Struct),
+                    Construct::Structure(_, size, alignment) => (*size, *alignment, Regtype::Struct, false),
                     Construct::Reference(symbol_i_) => {
                         let symbol_i = *symbol_i_;
                         generator.down_ref(symbol_i);
-                        let size = match generator.current() {
-                            Construct::Structure(_, size) => *size,
+                        let result = match generator.current() {
+                            Construct::Structure(_, size, alignment) => (*size, *alignment, Regtype::Struct, false),
+                            Construct::Alias(_, _) => {
+                                // Distinct alias, stored as the underlying datatype
+                                generator.down();
+                                let info = get_datatype_info(generator);
+                                generator.up();
+                                (info.size, info.alignment, info.regtype, info.signed)
+                            },
+                            Construct::Datatype(_) => {
+                                // Non-distinct alias
+                                let info = get_datatype_info(generator);
+                                (info.size, info.alignment, info.regtype, info.signed)
+                            },
                             _ => panic!(""),
                         };
                         generator.up();
-                        (size, Regtype::Struct)
+                        result
                     },
                     _ => panic!("Invalid child of Datatype in get_datatype_info"),
                 };
                 generator.up();
                 result
             },
-            Datatype::Pointer => (8, Regtype::Pointer),
+            Datatype::Pointer => (8, 8, Regtype::Pointer, false),
+            Datatype::Function => (8, 8, Regtype::Pointer, false),
+            Datatype::Slice => (16, 8, Regtype::Struct, false),
         },
         _ => {
             println!("{}", generator.current());
             panic!("Node at create_pass_location isn't Datatype");
         },
     };
-    return DatatypeInfo { size: size, regtype: regtype };
+    return DatatypeInfo { size: size, alignment: alignment, regtype: regtype, signed: signed };
 }
 
 pub fn get_symbol_datatype(generator: &mut Generator, symbol_i: usize) -> (usize, DatatypeInfo) {
     // Current node = <doesn't matter>
     generator.down_ref(symbol_i);
-    // Current node = Variable
+    // Current node = Variable or Argument
     match generator.current() {
         Construct::Variable(_) => (),
+        Construct::Argument(_) => (),
         _ => panic!(""),
     }
     generator.down();
@@ -126,82 +150,270 @@ pub fn get_symbol_datatype(generator: &mut Generator, symbol_i: usize) -> (usize
     return (node_i, info);
 }
 
-pub fn get_pointer_datatype(generator: &mut Generator, symbol_i: usize) -> usize {
-    // Current node = <doesn't matter>
-    generator.down_ref(symbol_i);
-
-    // Current node = Variable
-    match generator.current() {
-        Construct::Variable(_) => (),
-        _ => panic!(""),
+fn check_mutable(generator: &mut Generator) -> bool {
+    // Current node = datatype
+    // Qualifiers come before anything else, and only apply to this datatype,
+    // not to the datatype a pointer points to.
+    let mut mutable = false;
+    if generator.down() {
+        loop {
+            match generator.current() {
+                Construct::Qualifier(Qualifier::Mut) => mutable = true,
+                _ => break,
+            }
+            if !generator.next() {
+                break;
+            }
+        }
+        generator.up();
     }
-    generator.down();
+    return mutable;
+}
 
-    // Current node = datatype, expect Datatype::Pointer
-    match generator.current() {
-        Construct::Datatype(datatype) => match datatype {
-            Datatype::Pointer => (),
-            _ => panic!(""),
-        },
-        _ => panic!(""),
-    }
-    generator.down();
+pub fn is_mutable(generator: &mut Generator, datatype_i: usize) -> bool {
+    generator.down_ref(datatype_i);
+    let mutable = check_mutable(generator);
+    generator.up();
+    return mutable;
+}
 
-    // Current level = { qualifier } , datatype
-    // Ignore qualifiers, not relevant
-    loop {
-        match generator.current() {
-            Construct::Datatype(_) => break,
-            _ => (),
-        }
-        if !generator.next() {
-            panic!("Pointer node didn't have datatype child");
+// Structure node that a datatype refers to, if it is a struct
+pub fn find_datatype_structure(generator: &mut Generator, datatype_i: usize) -> Option<usize> {
+    let datatype_i = follow_alias(generator, datatype_i);
+    generator.down_ref(datatype_i);
+    let mut ref_i = None;
+    if let Construct::Datatype(Datatype::Terminal) = generator.current() {
+        generator.down();
+        skip_qualifiers(generator);
+        if let Construct::Reference(ref_i_) = generator.current() {
+            ref_i = Some(*ref_i_);
         }
+        generator.up();
     }
-    // Current node = datatype
-    let node_i = generator.get_ref_id();
-
     generator.up();
+
+    let ref_i = match ref_i {
+        Some(ref_i) => ref_i,
+        None => return None,
+    };
+    generator.down_ref(ref_i);
+    let structure_i = match generator.current() {
+        Construct::Structure(..) => Some(ref_i),
+        Construct::Alias(_, _) => {
+            // Distinct alias of a struct
+            generator.down();
+            let datatype_i = generator.get_ref_id();
+            generator.up();
+            find_datatype_structure(generator, datatype_i)
+        },
+        _ => None,
+    };
     generator.up();
+    return structure_i;
+}
+
+// Datatype of the elements of a slice datatype, None if it isn't a slice
+pub fn get_slice_element(generator: &mut Generator, datatype_i: usize) -> Option<usize> {
+    let datatype_i = follow_alias(generator, datatype_i);
+    generator.down_ref(datatype_i);
+    let mut element = None;
+    if let Construct::Datatype(Datatype::Slice) = generator.current() {
+        generator.down();
+        skip_qualifiers(generator);
+        element = Some(generator.get_ref_id());
+        generator.up();
+    }
     generator.up();
+    return element;
+}
 
-    return node_i;
+// Members of a slice:
+//   ptr: pointer to the first element
+//   len: number of elements, u64
+fn find_slice_member(generator: &mut Generator, element_i: usize, member: &String) -> Option<(usize, usize)> {
+    match member.as_str() {
+        "ptr" => {
+            let pointer_i = generator.add_node(&Construct::Datatype(Datatype::Pointer), &[element_i]);
+            Some((0, pointer_i))
+        },
+        "len" => {
+            let primitive_i = generator.add_node(&Construct::Primitive(Primitive::U64), &[]);
+            let len_i = generator.add_node(&Construct::Datatype(Datatype::Terminal), &[primitive_i]);
+            Some((8, len_i))
+        },
+        _ => None,
+    }
 }
 
-fn check_mutable(generator: &mut Generator) -> bool {
-    // Current node = datatype
-    generator.down();
-    let mut mutable = false;
-    loop {
-        match generator.current() {
-            Construct::Qualifier(qualifier) => match qualifier {
-                Qualifier::Mut => {
+// Offset and datatype of a member of the struct that a datatype refers to
+pub fn find_member(generator: &mut Generator, datatype_i: usize, member: &String) -> Option<(usize, usize)> {
+    if let Some(element_i) = get_slice_element(generator, datatype_i) {
+        return find_slice_member(generator, element_i, member);
+    }
+    let structure_i = match find_datatype_structure(generator, datatype_i) {
+        Some(structure_i) => structure_i,
+        None => return None,
+    };
+    generator.down_ref(structure_i);
+    let mut result = None;
+    if generator.down() {
+        loop {
+            if let Construct::Member(name, offset) = generator.current() {
+                if name == member {
+                    let offset = *offset;
+                    generator.down();
+                    result = Some((offset, generator.get_ref_id()));
                     generator.up();
-                    mutable = true;
-                },
-                // _ => (),
-            },
-            _ => (),
+                }
+            }
+            if !generator.next() {
+                break;
+            }
         }
-        if !generator.next() {
-            break;
+        generator.up();
+    }
+    generator.up();
+    return result;
+}
+
+// Number of fields in a datatype, which are the members of structs and
+// slices, counting the fields of members that are structs themselves
+pub fn count_fields(generator: &mut Generator, datatype_i: usize) -> usize {
+    if get_slice_element(generator, datatype_i).is_some() {
+        return 2;
+    }
+    let structure_i = match find_datatype_structure(generator, datatype_i) {
+        Some(structure_i) => structure_i,
+        None => return 1,
+    };
+    let mut members: Vec<usize> = Vec::new();
+    generator.down_ref(structure_i);
+    if generator.down() {
+        loop {
+            if let Construct::Member(..) = generator.current() {
+                generator.down();
+                members.push(generator.get_ref_id());
+                generator.up();
+            }
+            if !generator.next() {
+                break;
+            }
         }
+        generator.up();
     }
     generator.up();
-    return mutable;
+    return members.iter().map(|member_i| count_fields(generator, *member_i)).sum();
 }
 
-fn match_datatype_pointer(generator: &mut Generator, other: usize) -> bool {
-    generator.down_ref(other);
-    let matches = match generator.current() {
-        Construct::Datatype(datatype) => match datatype {
-            Datatype::Pointer => true,
-            _ => false,
-        },
-        _ => panic!(""),
+// Fields of a member, as the index of its first field and how many it has,
+// and the member's datatype
+pub fn find_member_fields(generator: &mut Generator, datatype_i: usize, member: &String) -> Option<(usize, usize, usize)> {
+    if let Some(element_i) = get_slice_element(generator, datatype_i) {
+        return match find_slice_member(generator, element_i, member) {
+            Some((offset, member_datatype_i)) => Some((offset / 8, 1, member_datatype_i)),
+            None => None,
+        };
+    }
+    let structure_i = match find_datatype_structure(generator, datatype_i) {
+        Some(structure_i) => structure_i,
+        None => return None,
     };
+    let mut members: Vec<(String, usize)> = Vec::new();
+    generator.down_ref(structure_i);
+    if generator.down() {
+        loop {
+            if let Construct::Member(name, _) = generator.current() {
+                let name = String::clone(name);
+                generator.down();
+                members.push((name, generator.get_ref_id()));
+                generator.up();
+            }
+            if !generator.next() {
+                break;
+            }
+        }
+        generator.up();
+    }
     generator.up();
-    return matches;
+    let mut start = 0;
+    for (name, member_datatype_i) in members {
+        let count = count_fields(generator, member_datatype_i);
+        if name == *member {
+            return Some((start, count, member_datatype_i));
+        }
+        start += count;
+    }
+    return None;
+}
+
+// Function declared in the struct a datatype refers to, if it has one
+pub fn find_method(generator: &mut Generator, datatype_i: usize, method: &str) -> Option<usize> {
+    let structure_i = match find_datatype_structure(generator, datatype_i) {
+        Some(structure_i) => structure_i,
+        None => return None,
+    };
+    generator.down_ref(structure_i);
+    let mut function_i = None;
+    if generator.down() {
+        loop {
+            if let Construct::Function(name, _) = generator.current() {
+                if name == method {
+                    function_i = Some(generator.get_ref_id());
+                }
+            }
+            if !generator.next() {
+                break;
+            }
+        }
+        generator.up();
+    }
+    generator.up();
+    return function_i;
+}
+
+// Datatype that a pointer datatype points to, None if it isn't a pointer
+pub fn get_pointee(generator: &mut Generator, datatype_i: usize) -> Option<usize> {
+    let datatype_i = follow_alias(generator, datatype_i);
+    generator.down_ref(datatype_i);
+    let mut pointee = None;
+    if let Construct::Datatype(Datatype::Pointer) = generator.current() {
+        generator.down();
+        skip_qualifiers(generator);
+        pointee = Some(generator.get_ref_id());
+        generator.up();
+    }
+    generator.up();
+    return pointee;
+}
+
+fn match_datatype_pointer(generator: &mut Generator, pointer: usize, other: usize) -> bool {
+    let pointee = get_pointee(generator, pointer)
+        .expect("Pointer datatype has no pointee");
+    let other_pointee = match get_pointee(generator, other) {
+        Some(other_pointee) => other_pointee,
+        None => return false,
+    };
+    // Pointees must match exactly, since there's no implicit casting, and a
+    // pointer to mut can't be made from a pointer to const, at any level.
+    if is_mutable(generator, pointee) && !is_mutable(generator, other_pointee) {
+        return false;
+    }
+    return validate_datatypes(generator, pointee, other_pointee, false, false);
+}
+
+fn match_datatype_slice(generator: &mut Generator, slice: usize, other: usize) -> bool {
+    let element = get_slice_element(generator, slice)
+        .expect("Slice datatype has no element datatype");
+    let other_element = match get_slice_element(generator, other) {
+        Some(other_element) => other_element,
+        None => return false,
+    };
+    // Elements are accessed in place, so their datatypes must match exactly,
+    // and mut elements can't be made from const elements.
+    if is_mutable(generator, element) && !is_mutable(generator, other_element) {
+        return false;
+    }
+    return validate_datatypes(generator, element, other_element, false, false);
 }
 
 fn match_datatype_reference(generator: &mut Generator, ref_i: usize, other: usize) -> bool {
@@ -250,47 +462,168 @@ fn match_datatype_primitive(generator: &mut Generator, primitive: Primitive, oth
     return matches;
 }
 
-pub fn validate_datatypes(generator: &mut Generator, lhs: usize, rhs: usize, lhs_mutable: bool, rhs_mutable: bool) -> bool {
+// Argument and return datatypes of a function, or of a function datatype.
+// None if the node is neither.
+pub fn get_signature(generator: &mut Generator, node_i: usize) -> Option<(Vec<usize>, Vec<usize>)> {
+    let node_i = follow_alias(generator, node_i);
+    generator.down_ref(node_i);
+    let is_function = match generator.current() {
+        Construct::Function(..) => true,
+        Construct::Datatype(Datatype::Function) => true,
+        _ => false,
+    };
+    if !is_function {
+        generator.up();
+        return None;
+    }
+    let mut arguments: Vec<usize> = Vec::new();
+    let mut returns: Vec<usize> = Vec::new();
+    if generator.down() {
+        loop {
+            match generator.current() {
+                Construct::Datatype(_) => arguments.push(generator.get_ref_id()),
+                Construct::Argument(_) => {
+                    generator.down();
+                    arguments.push(generator.get_ref_id());
+                    generator.up();
+                },
+                Construct::Returned => {
+                    generator.down();
+                    returns.push(generator.get_ref_id());
+                    generator.up();
+                },
+                _ => (),
+            }
+            if !generator.next() {
+                break;
+            }
+        }
+        generator.up();
+    }
+    generator.up();
+    return Some((arguments, returns));
+}
+
+// Whether a function or function datatype takes any number of arguments
+// after the others
+pub fn is_variadic(generator: &mut Generator, node_i: usize) -> bool {
+    let node_i = follow_alias(generator, node_i);
+    generator.down_ref(node_i);
+    let mut variadic = false;
+    if generator.down() {
+        loop {
+            if let Construct::Variadic = generator.current() {
+                variadic = true;
+            }
+            if !generator.next() {
+                break;
+            }
+        }
+        generator.up();
+    }
+    generator.up();
+    return variadic;
+}
+
+fn match_datatype_function(generator: &mut Generator, datatype_i: usize, other: usize) -> bool {
+    let (arguments, returns) = get_signature(generator, datatype_i)
+        .expect("Function datatype has no signature");
+    let (other_arguments, other_returns) = match get_signature(generator, other) {
+        Some(signature) => signature,
+        None => return false,
+    };
+    if arguments.len() != other_arguments.len() || returns.len() != other_returns.len() {
+        return false;
+    }
+    if is_variadic(generator, datatype_i) != is_variadic(generator, other) {
+        return false;
+    }
+    // Arguments are passed the other way, from the caller of the datatype to
+    // the function it points to
+    for (lhs, rhs) in arguments.iter().zip(other_arguments.iter()) {
+        if !validate_datatypes(generator, *rhs, *lhs, false, false) {
+            return false;
+        }
+    }
+    for (lhs, rhs) in returns.iter().zip(other_returns.iter()) {
+        if !validate_datatypes(generator, *lhs, *rhs, false, false) {
+            return false;
+        }
+    }
+    return true;
+}
+
+// Follows non-distinct aliases to the datatype they stand for.
+// Distinct aliases are left as a reference to the alias node, so they only
+// match themselves.
+pub fn follow_alias(generator: &mut Generator, datatype_i: usize) -> usize {
+    generator.down_ref(datatype_i);
+    let mut target = None;
+    if let Construct::Datatype(Datatype::Terminal) = generator.current() {
+        generator.down();
+        skip_qualifiers(generator);
+        if let Construct::Reference(ref_i) = generator.current() {
+            let ref_i = *ref_i;
+            generator.down_ref(ref_i);
+            if let Construct::Datatype(_) = generator.current() {
+                target = Some(ref_i);
+            }
+            generator.up();
+        }
+        generator.up();
+    }
+    generator.up();
+    match target {
+        Some(ref_i) => follow_alias(generator, ref_i),
+        None => datatype_i,
+    }
+}
+
+pub fn validate_datatypes(generator: &mut Generator, lhs_: usize, rhs_: usize, lhs_mutable: bool, rhs_mutable: bool) -> bool {
+    // Mutability is given by the qualifiers where the datatype is used, so
+    // only compare the aliased datatypes.
+    let lhs = follow_alias(generator, lhs_);
+    let rhs = follow_alias(generator, rhs_);
 
     generator.down_ref(lhs);
-    match generator.current() {
+    let matches = match generator.current() {
         Construct::Datatype(datatype) => match datatype {
-            Datatype::Pointer => {
-                if !match_datatype_pointer(generator, rhs) {
-                    return false;
-                }
-            },
+            Datatype::Pointer => match_datatype_pointer(generator, lhs, rhs),
+            Datatype::Function => match_datatype_function(generator, lhs, rhs),
+            Datatype::Slice => match_datatype_slice(generator, lhs, rhs),
             Datatype::Terminal => {
                 generator.down();
                 skip_qualifiers(generator);
-                match generator.current() {
+                let matches = match generator.current() {
                     Construct::Reference(node_i_) => {
                         let node_i = *node_i_;
-                        if !match_datatype_reference(generator, node_i, rhs) {
-                            return false;
-                        }
+                        match_datatype_reference(generator, node_i, rhs)
                     },
                     Construct::Primitive(primitive_) => {
                         let primitive = Primitive::clone(primitive_);
-                        if !match_datatype_primitive(generator, primitive, rhs) {
-                            return false;
-                        }
+                        match_datatype_primitive(generator, primitive, rhs)
                     },
-                    _ => return false,
-                }
+                    _ => false,
+                };
+                generator.up();
+                matches
             }
         },
         _ => panic!(""),
-    }
+    };
     generator.up();
+    if !matches {
+        return false;
+    }
 
-    generator.down_ref(lhs);
+    generator.down_ref(lhs_);
     if lhs_mutable && !check_mutable(generator) {
         generator.up();
         return false;
     }
+    generator.up();
 
-    generator.down_ref(rhs);
+    generator.down_ref(rhs_);
     if rhs_mutable && !check_mutable(generator) {
         generator.up();
         return false;
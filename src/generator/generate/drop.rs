@@ -0,0 +1,90 @@
+
+use super::construct::*;
+use super::generator::Generator;
+use super::instructions::*;
+
+use super::datatype::find_method;
+use super::datatype::get_symbol_datatype;
+use super::function::get_function_label;
+use super::expression::new_temp_pointer;
+
+
+// Drop function of the struct a datatype refers to, if it has one
+pub fn find_drop_function(generator: &mut Generator, datatype_i: usize) -> Option<usize> {
+    return find_method(generator, datatype_i, "drop");
+}
+
+// Drop function for a symbol, if it is a variable or argument that needs
+// dropping
+pub fn find_symbol_drop(generator: &mut Generator, name: &String) -> Option<usize> {
+    let symbol_i = match generator.find_symbol(name) {
+        Some(symbol_i) => symbol_i,
+        None => return None,
+    };
+    generator.down_ref(symbol_i);
+    let droppable = match generator.current() {
+        Construct::Variable(_) => true,
+        Construct::Argument(_) => true,
+        _ => false,
+    };
+    generator.up();
+    if !droppable {
+        return None;
+    }
+    let (datatype_i, _) = get_symbol_datatype(generator, symbol_i);
+    return find_drop_function(generator, datatype_i);
+}
+
+// Call the drop function on a variable or argument, given by its node
+pub fn generate_drop(generator: &mut Generator, name: &String, symbol_i: usize) {
+    let (datatype_i, info) = get_symbol_datatype(generator, symbol_i);
+    let function_i = match find_drop_function(generator, datatype_i) {
+        Some(function_i) => function_i,
+        None => panic!("Dropping {}, which has no drop function", name),
+    };
+
+    let symbol = Symbol {
+        name: String::clone(name),
+        version: 0,
+        declaration: symbol_i,
+        size: info.size,
+        alignment: info.alignment,
+        regtype: info.regtype,
+        global: false,
+    };
+    let pointer = new_temp_pointer(generator);
+    let location = PassLocation {
+        index: 0,
+        size: 8,
+        regtype: Regtype::Pointer,
+        signed: false,
+    };
+    generator.add_element(Element::Instruction(Instruction::Address));
+    generator.add_element(Element::Operand(Operand::Symbol(symbol)));
+    generator.add_element(Element::Operand(Operand::Symbol(Symbol::clone(&pointer))));
+    let label = get_function_label(generator, function_i);
+    generator.add_element(Element::Instruction(Instruction::Call));
+    generator.add_element(Element::Operand(Operand::Label(label)));
+    generator.add_element(Element::Operand(Operand::Symbol(pointer)));
+    generator.add_element(Element::Operand(Operand::PassLocation(location)));
+}
+
+// Symbol that is moved out of by using the expression as a value. Only a
+// variable that needs dropping is moved, anything else is copied.
+pub fn get_moved_symbol(generator: &mut Generator) -> Option<String> {
+    // Current node = Expression
+    match generator.current() {
+        Construct::Expression(Expression::Identifier) => (),
+        _ => return None,
+    }
+    generator.down();
+    let name = match generator.current() {
+        Construct::Identifier(name) => String::clone(name),
+        _ => panic!("Expression::Identifier has no identifier child"),
+    };
+    generator.up();
+    if find_symbol_drop(generator, &name).is_none() {
+        return None;
+    }
+    return Some(name);
+}
@@ -3,10 +3,35 @@ use super::construct::*;
 use super::generator::Generator;
 use super::instructions::*;
 use super::datatype::get_symbol_datatype;
-use super::datatype::get_pointer_datatype;
+use super::datatype::get_datatype_info;
+use super::datatype::DatatypeInfo;
+use super::datatype::validate_datatypes;
+use super::datatype::get_signature;
+use super::datatype::is_variadic;
+use super::datatype::find_member;
+use super::datatype::find_method;
+use super::datatype::count_fields;
+use super::datatype::find_member_fields;
+use super::datatype::is_mutable;
+use super::datatype::get_pointee;
+use super::datatype::get_slice_element;
+use crate::lexer::token;
+use super::resolve::evaluate_constant;
+use super::resolve::get_datatype_primitive;
+use super::resolve::get_datatype_layout;
+use super::resolve::resolve_datatype;
+use super::drop::get_moved_symbol;
+use super::drop::find_drop_function;
+use super::function::get_function_label;
+use super::function::get_global_label;
 
 
 fn get_symbol_identifier(generator: &mut Generator, lvalue: bool) -> (Symbol, usize) {
+    return get_member_identifier(generator, lvalue, None);
+}
+
+// Symbol accessed through a member, given by fields, see get_member_fields()
+fn get_member_identifier(generator: &mut Generator, lvalue: bool, fields: Option<(u64, u64)>) -> (Symbol, usize) {
     // Current node = Expression::Identifier
     let name = match generator.current() {
         Construct::Identifier(name_) => String::clone(name_),
@@ -14,72 +39,1274 @@ fn get_symbol_identifier(generator: &mut Generator, lvalue: bool) -> (Symbol, us
     };
     let symbol_i = generator.find_symbol(&name).expect("Failed to resolve symbol");
     let (datatype_node_i, datatype_info) = get_symbol_datatype(generator, symbol_i);
-    let version = generator.get_symbol_version(&name, lvalue); // Increment if setting lvalue
+    let version = generator.get_member_version(&name, lvalue, fields); // Increment if setting lvalue
+    if !lvalue && generator.is_symbol_moved(&name) {
+        panic!("Value of {} is used after being moved", name);
+    }
+    // Global variables are stored under their label
+    let global = generator.is_global_symbol(&name);
+    let name = if global { get_global_label(generator, symbol_i) } else { name };
 
     let symbol = Symbol {
-        name: String::clone(&name),
+        name: name,
         version: version,
+        declaration: symbol_i,
         size: datatype_info.size,
+        alignment: datatype_info.alignment,
         regtype: datatype_info.regtype,
+        global: global,
     };
     return (symbol, datatype_node_i);
 }
 
-fn get_lvalue_pointer(generator: &mut Generator) -> (Symbol, usize) {
-    // Current node = Construct::Expression
-    match generator.current() {
-        Construct::Expression(expression) => match expression {
-            Expression::Identifier => {
+fn get_const_identifier(generator: &mut Generator) -> Option<(Constant, usize, DatatypeInfo)> {
+    // Current node = Identifier
+    // Consts are evaluated when resolved, so the expression child is now a
+    // constant and can be used as an immediate.
+    let name = match generator.current() {
+        Construct::Identifier(name_) => String::clone(name_),
+        _ => panic!(""),
+    };
+    let symbol_i = generator.find_symbol(&name).expect("Failed to resolve symbol");
+    generator.down_ref(symbol_i);
+    let result = match generator.current() {
+        Construct::Const(_) => {
+            generator.down();
+            let datatype_i = generator.get_ref_id();
+            let info = get_datatype_info(generator);
+            generator.next();
+            let constant = match generator.current() {
+                Construct::Expression(Expression::Constant(constant)) => get_constant_operand(constant, &info.regtype),
+                _ => panic!("Const {} hasn't been evaluated", name),
+            };
+            generator.up();
+            Some((constant, datatype_i, info))
+        },
+        _ => None,
+    };
+    generator.up();
+    return result;
+}
+
+fn get_function_identifier(generator: &mut Generator) -> Option<(String, usize)> {
+    // Current node = Identifier
+    let name = match generator.current() {
+        Construct::Identifier(name_) => String::clone(name_),
+        _ => panic!(""),
+    };
+    let symbol_i = generator.find_symbol(&name).expect("Failed to resolve symbol");
+    generator.down_ref(symbol_i);
+    let function = match generator.current() {
+        Construct::Function(..) => true,
+        _ => false,
+    };
+    generator.up();
+    if !function {
+        return None;
+    }
+    return Some((get_function_label(generator, symbol_i), symbol_i));
+}
+
+// Value of a pointer expression, and the datatype it points to
+fn get_dereferenced_pointer(generator: &mut Generator) -> (Symbol, usize) {
+    // Current node = Expression
+    // Storing through the pointer only reads it, the pointer is unchanged
+    let (pointer, datatype_i) = generate_expression_rvalue(generator);
+    let pointee_i = match get_pointee(generator, datatype_i) {
+        Some(pointee_i) => pointee_i,
+        None => panic!("Dereferencing a value that isn't a pointer"),
+    };
+    if generator.options.safety_checks {
+        let null = Operand::Constant(Constant::Int(0));
+        generate_check(generator, Condition::NotEqual, Operand::Symbol(Symbol::clone(&pointer)), null, "Dereferencing a null pointer");
+    }
+    return (pointer, pointee_i);
+}
+
+pub fn new_temp(generator: &mut Generator, info: &DatatypeInfo) -> Symbol {
+    return Symbol {
+        name: String::from("__temp"),
+        version: generator.get_temp_version(),
+        declaration: 0,
+        size: info.size,
+        alignment: info.alignment,
+        regtype: info.regtype,
+        global: false,
+    };
+}
+
+pub fn new_temp_pointer(generator: &mut Generator) -> Symbol {
+    return new_temp(generator, &DatatypeInfo { size: 8, alignment: 8, regtype: Regtype::Pointer, signed: false });
+}
+
+fn generate_address(generator: &mut Generator, src: Operand) -> Symbol {
+    let pointer = new_temp_pointer(generator);
+    generator.add_element(Element::Instruction(Instruction::Address));
+    generator.add_element(Element::Operand(src));
+    generator.add_element(Element::Operand(Operand::Symbol(Symbol::clone(&pointer))));
+    return pointer;
+}
+
+fn generate_load(generator: &mut Generator, pointer: &Symbol, datatype_i: usize) -> Symbol {
+    generator.down_ref(datatype_i);
+    let info = get_datatype_info(generator);
+    generator.up();
+    let value = new_temp(generator, &info);
+    generator.add_element(Element::Instruction(Instruction::Load));
+    generator.add_element(Element::Operand(Operand::Symbol(Symbol::clone(pointer))));
+    generator.add_element(Element::Operand(Operand::Symbol(Symbol::clone(&value))));
+    return value;
+}
+
+// Name of the member in an access expression, and whether the member is
+// being called
+fn get_access_member(generator: &mut Generator) -> (String, bool) {
+    // Current node = Expression::BinaryOp(Access)
+    // Children: expression , ( Expression::Identifier | Expression::Function )
+    generator.down();
+    generator.next();
+    let call = match generator.current() {
+        Construct::Expression(Expression::Identifier) => false,
+        Construct::Expression(Expression::Function) => true,
+        _ => panic!("Expected member name after '.'"),
+    };
+    generator.down();
+    let member = match generator.current() {
+        Construct::Identifier(name) => String::clone(name),
+        _ => panic!("Expected member name after '.'"),
+    };
+    generator.up();
+    generator.up();
+    return (member, call);
+}
+
+// Pointer to a member of the struct pointed to
+fn get_member_pointer(generator: &mut Generator, pointer: &Symbol, datatype_i: usize, member: &String) -> (Symbol, usize) {
+    let (offset, member_datatype_i) = match find_member(generator, datatype_i, member) {
+        Some(result) => result,
+        None => panic!("Value doesn't have a member {}", member),
+    };
+    if offset == 0 {
+        return (Symbol::clone(pointer), member_datatype_i);
+    }
+    let member_pointer = new_temp_pointer(generator);
+    generator.add_element(Element::Instruction(Instruction::ALUOp(ALUOp::Add)));
+    generator.add_element(Element::Operand(Operand::Symbol(Symbol::clone(pointer))));
+    generator.add_element(Element::Operand(Operand::Constant(Constant::Int(offset as i64))));
+    generator.add_element(Element::Operand(Operand::Symbol(Symbol::clone(&member_pointer))));
+    return (member_pointer, member_datatype_i);
+}
+
+// Fields of a member of a variable, eg: v.a.b, as bits set for each field,
+// with the bits of all the fields of the variable. Members are assigned one
+// at a time by tracking which fields have been written, see count_fields().
+// None if the member isn't of a variable.
+fn get_member_fields(generator: &mut Generator) -> Option<(u64, u64)> {
+    // Current node = Expression::BinaryOp(Access)
+    let mut members: Vec<String> = Vec::new();
+    let mut depth = 0;
+    let name = loop {
+        match generator.current() {
+            Construct::Expression(Expression::BinaryOp(BinaryOp::Access)) => {
+                let (member, call) = get_access_member(generator);
+                if call {
+                    break None;
+                }
+                members.push(member);
+                generator.down();
+                depth += 1;
+            },
+            Construct::Expression(Expression::Identifier) => {
                 generator.down();
                 let name = match generator.current() {
-                    Construct::Identifier(name_) => String::clone(name_),
-                    _ => panic!(""),
+                    Construct::Identifier(name) => Some(String::clone(name)),
+                    _ => None,
                 };
-                let symbol_i = generator.find_symbol(&name).expect("Failed to resolve symbol");
-                let datatype_node_i = get_pointer_datatype(generator, symbol_i);
-                let version = generator.get_symbol_version(&name, true);
                 generator.up();
-                let symbol = Symbol {
-                    name: String::clone(&name),
-                    version: version,
-                    size: 8,
-                    regtype: Regtype::Pointer,
-                };
-                return (symbol, datatype_node_i);
+                break name;
             },
-            _ => panic!("Dereferencing expressions not implemented yet"),
+            _ => break None,
+        }
+    };
+    for _ in 0..depth {
+        generator.up();
+    }
+    let name = match name {
+        Some(name) => name,
+        None => return None,
+    };
+    let symbol_i = match generator.find_symbol(&name) {
+        Some(symbol_i) => symbol_i,
+        None => return None,
+    };
+    generator.down_ref(symbol_i);
+    let variable = match generator.current() {
+        Construct::Variable(_) => true,
+        Construct::Argument(_) => true,
+        _ => false,
+    };
+    generator.up();
+    if !variable {
+        return None;
+    }
+
+    let (mut datatype_i, _) = get_symbol_datatype(generator, symbol_i);
+    let total = count_fields(generator, datatype_i);
+    let mut start = 0;
+    let mut count = total;
+    for member in members.iter().rev() {
+        match find_member_fields(generator, datatype_i, member) {
+            Some((member_start, member_count, member_datatype_i)) => {
+                start += member_start;
+                count = member_count;
+                datatype_i = member_datatype_i;
+            },
+            None => return None, // Reported when generating the access
+        }
+    }
+    if total > 64 {
+        if !generator.is_symbol_assigned(&name) {
+            panic!("{} has too many fields to be assigned one member at a time, give it a value first", name);
+        }
+        return None;
+    }
+    let bits = |count: usize| if count == 64 { u64::MAX } else { (1 << count) - 1 };
+    return Some((bits(count) << start, bits(total)));
+}
+
+// Pointer to the value of an expression, with its datatype and whether the
+// value can be assigned to. Variables and their members are used in place,
+// anything else is evaluated into a temporary.
+// Writing to a member only assigns the fields of that member, so a variable
+// counts as assigned once all of its members have been written.
+fn get_value_pointer(generator: &mut Generator, write: bool) -> (Symbol, usize, bool) {
+    return get_fields_pointer(generator, write, None);
+}
+
+// Pointer to a value within a member of a variable, given by fields
+fn get_fields_pointer(generator: &mut Generator, write: bool, fields: Option<(u64, u64)>) -> (Symbol, usize, bool) {
+    // Current node = Expression
+    let expression = match generator.current() {
+        Construct::Expression(expression) => Expression::clone(expression),
+        _ => panic!("Node at get_value_pointer() is not an expression"),
+    };
+    match expression {
+        Expression::Identifier => {
+            generator.down();
+            let name = match generator.current() {
+                Construct::Identifier(name) => String::clone(name),
+                _ => panic!(""),
+            };
+            let variable = match generator.find_symbol(&name) {
+                Some(symbol_i) => {
+                    generator.down_ref(symbol_i);
+                    let variable = match generator.current() {
+                        Construct::Variable(_) => true,
+                        Construct::Argument(_) => true,
+                        _ => false,
+                    };
+                    generator.up();
+                    variable
+                },
+                None => panic!("Failed to resolve symbol {}", name),
+            };
+            // Variables have a stack slot, so their address can always be
+            // taken
+            if variable {
+                let moved = write && generator.is_symbol_moved(&name);
+                if moved && generator.is_symbol_assigned(&name) {
+                    panic!("Assigning to a member of {} after it has been moved", name);
+                }
+                let (symbol, datatype_i) = get_member_identifier(generator, write, fields);
+                // Writing every member gives the variable a value, which needs dropping
+                if moved && generator.is_symbol_assigned(&name) {
+                    generator.set_symbol_moved(&name, false);
+                }
+                generator.up();
+                let mutable = is_mutable(generator, datatype_i);
+                let pointer = generate_address(generator, Operand::Symbol(symbol));
+                return (pointer, datatype_i, mutable);
+            }
+            generator.up();
         },
-        _ => panic!(""),
+        Expression::BinaryOp(BinaryOp::Access) => {
+            let (member, call) = get_access_member(generator);
+            if !call {
+                let fields = match fields {
+                    Some(fields) => Some(fields),
+                    None => get_member_fields(generator),
+                };
+                generator.down();
+                let (pointer, datatype_i, mutable) = get_fields_pointer(generator, write, fields);
+                generator.up();
+                let (pointer, datatype_i) = get_member_pointer(generator, &pointer, datatype_i, &member);
+                return (pointer, datatype_i, mutable);
+            }
+        },
+        Expression::BinaryOp(BinaryOp::Index) => return get_element_pointer(generator),
+        Expression::UnaryOp(UnaryOp::Deref) => {
+            // The pointer is the value, mutability is given by what it
+            // points to
+            generator.down();
+            let (pointer, datatype_i) = get_dereferenced_pointer(generator);
+            generator.up();
+            let mutable = is_mutable(generator, datatype_i);
+            return (pointer, datatype_i, mutable);
+        },
+        _ => (),
     }
+    let (symbol, datatype_i) = generate_expression_rvalue(generator);
+    let pointer = generate_address(generator, Operand::Symbol(symbol));
+    return (pointer, datatype_i, false);
 }
 
-pub fn generate_expression_lvalue(generator: &mut Generator) -> (Instruction, Symbol, usize) {
+pub fn generate_expression_lvalue(generator: &mut Generator) -> (Instruction, Symbol, usize, bool) {
     // Current node = Expression
+    // Returns whether the lvalue can be assigned to as well
     match generator.current() {
         Construct::Expression(expression) => match expression {
             Expression::Identifier => {
                 generator.down();
                 // Current node = Construct::Identifier
+                if get_const_identifier(generator).is_some() {
+                    panic!("Can't assign to a const");
+                }
                 let (symbol, datatype_i) = get_symbol_identifier(generator, true);
                 generator.up();
-                return (Instruction::Move, symbol, datatype_i);
+                let mutable = is_mutable(generator, datatype_i);
+                return (Instruction::Move, symbol, datatype_i, mutable);
             },
             Expression::UnaryOp(op) => match op {
                 UnaryOp::Deref => {
                     generator.down();
                     // Current node = Expression
-                    let (symbol, datatype_i) = get_lvalue_pointer(generator);
+                    let (symbol, datatype_i) = get_dereferenced_pointer(generator);
                     generator.up();
-                    return (Instruction::Store, symbol, datatype_i);
+                    let mutable = is_mutable(generator, datatype_i);
+                    return (Instruction::Store, symbol, datatype_i, mutable);
                 },
                 _ => panic!("Invalid lvalue expression"),
             },
+            Expression::BinaryOp(BinaryOp::Access) => {
+                if get_access_member(generator).1 {
+                    panic!("Can't assign to a function call");
+                }
+                let (pointer, datatype_i, mutable) = get_value_pointer(generator, true);
+                return (Instruction::Store, pointer, datatype_i, mutable);
+            },
+            Expression::BinaryOp(BinaryOp::Index) => {
+                let (pointer, datatype_i, mutable) = get_element_pointer(generator);
+                return (Instruction::Store, pointer, datatype_i, mutable);
+            },
             _ => panic!("Invalid lvalue expresion"),
         },
         _ => panic!("Node at generate_expression_lvalue not Expression"),
     }
 }
 
+// Datatype C would give a value with no datatype of its own, eg: literals
+// without a suffix, when passed to a variadic function
+fn get_variadic_datatype(generator: &mut Generator) -> Option<usize> {
+    // Current node = Expression
+    if let Construct::Expression(Expression::Null) = generator.current() {
+        let byte_i = add_primitive_datatype(generator, Primitive::U8);
+        return Some(add_pointer_datatype(generator, byte_i, false));
+    }
+    let primitive = if is_literal_expression(generator) {
+        get_literal_primitive(generator)
+    } else {
+        match generator.current() {
+            Construct::Expression(Expression::Sizeof)
+            | Construct::Expression(Expression::Alignof)
+            | Construct::Expression(Expression::Offsetof) => Some(Primitive::U64),
+            _ => None,
+        }
+    };
+    return primitive.map(|primitive| add_primitive_datatype(generator, primitive));
+}
+
+// Arguments after the named ones take the datatype of their value, and are
+// promoted like in C: f32 is passed as f64, and narrow integers are already
+// extended to 32 bits when passed.
+fn generate_variadic_argument(generator: &mut Generator, index: usize, name: &String) -> (Operand, PassLocation) {
+    // Current node = Expression
+    let (src, datatype_i) = match get_variadic_datatype(generator) {
+        Some(datatype_i) => generate_expression_operand(generator, datatype_i),
+        None => {
+            let (symbol, datatype_i) = generate_expression_rvalue(generator);
+            (Operand::Symbol(symbol), datatype_i)
+        },
+    };
+    generator.down_ref(datatype_i);
+    let info = match generator.current() {
+        // A function used as a value is a pointer to it
+        Construct::Function(..) => DatatypeInfo { size: 8, alignment: 8, regtype: Regtype::Pointer, signed: false },
+        _ => get_datatype_info(generator),
+    };
+    generator.up();
+    let size = match info.regtype {
+        Regtype::Integer | Regtype::Pointer => info.size,
+        Regtype::Float => 8,
+        Regtype::Struct => generator.error(&format!("Argument {} in call to {} is a struct or slice, only primitives and pointers can be variadic arguments", index + 1, name)),
+    };
+    let location = PassLocation {
+        index: index,
+        size: size,
+        regtype: info.regtype,
+        signed: info.signed,
+    };
+    return (src, location);
+}
+
+// A method is called with a pointer to the struct it is called on, given
+// by receiver, before the arguments in the call
+fn generate_call(generator: &mut Generator, callee: Operand, signature_i: usize, name: &String, receiver: Option<(Symbol, usize)>) -> Option<(Symbol, usize)> {
+    // Current node = Expression::Function
+    // Children: identifier , { expression }
+    let (arguments, returns) = match get_signature(generator, signature_i) {
+        Some(signature) => signature,
+        None => panic!("{} is not a function", name),
+    };
+    let variadic = is_variadic(generator, signature_i);
+    // Structs are passed to C in a different way, which isn't supported
+    generator.down_ref(signature_i);
+    let c_function = variadic || match generator.current() {
+        Construct::Function(_, linkage) => *linkage == Linkage::Extern,
+        _ => false,
+    };
+    generator.up();
+
+    let mut operands: Vec<Operand> = vec![callee];
+    let mut count: usize = 0;
+    if let Some((pointer, pointer_datatype_i)) = receiver {
+        if arguments.is_empty() || !validate_datatypes(generator, arguments[0], pointer_datatype_i, false, false) {
+            panic!("First argument of method {} doesn't match a pointer to the value it is called on", name);
+        }
+        operands.push(Operand::Symbol(pointer));
+        operands.push(Operand::PassLocation(PassLocation {
+            index: 0,
+            size: 8,
+            regtype: Regtype::Pointer,
+            signed: false,
+        }));
+        count = 1;
+    }
+    generator.down();
+    while generator.next() {
+        if count >= arguments.len() && variadic {
+            let (src, location) = generate_variadic_argument(generator, count, name);
+            operands.push(src);
+            operands.push(Operand::PassLocation(location));
+            count+=1;
+            continue;
+        }
+        if count == arguments.len() {
+            panic!("Too many arguments in call to {}", name);
+        }
+        let datatype_i = arguments[count];
+        let (src, src_datatype_i) = generate_expression_operand(generator, datatype_i);
+        if !validate_datatypes(generator, datatype_i, src_datatype_i, false, false) {
+            panic!("Datatype of argument {} doesn't match in call to {}", count + 1, name);
+        }
+        generator.down_ref(datatype_i);
+        let info = get_datatype_info(generator);
+        generator.up();
+        if c_function && info.regtype == Regtype::Struct {
+            generator.error(&format!("Argument {} in call to {} is a struct or slice, which can't be passed to C functions yet, pass a pointer instead", count + 1, name));
+        }
+        operands.push(src);
+        operands.push(Operand::PassLocation(PassLocation {
+            index: count,
+            size: info.size,
+            regtype: info.regtype,
+            signed: info.signed,
+        }));
+        count+=1;
+    }
+    generator.up();
+    if count < arguments.len() {
+        panic!("Too few arguments in call to {}", name);
+    }
+
+    let instruction = if variadic {
+        Instruction::VariadicCall
+    } else {
+        Instruction::Call
+    };
+    generator.add_element(Element::Instruction(instruction));
+    for operand in operands {
+        generator.add_element(Element::Operand(operand));
+    }
+
+    if returns.is_empty() {
+        return None;
+    }
+    if returns.len() > 1 {
+        panic!("Calling functions with multiple return values not implemented yet");
+    }
+    let datatype_i = returns[0];
+    generator.down_ref(datatype_i);
+    let info = get_datatype_info(generator);
+    generator.up();
+    let returned = new_temp(generator, &info);
+    generator.add_element(Element::Instruction(Instruction::GetReturned));
+    generator.add_element(Element::Operand(Operand::PassLocation(PassLocation {
+        index: 0,
+        size: info.size,
+        regtype: info.regtype,
+        signed: info.signed,
+    })));
+    generator.add_element(Element::Operand(Operand::Symbol(Symbol::clone(&returned))));
+    return Some((returned, datatype_i));
+}
+
+// Generate a function call, given by a function expression or by accessing a
+// function member. Returns None if the function doesn't return a value.
+pub fn generate_expression_call(generator: &mut Generator) -> Option<(Symbol, usize)> {
+    // Current node = Expression
+    match generator.current() {
+        Construct::Expression(Expression::Function) => {
+            // Children: identifier , { expression }
+            generator.down();
+            let name = match generator.current() {
+                Construct::Identifier(name) => String::clone(name),
+                _ => panic!("Function call has no identifier"),
+            };
+            let symbol_i = match generator.find_symbol(&name) {
+                Some(symbol_i) => symbol_i,
+                None => panic!("Calling unknown function {}", name),
+            };
+            generator.down_ref(symbol_i);
+            let function = match generator.current() {
+                Construct::Function(..) => true,
+                _ => false,
+            };
+            generator.up();
+            // Either call the function directly, or through a function pointer
+            let (callee, signature_i) = if function {
+                (Operand::Label(get_function_label(generator, symbol_i)), symbol_i)
+            } else {
+                let (symbol, datatype_i) = get_symbol_identifier(generator, false);
+                (Operand::Symbol(symbol), datatype_i)
+            };
+            generator.up();
+            return generate_call(generator, callee, signature_i, &name, None);
+        },
+        Construct::Expression(Expression::BinaryOp(BinaryOp::Access)) => {
+            let (member, call) = get_access_member(generator);
+            if !call {
+                panic!("Expression is not a function call");
+            }
+            // Children: expression , Expression::Function
+            generator.down();
+            let (pointer, datatype_i, mutable) = get_value_pointer(generator, false);
+            if get_pointee(generator, datatype_i).is_some() {
+                let value = generate_load(generator, &pointer, datatype_i);
+                generator.next();
+                let result = generate_pointer_method(generator, &value, datatype_i, &member);
+                generator.up();
+                return Some(result);
+            }
+            // Function members are called through their pointer, methods
+            // declared in the struct are called with a pointer to it
+            if find_member(generator, datatype_i, &member).is_none() {
+                if let Some(function_i) = find_method(generator, datatype_i, &member) {
+                    if member == "drop" {
+                        panic!("drop is called when a value goes out of scope, so can't be called directly");
+                    }
+                    let callee = Operand::Label(get_function_label(generator, function_i));
+                    let pointer_datatype_i = add_pointer_datatype(generator, datatype_i, mutable);
+                    generator.next();
+                    let result = generate_call(generator, callee, function_i, &member, Some((pointer, pointer_datatype_i)));
+                    generator.up();
+                    return result;
+                }
+            }
+            let (member_pointer, member_datatype_i) = get_member_pointer(generator, &pointer, datatype_i, &member);
+            let callee = generate_load(generator, &member_pointer, member_datatype_i);
+            generator.next();
+            let result = generate_call(generator, Operand::Symbol(callee), member_datatype_i, &member, None);
+            generator.up();
+            return result;
+        },
+        _ => panic!("Expression is not a function call"),
+    }
+}
+
+// Adds a datatype for a primitive, which isn't in the tree
+fn add_primitive_datatype(generator: &mut Generator, primitive: Primitive) -> usize {
+    let primitive_i = generator.add_node(&Construct::Primitive(primitive), &[]);
+    return generator.add_node(&Construct::Datatype(Datatype::Terminal), &[primitive_i]);
+}
+
+// Methods built in to pointers:
+//   offset(n: i64): pointer n values of the pointee datatype further on
+fn generate_pointer_method(generator: &mut Generator, pointer: &Symbol, datatype_i: usize, method: &String) -> (Symbol, usize) {
+    // Current node = Expression::Function
+    // Children: identifier , { expression }
+    if method != "offset" {
+        panic!("Pointers don't have a method {}", method);
+    }
+    let pointee_i = get_pointee(generator, datatype_i)
+        .expect("Pointer datatype has no pointee");
+    generator.down_ref(pointee_i);
+    let layout = get_datatype_layout(generator);
+    generator.up();
+
+    let count_datatype_i = add_primitive_datatype(generator, Primitive::I64);
+    generator.down();
+    if !generator.next() {
+        panic!("offset takes the number of values to offset by");
+    }
+    let (count, src_datatype_i) = generate_expression_operand(generator, count_datatype_i);
+    if !validate_datatypes(generator, count_datatype_i, src_datatype_i, false, false) {
+        panic!("Offset of a pointer must be an i64");
+    }
+    if generator.next() {
+        panic!("offset only takes the number of values to offset by");
+    }
+    generator.up();
+
+    let result = generate_pointer_offset(generator, pointer, count, layout.size);
+    return (result, datatype_i);
+}
+
+// Pointer count values of the given size after pointer
+fn generate_pointer_offset(generator: &mut Generator, pointer: &Symbol, count: Operand, size: usize) -> Symbol {
+    // Constant offsets are scaled here, anything else at runtime
+    let bytes = match count {
+        Operand::Constant(Constant::Int(count)) => Operand::Constant(Constant::Int(count * size as i64)),
+        _ => {
+            let bytes = new_temp(generator, &DatatypeInfo { size: 8, alignment: 8, regtype: Regtype::Integer, signed: false });
+            generator.add_element(Element::Instruction(Instruction::ALUOp(ALUOp::Multiply)));
+            generator.add_element(Element::Operand(count));
+            generator.add_element(Element::Operand(Operand::Constant(Constant::Int(size as i64))));
+            generator.add_element(Element::Operand(Operand::Symbol(Symbol::clone(&bytes))));
+            Operand::Symbol(bytes)
+        },
+    };
+    let result = new_temp_pointer(generator);
+    generator.add_element(Element::Instruction(Instruction::ALUOp(ALUOp::Add)));
+    generator.add_element(Element::Operand(Operand::Symbol(Symbol::clone(pointer))));
+    generator.add_element(Element::Operand(bytes));
+    generator.add_element(Element::Operand(Operand::Symbol(Symbol::clone(&result))));
+    return result;
+}
+
+// Pointer to an element of a slice, with its datatype and whether it can be
+// assigned to. The index is checked against the length if safety checks
+// are on.
+fn get_element_pointer(generator: &mut Generator) -> (Symbol, usize, bool) {
+    // Current node = Expression::BinaryOp(Index)
+    // Children: expression(slice) , expression(index)
+    // Writing to an element doesn't change the slice, so it is only read
+    generator.down();
+    let (slice, slice_datatype_i, _) = get_value_pointer(generator, false);
+    let element_i = match get_slice_element(generator, slice_datatype_i) {
+        Some(element_i) => element_i,
+        None => panic!("Indexing a value that isn't a slice"),
+    };
+    generator.next();
+    let index_datatype_i = add_primitive_datatype(generator, Primitive::U64);
+    let (index, src_datatype_i) = generate_expression_operand(generator, index_datatype_i);
+    if !validate_datatypes(generator, index_datatype_i, src_datatype_i, false, false) {
+        panic!("Index of a slice must be a u64");
+    }
+    generator.up();
+
+    let (ptr_pointer, ptr_datatype_i) = get_member_pointer(generator, &slice, slice_datatype_i, &String::from("ptr"));
+    let data = generate_load(generator, &ptr_pointer, ptr_datatype_i);
+    if generator.options.safety_checks {
+        let (len_pointer, len_datatype_i) = get_member_pointer(generator, &slice, slice_datatype_i, &String::from("len"));
+        let len = generate_load(generator, &len_pointer, len_datatype_i);
+        generate_check(generator, Condition::Below, Operand::clone(&index), Operand::Symbol(len), "Index out of bounds");
+    }
+
+    generator.down_ref(element_i);
+    let layout = get_datatype_layout(generator);
+    generator.up();
+    let pointer = generate_pointer_offset(generator, &data, index, layout.size);
+    let mutable = is_mutable(generator, element_i);
+    return (pointer, element_i, mutable);
+}
+
+// Condition that holds when comparing lhs with rhs using op. Integers and
+// bools can be compared, and pointers, which are ordered as addresses.
+fn get_condition(generator: &mut Generator, op: &BinaryOp, datatype_i: usize) -> Condition {
+    generator.down_ref(datatype_i);
+    let info = get_datatype_info(generator);
+    let primitive = get_datatype_primitive(generator);
+    generator.up();
+    let signed = match (info.regtype, primitive) {
+        (Regtype::Pointer, _) => false,
+        (Regtype::Float, _) => true,
+        (Regtype::Struct, _) => panic!("Structs can't be compared"),
+        (_, Some(Primitive::Bool)) => match op {
+            BinaryOp::LogicalEquals | BinaryOp::NotEquals => false,
+            _ => panic!("Bools can only be compared with == and !="),
+        },
+        _ => info.signed,
+    };
+    match (op, signed) {
+        (BinaryOp::LogicalEquals, _) => Condition::Equal,
+        (BinaryOp::NotEquals, _) => Condition::NotEqual,
+        (BinaryOp::LessThan, true) => Condition::LessThan,
+        (BinaryOp::LessEquals, true) => Condition::LessEqual,
+        (BinaryOp::GreaterThan, true) => Condition::GreaterThan,
+        (BinaryOp::GreaterEquals, true) => Condition::GreaterEqual,
+        (BinaryOp::LessThan, false) => Condition::Below,
+        (BinaryOp::LessEquals, false) => Condition::BelowEqual,
+        (BinaryOp::GreaterThan, false) => Condition::Above,
+        (BinaryOp::GreaterEquals, false) => Condition::AboveEqual,
+        _ => panic!("{} is not a comparison", op),
+    }
+}
+
+// Comparisons give a bool. A constant or null takes the datatype of the
+// other side.
+fn generate_comparison(generator: &mut Generator, op: &BinaryOp) -> (Symbol, usize) {
+    // Current node = Expression::BinaryOp
+    // Children: expression(lhs) , expression(rhs)
+    generator.down();
+    let mut sides: Vec<usize> = vec![generator.get_ref_id()];
+    generator.next();
+    sides.push(generator.get_ref_id());
+    generator.up();
+    // Evaluating a constant needs the datatype of the other side first, so
+    // swap the sides, which mirrors the comparison
+    generator.down_ref(sides[0]);
+    let swap = match generator.current() {
+        Construct::Expression(Expression::Null) => true,
+        _ => is_literal_expression(generator),
+    };
+    generator.up();
+    // With literals on both sides, the lhs takes the datatype C would give it
+    generator.down_ref(sides[1]);
+    let literals = swap && is_literal_expression(generator);
+    generator.up();
+    let op = if swap {
+        sides.reverse();
+        match op {
+            BinaryOp::LessThan => BinaryOp::GreaterThan,
+            BinaryOp::LessEquals => BinaryOp::GreaterEquals,
+            BinaryOp::GreaterThan => BinaryOp::LessThan,
+            BinaryOp::GreaterEquals => BinaryOp::LessEquals,
+            op => BinaryOp::clone(op),
+        }
+    } else {
+        BinaryOp::clone(op)
+    };
+
+    generator.down_ref(sides[0]);
+    let (lhs, lhs_datatype_i) = if literals {
+        let datatype_i = match get_literal_primitive(generator) {
+            Some(primitive) => add_primitive_datatype(generator, primitive),
+            None => panic!("Literal expression without a datatype"),
+        };
+        generate_constant_temp(generator, datatype_i)
+    } else {
+        generate_expression_rvalue(generator)
+    };
+    generator.up();
+    generator.down_ref(sides[1]);
+    let (rhs, rhs_datatype_i) = generate_expression_operand(generator, lhs_datatype_i);
+    generator.up();
+    // Either side can be the one pointing to mut
+    if !validate_datatypes(generator, lhs_datatype_i, rhs_datatype_i, false, false)
+        && !validate_datatypes(generator, rhs_datatype_i, lhs_datatype_i, false, false) {
+        panic!("Comparing values with different datatypes");
+    }
+    let condition = get_condition(generator, &op, lhs_datatype_i);
+
+    let result = new_temp(generator, &DatatypeInfo { size: 1, alignment: 1, regtype: Regtype::Integer, signed: false });
+    generator.add_element(Element::Instruction(Instruction::ALUOp(ALUOp::Compare(condition))));
+    generator.add_element(Element::Operand(Operand::Symbol(lhs)));
+    generator.add_element(Element::Operand(rhs));
+    generator.add_element(Element::Operand(Operand::Symbol(Symbol::clone(&result))));
+    let datatype_i = add_primitive_datatype(generator, Primitive::Bool);
+    return (result, datatype_i);
+}
+
+// Arithmetic on two numbers of the same datatype. A literal takes the
+// datatype of the other side, so that side is evaluated first.
+fn generate_arithmetic(generator: &mut Generator, op: &BinaryOp) -> (Symbol, usize) {
+    // Current node = Expression::BinaryOp
+    // Children: expression(lhs) , expression(rhs)
+    generator.down();
+    let lhs_i = generator.get_ref_id();
+    let lhs_literal = is_literal_expression(generator);
+    generator.next();
+    let rhs_i = generator.get_ref_id();
+    generator.up();
+
+    let (lhs, lhs_datatype_i, rhs, rhs_datatype_i) = if lhs_literal {
+        generator.down_ref(rhs_i);
+        let (rhs, rhs_datatype_i) = generate_expression_rvalue(generator);
+        generator.up();
+        generator.down_ref(lhs_i);
+        let (lhs, lhs_datatype_i) = generate_expression_operand(generator, rhs_datatype_i);
+        generator.up();
+        (lhs, lhs_datatype_i, Operand::Symbol(rhs), rhs_datatype_i)
+    } else {
+        generator.down_ref(lhs_i);
+        let (lhs, lhs_datatype_i) = generate_expression_rvalue(generator);
+        generator.up();
+        generator.down_ref(rhs_i);
+        let (rhs, rhs_datatype_i) = generate_expression_operand(generator, lhs_datatype_i);
+        generator.up();
+        (Operand::Symbol(lhs), lhs_datatype_i, rhs, rhs_datatype_i)
+    };
+    if !validate_datatypes(generator, lhs_datatype_i, rhs_datatype_i, false, false) {
+        panic!("Datatypes don't match in {}", op);
+    }
+
+    generator.down_ref(lhs_datatype_i);
+    let info = get_datatype_info(generator);
+    let primitive = get_datatype_primitive(generator);
+    generator.up();
+    match (info.regtype, primitive, op) {
+        (Regtype::Struct, _, _) => panic!("{} can't be applied to structs", op),
+        (Regtype::Pointer, _, _) => panic!("{} can't be applied to pointers", op),
+        (_, Some(Primitive::Bool), _) => panic!("{} can't be applied to bools", op),
+        (Regtype::Float, _, BinaryOp::Add | BinaryOp::Subtract | BinaryOp::Multiply | BinaryOp::Divide) => (),
+        (Regtype::Float, _, _) => panic!("{} can't be applied to floating point numbers", op),
+        _ => (),
+    }
+    let alu_op = match op {
+        BinaryOp::Add => ALUOp::Add,
+        BinaryOp::Subtract => ALUOp::Sub,
+        BinaryOp::Multiply => ALUOp::Multiply,
+        BinaryOp::Divide => ALUOp::Divide(info.signed),
+        BinaryOp::Modulo => ALUOp::Modulo(info.signed),
+        BinaryOp::ShiftLeft => ALUOp::ShiftLeft,
+        BinaryOp::ShiftRight => ALUOp::ShiftRight(info.signed),
+        BinaryOp::BitwiseAnd => ALUOp::And,
+        BinaryOp::BitwiseOr => ALUOp::Or,
+        _ => panic!("{} is not an arithmetic operator", op),
+    };
+
+    // Dividing by zero and shifting by the width or more are errors. They
+    // are found here if the rhs is a literal, otherwise only with checks.
+    let bits = info.size as i64 * 8;
+    match (alu_op, &rhs) {
+        (ALUOp::Divide(_) | ALUOp::Modulo(_), Operand::Constant(Constant::Int(0))) if info.regtype == Regtype::Integer => {
+            panic!("Division by zero");
+        },
+        (ALUOp::ShiftLeft | ALUOp::ShiftRight(_), Operand::Constant(Constant::Int(amount))) if *amount < 0 || *amount >= bits => {
+            panic!("Shift amount {} is out of range for a {} bit value", amount, bits);
+        },
+        (_, Operand::Constant(_)) => (),
+        (ALUOp::Divide(_) | ALUOp::Modulo(_), _) if generator.options.overflow_checks && info.regtype == Regtype::Integer => {
+            generate_check(generator, Condition::NotEqual, Operand::clone(&rhs), Operand::Constant(Constant::Int(0)), "Division by zero");
+        },
+        (ALUOp::ShiftLeft | ALUOp::ShiftRight(_), _) if generator.options.overflow_checks => {
+            // A negative amount is above the width when compared unsigned
+            generate_check(generator, Condition::Below, Operand::clone(&rhs), Operand::Constant(Constant::Int(bits)), "Shift amount out of range");
+        },
+        _ => (),
+    }
+
+    let result = new_temp(generator, &info);
+    generate_alu_op(generator, alu_op, &info, &[lhs, rhs], &result);
+    return (result, lhs_datatype_i);
+}
+
+// Integer arithmetic wraps at the width of its datatype, unless overflow
+// checks are on, where overflowing panics with the location in the source.
+fn generate_alu_op(generator: &mut Generator, op: ALUOp, info: &DatatypeInfo, operands: &[Operand], result: &Symbol) {
+    // Current node = expression being calculated
+    let name = match op {
+        ALUOp::Add => Some("Addition"),
+        ALUOp::Sub => Some("Subtraction"),
+        ALUOp::Multiply => Some("Multiplication"),
+        ALUOp::Negate => Some("Negation"),
+        ALUOp::Divide(true) => Some("Division"),
+        ALUOp::Modulo(true) => Some("Remainder"),
+        _ => None,
+    };
+    let name = match name {
+        Some(name) if generator.options.overflow_checks && info.regtype == Regtype::Integer => name,
+        _ => {
+            generator.add_element(Element::Instruction(Instruction::ALUOp(op)));
+            for operand in operands {
+                generator.add_element(Element::Operand(Operand::clone(operand)));
+            }
+            generator.add_element(Element::Operand(Operand::Symbol(Symbol::clone(result))));
+            return;
+        },
+    };
+    let (label, length) = generate_panic_message(generator, &format!("{} overflowed", name));
+    generator.add_element(Element::Instruction(Instruction::CheckedALUOp(op, info.signed)));
+    for operand in operands {
+        generator.add_element(Element::Operand(Operand::clone(operand)));
+    }
+    generator.add_element(Element::Operand(Operand::Symbol(Symbol::clone(result))));
+    generator.add_element(Element::Operand(Operand::Label(label)));
+    generator.add_element(Element::Operand(Operand::Integer(length as i64)));
+}
+
+// Message printed by the panic routine for a failed runtime check at the
+// current node, put in read only data. Returns its label and length.
+fn generate_panic_message(generator: &mut Generator, message: &str) -> (String, usize) {
+    let message = format!("{}:{}: {}\n", generator.options.input, generator.get_location(), message);
+    let label = generator.new_label();
+    generator.add_element(Element::Instruction(Instruction::ReadOnly));
+    generator.add_element(Element::Operand(Operand::Label(String::clone(&label))));
+    generator.add_element(Element::Operand(Operand::Bytes(Vec::from(message.as_bytes()))));
+    return (label, message.len());
+}
+
+// Panic with the message unless Condition(lhs, rhs) holds
+fn generate_check(generator: &mut Generator, condition: Condition, lhs: Operand, rhs: Operand, message: &str) {
+    // Current node = expression being checked
+    let (message_label, length) = generate_panic_message(generator, message);
+    let end = generator.new_label();
+    generator.add_element(Element::Instruction(Instruction::Branch(condition)));
+    generator.add_element(Element::Operand(lhs));
+    generator.add_element(Element::Operand(rhs));
+    generator.add_element(Element::Operand(Operand::Label(String::clone(&end))));
+    generator.add_element(Element::Instruction(Instruction::Panic));
+    generator.add_element(Element::Operand(Operand::Label(message_label)));
+    generator.add_element(Element::Operand(Operand::Integer(length as i64)));
+    generator.add_element(Element::Instruction(Instruction::Label));
+    generator.add_element(Element::Operand(Operand::Label(end)));
+}
+
+// Value of a bool expression, used to decide which path to take. There's no
+// implicit conversion from other datatypes.
+pub fn generate_expression_condition(generator: &mut Generator) -> Operand {
+    // Current node = Expression
+    let datatype_i = add_primitive_datatype(generator, Primitive::Bool);
+    let (condition, condition_datatype_i) = generate_expression_operand(generator, datatype_i);
+    if !validate_datatypes(generator, datatype_i, condition_datatype_i, false, false) {
+        panic!("Condition must be a bool");
+    }
+    return condition;
+}
+
+// && and || only evaluate the rhs if the lhs doesn't decide the result
+fn generate_logical(generator: &mut Generator, op: &BinaryOp) -> (Symbol, usize) {
+    // Current node = Expression::BinaryOp
+    // Children: expression(lhs) , expression(rhs)
+    let skip = match op {
+        BinaryOp::LogicalAnd => 0,
+        _ => 1,
+    };
+    let result = new_temp(generator, &DatatypeInfo { size: 1, alignment: 1, regtype: Regtype::Integer, signed: false });
+    let end = generator.new_label();
+    generator.down();
+    let lhs = generate_expression_condition(generator);
+    generator.add_element(Element::Instruction(Instruction::Move));
+    generator.add_element(Element::Operand(lhs));
+    generator.add_element(Element::Operand(Operand::Symbol(Symbol::clone(&result))));
+    generator.add_element(Element::Instruction(Instruction::Branch(Condition::Equal)));
+    generator.add_element(Element::Operand(Operand::Symbol(Symbol::clone(&result))));
+    generator.add_element(Element::Operand(Operand::Constant(Constant::Int(skip))));
+    generator.add_element(Element::Operand(Operand::Label(String::clone(&end))));
+
+    // The rhs might not be evaluated, so the paths join after it
+    let skipped = generator.get_flow();
+    generator.next();
+    let rhs = generate_expression_condition(generator);
+    generator.add_element(Element::Instruction(Instruction::Move));
+    generator.add_element(Element::Operand(rhs));
+    generator.add_element(Element::Operand(Operand::Symbol(Symbol::clone(&result))));
+    generator.up();
+    let evaluated = generator.get_flow();
+    let flow = generator.merge_flow(&skipped, &evaluated);
+    generator.set_flow(&flow);
+
+    generator.add_element(Element::Instruction(Instruction::Label));
+    generator.add_element(Element::Operand(Operand::Label(end)));
+    let datatype_i = add_primitive_datatype(generator, Primitive::Bool);
+    return (result, datatype_i);
+}
+
+fn generate_logical_not(generator: &mut Generator) -> (Symbol, usize) {
+    // Current node = Expression::UnaryOp(LogicalNot)
+    // Children: expression
+    generator.down();
+    let operand = generate_expression_condition(generator);
+    generator.up();
+    let result = new_temp(generator, &DatatypeInfo { size: 1, alignment: 1, regtype: Regtype::Integer, signed: false });
+    generator.add_element(Element::Instruction(Instruction::ALUOp(ALUOp::Compare(Condition::Equal))));
+    generator.add_element(Element::Operand(operand));
+    generator.add_element(Element::Operand(Operand::Constant(Constant::Int(0))));
+    generator.add_element(Element::Operand(Operand::Symbol(Symbol::clone(&result))));
+    let datatype_i = add_primitive_datatype(generator, Primitive::Bool);
+    return (result, datatype_i);
+}
+
+// Allocate with the alloc function of an allocator, or a pointer to one,
+// which is a struct with the members:
+//   state: pointer passed back to the allocator's functions
+//   alloc: function(state, size: u64, alignment: u64) -> (pointer)
+fn generate_expression_new(generator: &mut Generator) -> (Symbol, usize) {
+    // Current node = Expression::New
+    // Children: datatype , expression(allocator)
+    resolve_datatype(generator);
+    generator.down();
+    let datatype_i = generator.get_ref_id();
+    let layout = get_datatype_layout(generator);
+    generator.next();
+    let (mut allocator, mut allocator_datatype_i, _) = get_value_pointer(generator, false);
+    generator.up();
+    if let Some(pointee_i) = get_pointee(generator, allocator_datatype_i) {
+        allocator = generate_load(generator, &allocator, allocator_datatype_i);
+        allocator_datatype_i = pointee_i;
+    }
+
+    let state_name = String::from("state");
+    let alloc_name = String::from("alloc");
+    if find_member(generator, allocator_datatype_i, &state_name).is_none()
+        || find_member(generator, allocator_datatype_i, &alloc_name).is_none() {
+        panic!("Allocator in new must be a struct with state and alloc members");
+    }
+    let (state_pointer, state_datatype_i) = get_member_pointer(generator, &allocator, allocator_datatype_i, &state_name);
+    let (alloc_pointer, alloc_datatype_i) = get_member_pointer(generator, &allocator, allocator_datatype_i, &alloc_name);
+
+    let valid = match get_signature(generator, alloc_datatype_i) {
+        Some((arguments, returns)) => {
+            arguments.len() == 3
+                && returns.len() == 1
+                && validate_datatypes(generator, arguments[0], state_datatype_i, false, false)
+                && is_datatype_u64(generator, arguments[1])
+                && is_datatype_u64(generator, arguments[2])
+                && is_datatype_pointer(generator, returns[0])
+        },
+        None => false,
+    };
+    if !valid {
+        panic!("alloc member of an allocator must take (state, size: u64, alignment: u64) and return a pointer");
+    }
+
+    let state = generate_load(generator, &state_pointer, state_datatype_i);
+    let alloc = generate_load(generator, &alloc_pointer, alloc_datatype_i);
+    let result = new_temp_pointer(generator);
+    generator.add_element(Element::Instruction(Instruction::Call));
+    generator.add_element(Element::Operand(Operand::Symbol(alloc)));
+    generator.add_element(Element::Operand(Operand::Symbol(state)));
+    generator.add_element(Element::Operand(word_location(0, Regtype::Pointer)));
+    generator.add_element(Element::Operand(Operand::Constant(Constant::Int(layout.size as i64))));
+    generator.add_element(Element::Operand(word_location(1, Regtype::Integer)));
+    generator.add_element(Element::Operand(Operand::Constant(Constant::Int(layout.alignment as i64))));
+    generator.add_element(Element::Operand(word_location(2, Regtype::Integer)));
+    generator.add_element(Element::Instruction(Instruction::GetReturned));
+    generator.add_element(Element::Operand(word_location(0, Regtype::Pointer)));
+    generator.add_element(Element::Operand(Operand::Symbol(Symbol::clone(&result))));
+
+    let pointer_i = add_pointer_datatype(generator, datatype_i, true);
+    return (result, pointer_i);
+}
+
+// Smallest and largest values of an integer datatype
+fn get_integer_range(info: &DatatypeInfo) -> (i128, i128) {
+    let bits = info.size * 8;
+    if info.signed {
+        return (-(1 << (bits - 1)), (1 << (bits - 1)) - 1);
+    }
+    return (0, (1 << bits) - 1);
+}
+
+// The number and arguments of a system call are passed in whole registers,
+// so each is a 64 bit integer or a pointer. Numbers with no datatype of
+// their own are i64.
+fn generate_expression_syscall(generator: &mut Generator) -> (Symbol, usize) {
+    // Current node = Expression::Syscall
+    // Children: expression(number) , { expression(argument) }
+    let i64_i = add_primitive_datatype(generator, Primitive::I64);
+    let mut operands: Vec<Operand> = Vec::new();
+    generator.down();
+    loop {
+        if operands.len() == 7 {
+            generator.error("A system call takes at most 6 arguments");
+        }
+        let operand = match generator.current() {
+            Construct::Expression(Expression::Null) => Operand::Constant(Constant::Int(0)),
+            _ => {
+                let (operand, datatype_i) = generate_expression_operand(generator, i64_i);
+                generator.down_ref(datatype_i);
+                let info = get_datatype_info(generator);
+                generator.up();
+                match info.regtype {
+                    Regtype::Integer | Regtype::Pointer if info.size == 8 => operand,
+                    _ => generator.error("Arguments of syscall must be 64 bit integers or pointers"),
+                }
+            },
+        };
+        operands.push(operand);
+        if !generator.next() {
+            break;
+        }
+    }
+    generator.up();
+
+    let result = new_temp(generator, &DatatypeInfo { size: 8, alignment: 8, regtype: Regtype::Integer, signed: true });
+    generator.add_element(Element::Instruction(Instruction::Syscall));
+    for operand in operands {
+        generator.add_element(Element::Operand(operand));
+    }
+    generator.add_element(Element::Operand(Operand::Symbol(Symbol::clone(&result))));
+    return (result, i64_i);
+}
+
+// Integers can be converted to any other integer datatype. A value that
+// doesn't fit wraps, unless overflow checks are on, where it panics.
+// Pointers can be converted to a pointer to any other datatype, but not
+// from a pointer to const to a pointer to mut, and to and from u64 for the
+// address.
+fn generate_expression_cast(generator: &mut Generator) -> (Symbol, usize) {
+    // Current node = Expression::Cast
+    // Children: datatype , expression(value)
+    resolve_datatype(generator);
+    generator.down();
+    let datatype_i = generator.get_ref_id();
+    let info = get_datatype_info(generator);
+    let primitive = get_datatype_primitive(generator);
+    generator.next();
+    // A literal value without a suffix is evaluated as the datatype it is
+    // cast to
+    if primitive.is_some() && is_literal_expression(generator) && find_literal_suffix(generator).is_none() {
+        let result = generate_constant_temp(generator, datatype_i);
+        generator.up();
+        return result;
+    }
+    let (value, value_datatype_i) = generate_expression_rvalue(generator);
+    generator.up();
+    generator.down_ref(value_datatype_i);
+    let value_info = get_datatype_info(generator);
+    let value_primitive = get_datatype_primitive(generator);
+    generator.up();
+
+    let address = match (get_pointee(generator, datatype_i), get_pointee(generator, value_datatype_i)) {
+        (Some(pointee_i), Some(value_pointee_i)) => {
+            if is_mutable(generator, pointee_i) && !is_mutable(generator, value_pointee_i) {
+                panic!("Can't cast a pointer to const to a pointer to mut");
+            }
+            true
+        },
+        (Some(_), None) => is_datatype_u64(generator, value_datatype_i),
+        (None, Some(_)) => is_datatype_u64(generator, datatype_i),
+        (None, None) => false,
+    };
+    if address {
+        let result = new_temp(generator, &info);
+        generator.add_element(Element::Instruction(Instruction::Move));
+        generator.add_element(Element::Operand(Operand::Symbol(value)));
+        generator.add_element(Element::Operand(Operand::Symbol(Symbol::clone(&result))));
+        return (result, datatype_i);
+    }
+    for (info, primitive) in [(&info, primitive), (&value_info, value_primitive)] {
+        match (info.regtype, primitive) {
+            (Regtype::Float, _) => panic!("Casts to and from floating point numbers not implemented yet"),
+            (_, Some(Primitive::Bool)) => panic!("Bools can't be cast, compare the value instead"),
+            (Regtype::Integer, _) => (),
+            (Regtype::Pointer, _) => panic!("Pointers can only be cast to other pointers, or u64"),
+            _ => panic!("Only integers and pointers can be cast"),
+        }
+    }
+
+    // Compared as the value's datatype, against the range of the result
+    if generator.options.overflow_checks {
+        let (min, max) = get_integer_range(&info);
+        let (value_min, value_max) = get_integer_range(&value_info);
+        if min > value_min {
+            let condition = if value_info.signed { Condition::GreaterEqual } else { Condition::AboveEqual };
+            generate_check(generator, condition, Operand::Symbol(Symbol::clone(&value)),
+                           Operand::Constant(Constant::Int(min as i64)), "Cast value out of range");
+        }
+        if max < value_max {
+            let condition = if value_info.signed { Condition::LessEqual } else { Condition::BelowEqual };
+            generate_check(generator, condition, Operand::Symbol(Symbol::clone(&value)),
+                           Operand::Constant(Constant::Int(max as i64)), "Cast value out of range");
+        }
+    }
+
+    let result = new_temp(generator, &info);
+    generator.add_element(Element::Instruction(Instruction::Convert(value_info.signed)));
+    generator.add_element(Element::Operand(Operand::Symbol(value)));
+    generator.add_element(Element::Operand(Operand::Symbol(Symbol::clone(&result))));
+    return (result, datatype_i);
+}
+
+// Adds a datatype pointing to the given datatype, which isn't in the tree.
+// Mutability of the pointee is given by mutable, not its own qualifiers.
+fn add_pointer_datatype(generator: &mut Generator, datatype_i: usize, mutable: bool) -> usize {
+    let mut children: Vec<usize> = Vec::new();
+    if mutable {
+        children.push(generator.add_node(&Construct::Qualifier(Qualifier::Mut), &[]));
+    }
+    generator.down_ref(datatype_i);
+    let construct = Construct::clone(generator.current());
+    if generator.down() {
+        loop {
+            match generator.current() {
+                Construct::Qualifier(_) => (),
+                _ => children.push(generator.get_ref_id()),
+            }
+            if !generator.next() {
+                break;
+            }
+        }
+        generator.up();
+    }
+    generator.up();
+    let pointee_i = generator.add_node(&construct, &children);
+    return generator.add_node(&Construct::Datatype(Datatype::Pointer), &[pointee_i]);
+}
+
+// String literals are stored in read only data, with a nul terminator so
+// they can be passed to C. The value is a []c8 slice of the literal, which
+// doesn't include the terminator.
+fn generate_string(generator: &mut Generator, string: &Vec<u8>) -> (Symbol, usize) {
+    let label = generator.new_label();
+    let mut bytes = Vec::clone(string);
+    bytes.push(0);
+    generator.add_element(Element::Instruction(Instruction::ReadOnly));
+    generator.add_element(Element::Operand(Operand::Label(String::clone(&label))));
+    generator.add_element(Element::Operand(Operand::Bytes(bytes)));
+
+    let element_i = add_primitive_datatype(generator, Primitive::C8);
+    let slice_i = generator.add_node(&Construct::Datatype(Datatype::Slice), &[element_i]);
+    let slice = new_temp(generator, &DatatypeInfo { size: 16, alignment: 8, regtype: Regtype::Struct, signed: false });
+    let pointer = generate_address(generator, Operand::Symbol(Symbol::clone(&slice)));
+    let data = generate_address(generator, Operand::Label(label));
+    generator.add_element(Element::Instruction(Instruction::Store));
+    generator.add_element(Element::Operand(Operand::Symbol(data)));
+    generator.add_element(Element::Operand(Operand::Symbol(Symbol::clone(&pointer))));
+    let (len_pointer, _) = get_member_pointer(generator, &pointer, slice_i, &String::from("len"));
+    generator.add_element(Element::Instruction(Instruction::Store));
+    generator.add_element(Element::Operand(Operand::Constant(Constant::Int(string.len() as i64))));
+    generator.add_element(Element::Operand(Operand::Symbol(len_pointer)));
+    return (slice, slice_i);
+}
+
+fn word_location(index: usize, regtype: Regtype) -> Operand {
+    return Operand::PassLocation(PassLocation {
+        index: index,
+        size: 8,
+        regtype: regtype,
+        signed: false,
+    });
+}
+
+fn is_datatype_u64(generator: &mut Generator, datatype_i: usize) -> bool {
+    generator.down_ref(datatype_i);
+    let primitive = get_datatype_primitive(generator);
+    generator.up();
+    return primitive == Some(Primitive::U64);
+}
+
+fn is_datatype_pointer(generator: &mut Generator, datatype_i: usize) -> bool {
+    generator.down_ref(datatype_i);
+    let pointer = match generator.current() {
+        Construct::Datatype(Datatype::Pointer) => true,
+        _ => false,
+    };
+    generator.up();
+    return pointer;
+}
+
 pub fn generate_expression_rvalue(generator: &mut Generator) -> (Symbol, usize) {
     // Current node = Expression
     let expression = match generator.current() {
@@ -90,58 +1317,325 @@ pub fn generate_expression_rvalue(generator: &mut Generator) -> (Symbol, usize)
         Expression::Identifier => {
             generator.down();
             // Current node = Construct::Identifier
+            // A function used as a value is a pointer to it
+            if let Some((label, function_i)) = get_function_identifier(generator) {
+                generator.up();
+                let pointer = generate_address(generator, Operand::Label(label));
+                return (pointer, function_i);
+            }
+            if let Some((constant, datatype_i, info)) = get_const_identifier(generator) {
+                generator.up();
+                let symbol = new_temp(generator, &info);
+                generator.add_element(Element::Instruction(Instruction::Move));
+                generator.add_element(Element::Operand(Operand::Constant(constant)));
+                generator.add_element(Element::Operand(Operand::Symbol(Symbol::clone(&symbol))));
+                return (symbol, datatype_i);
+            }
             let (symbol, datatype_i) = get_symbol_identifier(generator, false);
             generator.up();
             return (symbol, datatype_i);
         },
+        Expression::UnaryOp(UnaryOp::LogicalNot) => return generate_logical_not(generator),
+        Expression::UnaryOp(UnaryOp::Ref) => {
+            // Pointer can only be used to assign to the value if the value
+            // can be assigned to
+            generator.down();
+            let (pointer, datatype_i, mutable) = get_value_pointer(generator, false);
+            generator.up();
+            let pointer_i = add_pointer_datatype(generator, datatype_i, mutable);
+            return (pointer, pointer_i);
+        },
+        Expression::UnaryOp(UnaryOp::Deref) => {
+            generator.down();
+            let (pointer, datatype_i) = get_dereferenced_pointer(generator);
+            generator.up();
+            // The value would be dropped both as a copy and where it's owned
+            if find_drop_function(generator, datatype_i).is_some() {
+                panic!("Can't move a value with a drop function out of a pointer");
+            }
+            let value = generate_load(generator, &pointer, datatype_i);
+            return (value, datatype_i);
+        },
+        Expression::UnaryOp(_) | Expression::BinaryOp(_) if is_literal_expression(generator) => {
+            // Without anything to take the datatype from, a literal in it
+            // needs a suffix to give it one
+            let suffix = match find_literal_suffix(generator) {
+                Some(suffix) => suffix,
+                None => panic!("Datatype of {} can't be inferred here, give a literal in it a suffix", expression),
+            };
+            let datatype_i = add_primitive_datatype(generator, suffix);
+            return generate_constant_temp(generator, datatype_i);
+        },
         Expression::UnaryOp(op) => {
             generator.down();
             // Current node = expression
             let (operand_symbol, operand_datatype_i) = generate_expression_rvalue(generator);
             let result = match op {
                 UnaryOp::Negate => {
-                    let result_symbol = Symbol {
-                        name: String::from("__temp"),
-                        version: generator.get_temp_version(),
-                        size: operand_symbol.size,
-                        regtype: operand_symbol.regtype,
-                    };
-                    generator.add_element(Element::Instruction(Instruction::ALUOp(ALUOp::Negate)));
-                    generator.add_element(Element::Operand(Operand::Symbol(Symbol::clone(&operand_symbol))));
-                    generator.add_element(Element::Operand(Operand::Symbol(Symbol::clone(&result_symbol))));
-                    (Symbol::clone(&result_symbol), operand_datatype_i)
+                    generator.down_ref(operand_datatype_i);
+                    let info = get_datatype_info(generator);
+                    generator.up();
+                    let result_symbol = new_temp(generator, &info);
+                    generate_alu_op(generator, ALUOp::Negate, &info, &[Operand::Symbol(operand_symbol)], &result_symbol);
+                    (result_symbol, operand_datatype_i)
                 }
                 _ => panic!("{} not implemented yet", op),
             };
             generator.up();
             return result;
         },
-        Expression::BinaryOp(op) => {
+        Expression::BinaryOp(BinaryOp::Access) => {
+            if get_access_member(generator).1 {
+                return match generate_expression_call(generator) {
+                    Some(result) => result,
+                    None => panic!("Function call used as a value doesn't return anything"),
+                };
+            }
+            let (pointer, datatype_i, _) = get_value_pointer(generator, false);
+            let value = generate_load(generator, &pointer, datatype_i);
+            return (value, datatype_i);
+        },
+        Expression::BinaryOp(BinaryOp::Index) => {
+            let (pointer, datatype_i, _) = get_element_pointer(generator);
+            // The value would be dropped both as a copy and in the slice
+            if find_drop_function(generator, datatype_i).is_some() {
+                panic!("Can't move a value with a drop function out of a slice");
+            }
+            let value = generate_load(generator, &pointer, datatype_i);
+            return (value, datatype_i);
+        },
+        Expression::BinaryOp(op @ (BinaryOp::LogicalEquals | BinaryOp::NotEquals
+                                   | BinaryOp::LessThan | BinaryOp::LessEquals
+                                   | BinaryOp::GreaterThan | BinaryOp::GreaterEquals)) => {
+            return generate_comparison(generator, &op);
+        },
+        Expression::BinaryOp(op @ (BinaryOp::LogicalAnd | BinaryOp::LogicalOr)) => {
+            return generate_logical(generator, &op);
+        },
+        Expression::BinaryOp(op) => return generate_arithmetic(generator, &op),
+        Expression::Function => {
+            return match generate_expression_call(generator) {
+                Some(result) => result,
+                None => panic!("Function call used as a value doesn't return anything"),
+            };
+        },
+        Expression::New => return generate_expression_new(generator),
+        Expression::Cast => return generate_expression_cast(generator),
+        Expression::Syscall => return generate_expression_syscall(generator),
+        Expression::Constant(token::Constant::Str(string)) => return generate_string(generator, &string),
+        Expression::Constant(constant) => {
+            // Without anything to take the datatype from, a literal needs a
+            // suffix to give it one
+            let suffix = if generator.down() {
+                let suffix = match generator.current() {
+                    Construct::Primitive(primitive) => Primitive::clone(primitive),
+                    _ => panic!("Unexpected child of constant expression"),
+                };
+                generator.up();
+                suffix
+            } else {
+                panic!("Datatype of {} can't be inferred here, give it a suffix", constant);
+            };
+            let datatype_i = add_primitive_datatype(generator, suffix);
+            return generate_constant_temp(generator, datatype_i);
+        },
+        Expression::Sizeof | Expression::Alignof | Expression::Offsetof => {
+            // Sizes and offsets are u64 when not assigned to anything
+            let datatype_i = add_primitive_datatype(generator, Primitive::U64);
+            return generate_constant_temp(generator, datatype_i);
+        },
+        Expression::Null => panic!("null can only be assigned to or compared with a pointer"),
+        _ => panic!("{} not implemented", expression),
+    }
+}
+
+// Evaluates a constant expression as the given datatype, into a symbol
+fn generate_constant_temp(generator: &mut Generator, datatype_i: usize) -> (Symbol, usize) {
+    // Current node = Expression
+    let (operand, _) = generate_expression_operand(generator, datatype_i);
+    generator.down_ref(datatype_i);
+    let info = get_datatype_info(generator);
+    generator.up();
+    let symbol = new_temp(generator, &info);
+    generator.add_element(Element::Instruction(Instruction::Move));
+    generator.add_element(Element::Operand(operand));
+    generator.add_element(Element::Operand(Operand::Symbol(Symbol::clone(&symbol))));
+    return (symbol, datatype_i);
+}
+
+pub fn get_constant_operand(constant: &token::Constant, regtype: &Regtype) -> Constant {
+    match (constant, regtype) {
+        (token::Constant::Int(value), Regtype::Integer) => Constant::Int(*value as i64),
+        (token::Constant::Int(value), Regtype::Pointer) => Constant::Int(*value as i64),
+        (token::Constant::Float(value), Regtype::Float) => Constant::Float(*value),
+        (token::Constant::Int(_), Regtype::Float) => panic!("Integer constant used as a floating point value"),
+        (token::Constant::Float(_), _) => panic!("Floating point constant used as a non floating point value"),
+        (token::Constant::Str(_), _) => panic!("String literal used as a constant"),
+        (token::Constant::Char(value), Regtype::Integer) => Constant::Int(*value as i64),
+        (token::Constant::Char(_), _) => panic!("Character constant used as a value that isn't a c8"),
+        (token::Constant::Bool(value), Regtype::Integer) => Constant::Int(*value as i64),
+        (token::Constant::Bool(_), _) => panic!("Bool constant used as a value that isn't a bool"),
+        (_, Regtype::Struct) => panic!("Constant used as a struct value"),
+    }
+}
+
+// An expression made only of literals, eg: -1 or 2 * 3, is evaluated at
+// compile time as the datatype it is used as
+fn is_literal_expression(generator: &mut Generator) -> bool {
+    // Current node = Expression
+    let expression = match generator.current() {
+        Construct::Expression(expression) => Expression::clone(expression),
+        _ => return false,
+    };
+    let literal = match expression {
+        Expression::Constant(token::Constant::Str(_)) => false,
+        Expression::Constant(_) => true,
+        Expression::UnaryOp(UnaryOp::Negate) => {
+            generator.down();
+            let literal = is_literal_expression(generator);
+            generator.up();
+            literal
+        },
+        Expression::BinaryOp(BinaryOp::Add | BinaryOp::Subtract | BinaryOp::Multiply
+                             | BinaryOp::Divide | BinaryOp::Modulo
+                             | BinaryOp::ShiftLeft | BinaryOp::ShiftRight
+                             | BinaryOp::BitwiseAnd | BinaryOp::BitwiseOr) => {
             generator.down();
-            // Current children = expression(lhs) , expression(rhs)
-            let (lhs_symbol, lhs_datatype_i) = generate_expression_rvalue(generator);
+            let lhs = is_literal_expression(generator);
             generator.next();
-            let (rhs_symbol, rhs_datatype_i) = generate_expression_rvalue(generator);
-            // TODO: Assert datatypes match
-            let result = match op {
-                BinaryOp::Add => {
-                    let result_symbol = Symbol {
-                        name: String::from("__temp"),
-                        version: generator.get_temp_version(),
-                        size: lhs_symbol.size,
-                        regtype: lhs_symbol.regtype,
-                    };
-                    generator.add_element(Element::Instruction(Instruction::ALUOp(ALUOp::Add)));
-                    generator.add_element(Element::Operand(Operand::Symbol(Symbol::clone(&lhs_symbol))));
-                    generator.add_element(Element::Operand(Operand::Symbol(Symbol::clone(&rhs_symbol))));
-                    generator.add_element(Element::Operand(Operand::Symbol(Symbol::clone(&result_symbol))));
-                    (Symbol::clone(&result_symbol), lhs_datatype_i)
-                }
-                _ => panic!("{} not implemented yet", op),
+            let rhs = is_literal_expression(generator);
+            generator.up();
+            lhs && rhs
+        },
+        _ => false,
+    };
+    return literal;
+}
+
+// Suffix of any literal in a literal expression
+fn find_literal_suffix(generator: &mut Generator) -> Option<Primitive> {
+    // Current node = Expression
+    let mut suffix = None;
+    if generator.down() {
+        loop {
+            suffix = match generator.current() {
+                Construct::Primitive(primitive) => Some(Primitive::clone(primitive)),
+                _ => find_literal_suffix(generator),
+            };
+            if suffix.is_some() || !generator.next() {
+                break;
+            }
+        }
+        generator.up();
+    }
+    return suffix;
+}
+
+// Datatype of a literal expression with nothing to take one from: the
+// suffix of a literal in it, or else what C would give its first literal
+fn get_literal_primitive(generator: &mut Generator) -> Option<Primitive> {
+    // Current node = Expression
+    if let Some(suffix) = find_literal_suffix(generator) {
+        return Some(suffix);
+    }
+    let mut depth = 0;
+    while let Construct::Expression(Expression::UnaryOp(_) | Expression::BinaryOp(_)) = generator.current() {
+        generator.down();
+        depth += 1;
+    }
+    let primitive = match generator.current() {
+        Construct::Expression(Expression::Constant(constant)) => match constant {
+            token::Constant::Int(_) => Some(Primitive::I32),
+            token::Constant::Float(_) => Some(Primitive::F64),
+            token::Constant::Char(_) => Some(Primitive::C8),
+            token::Constant::Bool(_) => Some(Primitive::Bool),
+            token::Constant::Str(_) => None,
+        },
+        _ => None,
+    };
+    for _ in 0..depth {
+        generator.up();
+    }
+    return primitive;
+}
+
+pub fn generate_expression_operand(generator: &mut Generator, datatype_i: usize) -> (Operand, usize) {
+    // Current node = Expression
+    // A constant takes the datatype it is being assigned to, so can be used
+    // as an immediate. Anything else is evaluated into a symbol.
+    let literal = is_literal_expression(generator);
+    let constant = match generator.current() {
+        Construct::Expression(Expression::Constant(token::Constant::Str(_))) => {
+            let (symbol, symbol_datatype_i) = generate_expression_rvalue(generator);
+            return (Operand::Symbol(symbol), symbol_datatype_i);
+        },
+        Construct::Expression(Expression::Constant(constant)) => token::Constant::clone(constant),
+        Construct::Expression(Expression::UnaryOp(_) | Expression::BinaryOp(_)) if literal => {
+            generator.down_ref(datatype_i);
+            let primitive = get_datatype_primitive(generator);
+            generator.up();
+            let primitive = match primitive {
+                Some(primitive) => primitive,
+                None => panic!("Number used as a value that isn't a number"),
             };
+            return match evaluate_constant(generator, &primitive) {
+                Ok(constant) => (Operand::Constant(constant), datatype_i),
+                Err(error) => panic!("{}", error),
+            };
+        },
+        Construct::Expression(Expression::Sizeof)
+        | Construct::Expression(Expression::Alignof)
+        | Construct::Expression(Expression::Offsetof) => {
+            generator.down_ref(datatype_i);
+            let primitive = get_datatype_primitive(generator);
             generator.up();
-            return result;
+            let primitive = match primitive {
+                Some(primitive) => primitive,
+                None => panic!("{} must be assigned to an integer", generator.current()),
+            };
+            return match evaluate_constant(generator, &primitive) {
+                Ok(constant) => (Operand::Constant(constant), datatype_i),
+                Err(error) => panic!("{}", error),
+            };
         },
-        _ => panic!("{} not implemented", expression),
+        Construct::Expression(Expression::Null) => {
+            generator.down_ref(datatype_i);
+            let info = get_datatype_info(generator);
+            generator.up();
+            return match info.regtype {
+                Regtype::Pointer => (Operand::Constant(Constant::Int(0)), datatype_i),
+                _ => panic!("null used as a value that isn't a pointer"),
+            };
+        },
+        _ => {
+            // Using a variable that needs dropping as a value moves it
+            let moved = get_moved_symbol(generator);
+            let (symbol, symbol_datatype_i) = generate_expression_rvalue(generator);
+            if let Some(name) = moved {
+                generator.set_symbol_moved(&name, true);
+            }
+            return (Operand::Symbol(symbol), symbol_datatype_i);
+        }
+    };
+    generator.down_ref(datatype_i);
+    let info = get_datatype_info(generator);
+    let primitive = get_datatype_primitive(generator);
+    generator.up();
+    match (&constant, &primitive) {
+        (token::Constant::Bool(_), Some(Primitive::Bool)) => (),
+        (token::Constant::Bool(_), _) => panic!("Bool constant used as a value that isn't a bool, no implicit casting"),
+        (_, Some(Primitive::Bool)) => panic!("{} used as a bool, no implicit casting", constant),
+        (token::Constant::Char(_), Some(Primitive::C8)) => (),
+        (token::Constant::Char(_), _) => panic!("Character constant used as a value that isn't a c8, no implicit casting"),
+        _ => (),
     }
+    // Numbers are range checked against the primitive they are used as
+    let operand = match primitive {
+        Some(primitive) => match evaluate_constant(generator, &primitive) {
+            Ok(constant) => constant,
+            Err(error) => panic!("{}", error),
+        },
+        None => get_constant_operand(&constant, &info.regtype),
+    };
+    return (Operand::Constant(operand), datatype_i);
 }
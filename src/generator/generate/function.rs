@@ -1,4 +1,7 @@
 
+use std::collections::HashMap;
+
+use crate::mangle::{mangle, get_module_path};
 use super::construct::*;
 use super::generator::Generator;
 use super::instructions::*;
@@ -7,24 +10,164 @@ use super::resolve::resolve_content;
 use super::content::generate_content;
 use super::datatype::create_pass_location;
 use super::datatype::generate_argument_get;
+use super::datatype::get_signature;
+use super::datatype::get_datatype_info;
+use super::datatype::validate_datatypes;
+use super::drop::find_symbol_drop;
+use super::statement::generate_defers;
+
+
+// Labels every function and global variable below the current node. path
+// holds the module and the enclosing functions and structs, counts how many
+// symbols were already given each path. global is whether the node is
+// outside of any function or struct.
+fn label_content(generator: &mut Generator, path: &mut Vec<String>, global: bool, counts: &mut HashMap<String, usize>) {
+    // Current node = any node
+    if !generator.down() {
+        return;
+    }
+    loop {
+        let name = match generator.current() {
+            Construct::Function(name, _) => Some(String::clone(name)),
+            Construct::Structure(name, _, _) => Some(String::clone(name)),
+            _ => None,
+        };
+        // Variables outside of any function are global
+        if let (Construct::Variable(name), true) = (generator.current(), global) {
+            path.push(String::clone(name));
+            label_symbol(generator, path, counts);
+            path.pop();
+        }
+        if let Some(name) = name {
+            path.push(name);
+            if let Construct::Function(..) = generator.current() {
+                label_symbol(generator, path, counts);
+            }
+            label_content(generator, path, false, counts);
+            path.pop();
+        } else {
+            label_content(generator, path, global, counts);
+        }
+        if !generator.next() {
+            break;
+        }
+    }
+    generator.up();
+}
+
+fn label_symbol(generator: &mut Generator, path: &[String], counts: &mut HashMap<String, usize>) {
+    // Current node = Function or Variable
+    let base = mangle(path, 0);
+    let count = counts.entry(base).or_insert(0);
+    let label = mangle(path, *count);
+    *count += 1;
+    generator.labels.insert(generator.get_ref_id(), label);
+}
 
+// Labels come from the module and where each symbol is declared, see
+// mangle.rs, so are found before any code is generated.
+pub fn label_symbols(generator: &mut Generator) {
+    // Current node = Program
+    let mut path = get_module_path(&generator.options.input);
+    let mut counts: HashMap<String, usize> = HashMap::new();
+    label_content(generator, &mut path, true, &mut counts);
+}
+
+// Extern and exported functions and main are called from outside the
+// program, so keep their name
+pub fn get_function_label(generator: &mut Generator, function_i: usize) -> String {
+    generator.down_ref(function_i);
+    let (name, linkage) = match generator.current() {
+        Construct::Function(name, linkage) => (String::clone(name), *linkage),
+        _ => panic!("Node at get_function_label() is not a function"),
+    };
+    generator.up();
+    if name == "main" || linkage != Linkage::Internal {
+        return name;
+    }
+    return match generator.labels.get(&function_i) {
+        Some(label) => String::clone(label),
+        None => panic!("Function {} wasn't given a label", name),
+    };
+}
+
+// Global variables are only used within the program, so always have a
+// mangled label
+pub fn get_global_label(generator: &Generator, variable_i: usize) -> String {
+    return match generator.labels.get(&variable_i) {
+        Some(label) => String::clone(label),
+        None => panic!("Global variable wasn't given a label"),
+    };
+}
+
+// main is called with argc and argv, and returns the exit status, so can
+// be main() or main(argc: i32, argv: &&c8), returning an i32 or nothing.
+// Unless linking against libc, the entry point calling it is added here.
+fn generate_start(generator: &mut Generator) {
+    // Current node = Function
+    let function_i = generator.get_ref_id();
+    let (arguments, returns) = get_signature(generator, function_i)
+        .expect("Function has no signature");
+
+    let c8_i = generator.add_node(&Construct::Primitive(Primitive::C8), &[]);
+    let string_i = generator.add_node(&Construct::Datatype(Datatype::Terminal), &[c8_i]);
+    let string_pointer_i = generator.add_node(&Construct::Datatype(Datatype::Pointer), &[string_i]);
+    let argv_i = generator.add_node(&Construct::Datatype(Datatype::Pointer), &[string_pointer_i]);
+    let i32_i = generator.add_node(&Construct::Primitive(Primitive::I32), &[]);
+    let status_i = generator.add_node(&Construct::Datatype(Datatype::Terminal), &[i32_i]);
+
+    let valid_arguments = arguments.len() == 0 || (arguments.len() == 2
+        && validate_datatypes(generator, arguments[0], status_i, false, false)
+        && validate_datatypes(generator, arguments[1], argv_i, false, false));
+    if !valid_arguments {
+        panic!("main must take no arguments or (argc: i32, argv: &&c8)");
+    }
+    let valid_returns = returns.len() == 0 || (returns.len() == 1
+        && validate_datatypes(generator, status_i, returns[0], false, false));
+    if !valid_returns {
+        panic!("main must return an i32 or nothing");
+    }
+
+    if generator.options.libc {
+        return;
+    }
+    generator.add_element(Element::Instruction(Instruction::Start));
+    generator.add_element(Element::Operand(Operand::Label(String::from("main"))));
+    if returns.len() == 1 {
+        generator.down_ref(returns[0]);
+        let info = get_datatype_info(generator);
+        generator.up();
+        generator.add_element(Element::Operand(Operand::PassLocation(PassLocation {
+            index: 0,
+            size: info.size,
+            regtype: info.regtype,
+            signed: info.signed,
+        })));
+    }
+}
 
 pub fn generate_function(generator: &mut Generator) {
-    let mut name = match generator.current() {
-        Construct::Function(name) => String::clone(name),
+    let (name, linkage) = match generator.current() {
+        Construct::Function(name, linkage) => (String::clone(name), *linkage),
         _ => panic!("Node at generate_function() is not a function"),
     };
+    // Extern functions are left undefined, for the linker to find
+    if linkage == Linkage::Extern {
+        let label = get_function_label(generator, generator.get_ref_id());
+        generator.add_element(Element::Instruction(Instruction::Extern));
+        generator.add_element(Element::Operand(Operand::Label(label)));
+        return;
+    }
     generator.increase_scope_function();
 
+    if name == "main" {
+        generate_start(generator);
+    }
+
     // Add function label
+    let label = get_function_label(generator, generator.get_ref_id());
     generator.add_element(Element::Instruction(Instruction::Label));
-    if name != "main" {
-        name.push_str("__");
-        name.push_str(&generator.get_ref_id().to_string());
-    } else {
-        name = String::from("_start");
-    }
-    generator.add_element(Element::Operand(Operand::Label(name)));
+    generator.add_element(Element::Operand(Operand::Label(label)));
 
     generator.down();
 
@@ -37,8 +180,15 @@ pub fn generate_function(generator: &mut Generator) {
                 let name = String::clone(name_);
                 generator.add_symbol(&name, true);
                 let pass_location = create_pass_location(generator, arg_count);
+                if let (Linkage::Export, Regtype::Struct) = (linkage, &pass_location.regtype) {
+                    panic!("Exported functions can't take a struct, pass a pointer to {} instead", name);
+                }
                 arg_count+=1;
                 generate_argument_get(generator, &pass_location, &name);
+                // Arguments are owned by the function, so dropped on return
+                if find_symbol_drop(generator, &name).is_some() {
+                    generator.push_drop(&name);
+                }
             }
             Construct::Returned => {
                 generator.down();
@@ -60,6 +210,13 @@ pub fn generate_function(generator: &mut Generator) {
     resolve_content(generator);
     generate_content(generator);
 
+    // Falling through the end of the body returns without a value
+    if generator.is_reachable() {
+        let depth = generator.get_function_scope_depth();
+        generate_defers(generator, depth);
+        generator.add_element(Element::Instruction(Instruction::Return));
+    }
+
     generator.up(); // Out of function
     generator.decrease_scope_function();
 }
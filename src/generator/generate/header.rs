@@ -0,0 +1,335 @@
+use std::fmt::Write;
+
+use super::construct::*;
+use super::generator::Generator;
+use super::resolve::fully_define_structure;
+
+use super::datatype::{
+    skip_qualifiers, follow_alias, is_mutable, get_pointee, get_slice_element,
+    get_signature, is_variadic, find_datatype_structure,
+};
+
+
+// C header declaring the structs and exported functions of a program, so C
+// code can be linked with it. Structs are laid out the same as ours, which
+// is checked by the C compiler with static assertions.
+
+fn get_c_primitive(primitive: &Primitive) -> &'static str {
+    match primitive {
+        Primitive::U8 => "uint8_t",
+        Primitive::U16 => "uint16_t",
+        Primitive::U32 => "uint32_t",
+        Primitive::U64 => "uint64_t",
+        Primitive::I8 => "int8_t",
+        Primitive::I16 => "int16_t",
+        Primitive::I32 => "int32_t",
+        Primitive::I64 => "int64_t",
+        Primitive::F32 => "float",
+        Primitive::F64 => "double",
+        Primitive::C8 => "char",
+        Primitive::Bool => "bool",
+    }
+}
+
+fn format_c_declaration(base: &str, declarator: &str, constant: bool) -> String {
+    let qualifier = if constant { "const " } else { "" };
+    let declaration = format!("{}{} {}", qualifier, base, declarator);
+    return String::from(declaration.trim_end());
+}
+
+fn get_c_function(generator: &mut Generator, declarator: &str, arguments: &[String], returns: &[usize]) -> String {
+    let arguments = if arguments.is_empty() {
+        String::from("void")
+    } else {
+        arguments.join(", ")
+    };
+    let declarator = format!("{}({})", declarator, arguments);
+    match returns.len() {
+        0 => format_c_declaration("void", &declarator, false),
+        1 => get_c_declaration(generator, returns[0], &declarator, false),
+        _ => panic!("Functions returning more than one value can't be declared in C"),
+    }
+}
+
+// C declaration of a datatype, with the declarator being the name and any
+// pointers or arguments wrapped around it. Pointers to something that isn't
+// mut point to const.
+fn get_c_declaration(generator: &mut Generator, datatype_i: usize, declarator: &str, constant: bool) -> String {
+    let datatype_i = follow_alias(generator, datatype_i);
+    let pointer = if constant { "*const " } else { "*" };
+
+    if let Some(pointee_i) = get_pointee(generator, datatype_i) {
+        let declarator = format!("{}{}", pointer, declarator);
+        let pointee_constant = !is_mutable(generator, pointee_i);
+        return get_c_declaration(generator, pointee_i, declarator.trim_end(), pointee_constant);
+    }
+    if let Some(element_i) = get_slice_element(generator, datatype_i) {
+        let element_constant = !is_mutable(generator, element_i);
+        let ptr = get_c_declaration(generator, element_i, "*ptr", element_constant);
+        let base = format!("struct {{ {}; uint64_t len; }}", ptr);
+        return format_c_declaration(&base, declarator, constant);
+    }
+    if let Some((arguments, returns)) = get_signature(generator, datatype_i) {
+        let declarator = format!("({}{})", pointer, declarator);
+        let mut c_arguments: Vec<String> = Vec::new();
+        for argument_i in arguments {
+            c_arguments.push(get_c_declaration(generator, argument_i, "", false));
+        }
+        if is_variadic(generator, datatype_i) {
+            c_arguments.push(String::from("..."));
+        }
+        return get_c_function(generator, declarator.trim_end(), &c_arguments, &returns);
+    }
+
+    // Current node = Datatype::Terminal
+    // Children: { qualifier } , ( primitive | reference )
+    generator.down_ref(datatype_i);
+    generator.down();
+    skip_qualifiers(generator);
+    let declaration = match generator.current() {
+        Construct::Primitive(primitive) => format_c_declaration(get_c_primitive(primitive), declarator, constant),
+        Construct::Reference(ref_i_) => {
+            let ref_i = *ref_i_;
+            generator.down_ref(ref_i);
+            let declaration = match generator.current() {
+                Construct::Structure(name, _, _) => {
+                    let base = format!("struct {}", name);
+                    format_c_declaration(&base, declarator, constant)
+                },
+                Construct::Alias(_, _) => {
+                    // Distinct alias, C only has the underlying datatype
+                    generator.down();
+                    let aliased_i = generator.get_ref_id();
+                    generator.up();
+                    get_c_declaration(generator, aliased_i, declarator, constant)
+                },
+                _ => panic!("Reference doesn't point to a structure or alias node"),
+            };
+            generator.up();
+            declaration
+        },
+        _ => panic!("Unexpected child node of Datatype::Terminal"),
+    };
+    generator.up();
+    generator.up();
+    return declaration;
+}
+
+struct HeaderMember {
+    name: String,
+    offset: usize,
+    datatype_i: usize,
+}
+
+// Structs contained by value must be defined first, so they are written
+// before the struct that contains them.
+fn write_structure(generator: &mut Generator, structure_i: usize, written: &mut Vec<usize>, header: &mut String) {
+    if written.contains(&structure_i) {
+        return;
+    }
+    written.push(structure_i);
+
+    // Current node = Structure
+    // Children: { attribute } , { member | function }
+    generator.down_ref(structure_i);
+    let layout = fully_define_structure(generator);
+    let name = match generator.current() {
+        Construct::Structure(name, _, _) => String::clone(name),
+        _ => panic!("Node at write_structure() is not a structure"),
+    };
+    let mut attributes: Vec<String> = Vec::new();
+    let mut members: Vec<HeaderMember> = Vec::new();
+    if generator.down() {
+        loop {
+            match generator.current() {
+                Construct::Attribute(Attribute::Packed) => attributes.push(String::from("packed")),
+                Construct::Attribute(Attribute::Align(alignment)) => attributes.push(format!("aligned({})", alignment)),
+                Construct::Member(member, offset) => {
+                    let member = String::clone(member);
+                    let offset = *offset;
+                    generator.down();
+                    loop {
+                        if let Construct::Datatype(_) = generator.current() {
+                            break;
+                        }
+                        if !generator.next() {
+                            panic!("Member had no datatype child");
+                        }
+                    }
+                    members.push(HeaderMember {
+                        name: member,
+                        offset: offset,
+                        datatype_i: generator.get_ref_id(),
+                    });
+                    generator.up();
+                },
+                _ => (),
+            }
+            if !generator.next() {
+                break;
+            }
+        }
+        generator.up();
+    }
+    generator.up();
+
+    for member in &members {
+        if let Some(member_structure_i) = find_datatype_structure(generator, member.datatype_i) {
+            write_structure(generator, member_structure_i, written, header);
+        }
+    }
+
+    // Members are in offset order once the struct is defined, and C places
+    // them the same way, since padding only comes from alignment.
+    writeln!(header, "struct {} {{", name).unwrap();
+    for member in &members {
+        let declaration = get_c_declaration(generator, member.datatype_i, &member.name, false);
+        writeln!(header, "    {}; // offset {}", declaration, member.offset).unwrap();
+    }
+    if attributes.is_empty() {
+        writeln!(header, "}};").unwrap();
+    } else {
+        writeln!(header, "}} __attribute__(({}));", attributes.join(", ")).unwrap();
+    }
+    writeln!(header, "_Static_assert(sizeof(struct {}) == {}, \"Size of struct {} doesn't match\");",
+        name, layout.size, name).unwrap();
+    writeln!(header, "_Static_assert(_Alignof(struct {}) == {}, \"Alignment of struct {} doesn't match\");",
+        name, layout.alignment, name).unwrap();
+    for member in &members {
+        writeln!(header, "_Static_assert(offsetof(struct {}, {}) == {}, \"Offset of {}.{} doesn't match\");",
+            name, member.name, member.offset, name, member.name).unwrap();
+    }
+    writeln!(header).unwrap();
+}
+
+// Slice, or pointer to one
+fn has_slice(generator: &mut Generator, datatype_i: usize) -> bool {
+    if get_slice_element(generator, datatype_i).is_some() {
+        return true;
+    }
+    return match get_pointee(generator, datatype_i) {
+        Some(pointee_i) => has_slice(generator, pointee_i),
+        None => false,
+    };
+}
+
+fn write_function(generator: &mut Generator, header: &mut String) {
+    // Current node = Function
+    // Children: { argument } , { returned } , block
+    let name = match generator.current() {
+        Construct::Function(name, _) => String::clone(name),
+        _ => panic!("Node at write_function() is not a function"),
+    };
+    let mut arguments: Vec<(String, usize)> = Vec::new();
+    let mut returns: Vec<usize> = Vec::new();
+    if generator.down() {
+        loop {
+            match generator.current() {
+                Construct::Argument(argument) => {
+                    let argument = String::clone(argument);
+                    generator.down();
+                    arguments.push((argument, generator.get_ref_id()));
+                    generator.up();
+                },
+                Construct::Returned => {
+                    generator.down();
+                    returns.push(generator.get_ref_id());
+                    generator.up();
+                },
+                _ => (),
+            }
+            if !generator.next() {
+                break;
+            }
+        }
+        generator.up();
+    }
+    // A slice would be an anonymous struct, which a caller couldn't name
+    for (argument, datatype_i) in &arguments {
+        if has_slice(generator, *datatype_i) {
+            panic!("Argument {} of {} is a slice, which can't be declared in C", argument, name);
+        }
+    }
+    for datatype_i in &returns {
+        if has_slice(generator, *datatype_i) {
+            panic!("Function {} returns a slice, which can't be declared in C", name);
+        }
+    }
+    let mut c_arguments: Vec<String> = Vec::new();
+    for (argument, datatype_i) in arguments {
+        c_arguments.push(get_c_declaration(generator, datatype_i, &argument, false));
+    }
+    let declaration = get_c_function(generator, &name, &c_arguments, &returns);
+    writeln!(header, "{};", declaration).unwrap();
+}
+
+// Include guard from the header's file name, eg: out/lib.h -> LIB_H
+fn get_include_guard(output: &String) -> String {
+    let file_name = output.rsplit('/').next().unwrap_or(output);
+    let mut guard = String::new();
+    for c in file_name.chars() {
+        if c.is_ascii_alphanumeric() {
+            guard.push(c.to_ascii_uppercase());
+        } else {
+            guard.push('_');
+        }
+    }
+    if guard.starts_with(|c: char| c.is_ascii_digit()) {
+        guard.insert(0, '_');
+    }
+    return guard;
+}
+
+pub fn generate_header(generator: &mut Generator) -> String {
+    // Current node = Program
+    // Children: { function | struct | alias | ... }
+    let mut structures: Vec<(String, usize)> = Vec::new();
+    let mut functions: Vec<usize> = Vec::new();
+    if generator.down() {
+        loop {
+            match generator.current() {
+                Construct::Structure(name, _, _) => structures.push((String::clone(name), generator.get_ref_id())),
+                Construct::Function(_, Linkage::Export) => functions.push(generator.get_ref_id()),
+                _ => (),
+            }
+            if !generator.next() {
+                break;
+            }
+        }
+        generator.up();
+    }
+
+    let guard = get_include_guard(&generator.options.output);
+    let mut header = String::new();
+    writeln!(header, "// Generated from {}", generator.options.input).unwrap();
+    writeln!(header, "#ifndef {}", guard).unwrap();
+    writeln!(header, "#define {}", guard).unwrap();
+    writeln!(header).unwrap();
+    writeln!(header, "#include <stdbool.h>").unwrap();
+    writeln!(header, "#include <stddef.h>").unwrap();
+    writeln!(header, "#include <stdint.h>").unwrap();
+    writeln!(header).unwrap();
+
+    // Declare every struct first, so pointers to them can be used anywhere
+    if !structures.is_empty() {
+        for (name, _) in &structures {
+            writeln!(header, "struct {};", name).unwrap();
+        }
+        writeln!(header).unwrap();
+    }
+    let mut written: Vec<usize> = Vec::new();
+    for (_, structure_i) in &structures {
+        write_structure(generator, *structure_i, &mut written, &mut header);
+    }
+
+    for function_i in functions {
+        generator.down_ref(function_i);
+        write_function(generator, &mut header);
+        generator.up();
+    }
+    if !header.ends_with("\n\n") {
+        writeln!(header).unwrap();
+    }
+    writeln!(header, "#endif").unwrap();
+    return header;
+}
@@ -4,6 +4,10 @@ mod function;
 mod statement;
 mod expression;
 mod datatype;
+mod variable;
+mod structure;
+mod drop;
+mod header;
 
 use super::generator;
 use super::instructions;
@@ -12,3 +16,5 @@ use super::resolve;
 
 pub use content::generate_content;
 pub use statement::generate_statement;
+pub use header::generate_header;
+pub use function::label_symbols;
@@ -6,40 +6,350 @@ use super::instructions::*;
 use super::content::generate_content;
 use super::resolve::resolve_content;
 use super::expression::generate_expression_lvalue;
+use super::expression::generate_expression_operand;
+use super::expression::generate_expression_call;
 use super::expression::generate_expression_rvalue;
+use super::expression::generate_expression_condition;
+use super::expression::new_temp;
 use super::datatype::validate_datatypes;
+use super::datatype::get_datatype_info;
+use super::datatype::DatatypeInfo;
+use super::drop::find_symbol_drop;
+use super::drop::generate_drop;
+use super::generator::Deferred;
 
 
-fn generate_statement_block(generator: &mut Generator) {
-    // Children: block
+// Generate deferred statements and drops for leaving every scope down to
+// depth. Variables that have been moved out of aren't dropped.
+pub fn generate_defers(generator: &mut Generator, depth: usize) {
+    for deferred in generator.get_defers(depth) {
+        match deferred {
+            Deferred::Statement(node_i) => {
+                generator.down_ref(node_i);
+                generate_statement(generator);
+                generator.up();
+            },
+            Deferred::Drop(mapping_i) => {
+                if let Some((name, symbol_i)) = generator.get_drop_mapping(mapping_i) {
+                    generate_drop(generator, &name, symbol_i);
+                }
+            },
+        }
+    }
+}
+
+fn generate_block(generator: &mut Generator) {
+    // Current node = block
     generator.increase_scope();
-    generator.down();
     resolve_content(generator);
     generate_content(generator);
-    generator.up();
+    // Falling through the end of the block
+    if generator.is_reachable() {
+        let depth = generator.get_scope_depth() - 1;
+        generate_defers(generator, depth);
+    }
     generator.decrease_scope();
 }
 
+fn generate_statement_block(generator: &mut Generator) {
+    // Children: block
+    generator.down();
+    generate_block(generator);
+    generator.up();
+}
+
+// Jump to label if the condition is false
+fn generate_branch_false(generator: &mut Generator, condition: Operand, label: &String) {
+    generator.add_element(Element::Instruction(Instruction::Branch(Condition::Equal)));
+    generator.add_element(Element::Operand(condition));
+    generator.add_element(Element::Operand(Operand::Constant(Constant::Int(0))));
+    generator.add_element(Element::Operand(Operand::Label(String::clone(label))));
+}
+
+fn generate_statement_conditional(generator: &mut Generator) {
+    // Children: expression , block , [ block | statement(conditional) ]
+    let skip = generator.new_label();
+    generator.down();
+    let condition = generate_expression_condition(generator);
+    generate_branch_false(generator, condition, &skip);
+    let before = generator.get_flow();
+
+    generator.next();
+    generate_block(generator);
+    let taken = if generator.is_reachable() { Some(generator.get_flow()) } else { None };
+
+    // Without an else branch, skipping the block goes straight to the end
+    let skipped = if generator.next() {
+        let end = generator.new_label();
+        if taken.is_some() {
+            generator.add_element(Element::Instruction(Instruction::Jump));
+            generator.add_element(Element::Operand(Operand::Label(String::clone(&end))));
+        }
+        generator.add_element(Element::Instruction(Instruction::Label));
+        generator.add_element(Element::Operand(Operand::Label(skip)));
+        generator.set_flow(&before);
+        generator.set_reachable(true);
+        match generator.current() {
+            Construct::Statement(_) => generate_statement(generator),
+            _ => generate_block(generator),
+        }
+        let skipped = if generator.is_reachable() { Some(generator.get_flow()) } else { None };
+        generator.add_element(Element::Instruction(Instruction::Label));
+        generator.add_element(Element::Operand(Operand::Label(end)));
+        skipped
+    } else {
+        generator.add_element(Element::Instruction(Instruction::Label));
+        generator.add_element(Element::Operand(Operand::Label(skip)));
+        Some(before)
+    };
+    generator.up();
+
+    // Paths join at the end, unless neither reaches it
+    let flow = match (taken, skipped) {
+        (Some(taken), Some(skipped)) => generator.merge_flow(&taken, &skipped),
+        (Some(flow), None) | (None, Some(flow)) => flow,
+        (None, None) => {
+            generator.set_reachable(false);
+            return;
+        },
+    };
+    generator.set_flow(&flow);
+    generator.set_reachable(true);
+}
+
+fn generate_statement_while(generator: &mut Generator) {
+    // Children: expression , block
+    let start = generator.new_label();
+    let end = generator.new_label();
+
+    generator.add_element(Element::Instruction(Instruction::Label));
+    generator.add_element(Element::Operand(Operand::Label(String::clone(&start))));
+    generator.set_reachable(true);
+
+    generator.push_loop(&start, &end);
+    generator.down();
+    let condition = generate_expression_condition(generator);
+    generate_branch_false(generator, condition, &end);
+    // The condition being false is a path out of the loop
+    generator.push_loop_exit();
+    generator.next();
+    generate_block(generator);
+    generator.up();
+    if generator.is_reachable() {
+        generator.check_loop_moves();
+        generator.add_element(Element::Instruction(Instruction::Jump));
+        generator.add_element(Element::Operand(Operand::Label(start)));
+    }
+    generator.pop_loop();
+
+    generator.add_element(Element::Instruction(Instruction::Label));
+    generator.add_element(Element::Operand(Operand::Label(end)));
+    generator.set_reachable(true);
+}
+
+fn generate_statement_loop(generator: &mut Generator) {
+    // Children: block
+    let start = generator.new_label();
+    let end = generator.new_label();
+
+    generator.add_element(Element::Instruction(Instruction::Label));
+    generator.add_element(Element::Operand(Operand::Label(String::clone(&start))));
+    generator.set_reachable(true);
+
+    generator.push_loop(&start, &end);
+    generate_statement_block(generator);
+    if generator.is_reachable() {
+        generator.check_loop_moves();
+        generator.add_element(Element::Instruction(Instruction::Jump));
+        generator.add_element(Element::Operand(Operand::Label(start)));
+    }
+    generator.pop_loop();
+
+    generator.add_element(Element::Instruction(Instruction::Label));
+    generator.add_element(Element::Operand(Operand::Label(end)));
+    generator.set_reachable(true);
+}
+
+fn generate_statement_control(generator: &mut Generator, control: &Control) {
+    let current = match generator.current_loop() {
+        Some(current) => current,
+        None => panic!("{} outside of a loop", control),
+    };
+    generate_defers(generator, current.depth);
+    let label = match control {
+        Control::Break => {
+            if generator.is_reachable() {
+                generator.push_loop_exit();
+            }
+            current.end
+        },
+        Control::Continue => {
+            generator.check_loop_moves();
+            current.start
+        },
+    };
+    generator.add_element(Element::Instruction(Instruction::Jump));
+    generator.add_element(Element::Operand(Operand::Label(label)));
+    generator.set_reachable(false);
+}
+
+// Deferred statements are generated at every jump out of their block, so
+// one that jumps out itself, with return or break or continue outside of a
+// loop within it, would be generated again without end.
+fn check_deferred(generator: &mut Generator, in_loop: bool) {
+    // Current node = any node within a deferred statement
+    let in_loop = match generator.current() {
+        Construct::Function(..) => return, // Has its own returns
+        Construct::Statement(Statement::Return) => panic!("Return in a deferred statement"),
+        Construct::Statement(Statement::Control(control)) if !in_loop => {
+            panic!("{} out of a deferred statement", control);
+        },
+        Construct::Statement(Statement::Loop) | Construct::Statement(Statement::While) => true,
+        _ => in_loop,
+    };
+    if !generator.down() {
+        return;
+    }
+    loop {
+        check_deferred(generator, in_loop);
+        if !generator.next() {
+            break;
+        }
+    }
+    generator.up();
+}
+
+fn generate_statement_defer(generator: &mut Generator) {
+    // Children: statement
+    generator.down();
+    check_deferred(generator, false);
+    generator.push_defer();
+    generator.up();
+}
+
+fn generate_statement_expression(generator: &mut Generator) {
+    // Children: expression
+    // Any returned value is discarded
+    generator.down();
+    match generator.current() {
+        Construct::Expression(Expression::Syscall) => {
+            generate_expression_rvalue(generator);
+        },
+        _ => {
+            generate_expression_call(generator);
+        },
+    }
+    generator.up();
+}
+
 fn generate_statement_assign(generator: &mut Generator) {
     // Current node = Statement::Assign
     // Children: expression1, expression2
     generator.down();
-    let (instruction, dest_symbol, dest_datatype_i) = generate_expression_lvalue(generator);
+    let (instruction, dest_symbol, dest_datatype_i, mutable) = generate_expression_lvalue(generator);
     generator.next();
-    let (src_symbol, src_datatype_i) = generate_expression_rvalue(generator);
+    let (src, src_datatype_i) = generate_expression_operand(generator, dest_datatype_i);
     generator.up();
 
-    if !validate_datatypes(generator, dest_datatype_i, src_datatype_i, true, false) {
+    if !validate_datatypes(generator, dest_datatype_i, src_datatype_i, false, false) {
         panic!("Datatypes don't match in assign statement");
     }
+    if !mutable {
+        panic!("Assigning to a value that isn't mutable");
+    }
+
+    // Assigning to a variable that needs dropping replaces its value, so
+    // drop the old value if it has one
+    if let Instruction::Move = instruction {
+        let name = String::clone(&dest_symbol.name);
+        if find_symbol_drop(generator, &name).is_some() {
+            if !generator.is_symbol_moved(&name) {
+                let symbol_i = generator.find_symbol(&name).expect("Failed to resolve symbol");
+                generate_drop(generator, &name, symbol_i);
+            }
+            generator.set_symbol_moved(&name, false);
+        }
+    }
+
+    // Constants don't have a size, so one stored through a pointer is given
+    // the size of the value it replaces first
+    let src = match (instruction, src) {
+        (Instruction::Store, Operand::Constant(constant)) => {
+            generator.down_ref(dest_datatype_i);
+            let info = get_datatype_info(generator);
+            generator.up();
+            let value = new_temp(generator, &info);
+            generator.add_element(Element::Instruction(Instruction::Move));
+            generator.add_element(Element::Operand(Operand::Constant(constant)));
+            generator.add_element(Element::Operand(Operand::Symbol(Symbol::clone(&value))));
+            Operand::Symbol(value)
+        },
+        (_, src) => src,
+    };
 
     generator.add_element(Element::Instruction(instruction)); // Move, load or store
-    generator.add_element(Element::Operand(Operand::Symbol(src_symbol)));
+    generator.add_element(Element::Operand(src));
     generator.add_element(Element::Operand(Operand::Symbol(dest_symbol)));
 }
 
 fn generate_statement_return(generator: &mut Generator) {
+    // Children: [ expression ]
+    let returned = if generator.down() {
+        let datatype_i = match generator.get_return_datatype(0) {
+            Some(datatype_i) => datatype_i,
+            None => panic!("Returning a value from a function without a return type"),
+        };
+        let (src, src_datatype_i) = generate_expression_operand(generator, datatype_i);
+        generator.up();
+
+        if !validate_datatypes(generator, datatype_i, src_datatype_i, false, false) {
+            panic!("Datatypes don't match in return statement");
+        }
+
+        generator.down_ref(datatype_i);
+        let info = get_datatype_info(generator);
+        generator.up();
+        let location = PassLocation {
+            index: 0,
+            size: info.size,
+            regtype: info.regtype,
+            signed: info.signed,
+        };
+        Some((src, location))
+    } else {
+        if generator.get_return_datatype(0).is_some() {
+            panic!("Return statement is missing a value");
+        }
+        None
+    };
+
+    // The returned value is evaluated before deferred statements, so copy
+    // it in case they change it.
+    let depth = generator.get_function_scope_depth();
+    let returned = match returned {
+        Some((Operand::Symbol(symbol), location)) if !generator.get_defers(depth).is_empty() => {
+            let copy = new_temp(generator, &DatatypeInfo {
+                size: symbol.size,
+                alignment: symbol.alignment,
+                regtype: symbol.regtype,
+                signed: false,
+            });
+            generator.add_element(Element::Instruction(Instruction::Move));
+            generator.add_element(Element::Operand(Operand::Symbol(symbol)));
+            generator.add_element(Element::Operand(Operand::Symbol(Symbol::clone(&copy))));
+            Some((Operand::Symbol(copy), location))
+        },
+        returned => returned,
+    };
+    generate_defers(generator, depth);
+
+    if let Some((src, location)) = returned {
+        generator.add_element(Element::Instruction(Instruction::SetReturned));
+        generator.add_element(Element::Operand(src));
+        generator.add_element(Element::Operand(Operand::PassLocation(location)));
+    }
     generator.add_element(Element::Instruction(Instruction::Return));
+    generator.set_reachable(false);
 }
 
 pub fn generate_statement(generator: &mut Generator) {
@@ -48,7 +358,15 @@ pub fn generate_statement(generator: &mut Generator) {
             Statement::Block => generate_statement_block(generator),
             Statement::Assign => generate_statement_assign(generator),
             Statement::Return => generate_statement_return(generator),
-            _ => panic!("{} generation not implemented yet", statement),
+            Statement::Loop => generate_statement_loop(generator),
+            Statement::While => generate_statement_while(generator),
+            Statement::Conditional => generate_statement_conditional(generator),
+            Statement::Control(control) => {
+                let control = Control::clone(control);
+                generate_statement_control(generator, &control);
+            },
+            Statement::Defer => generate_statement_defer(generator),
+            Statement::Expression => generate_statement_expression(generator),
         }
     } else {
         panic!("Node at generate_statement isn't a statement");
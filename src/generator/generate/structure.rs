@@ -0,0 +1,89 @@
+
+use super::construct::*;
+use super::generator::Generator;
+
+use super::datatype::skip_qualifiers;
+use super::function::generate_function;
+
+
+// drop must take a pointer to the struct, and return nothing
+fn check_drop_signature(generator: &mut Generator, structure_i: usize, structure: &String) {
+    // Current node = Function
+    // Children: { argument } , { returned } , block
+    generator.down();
+    let mut arguments: usize = 0;
+    loop {
+        match generator.current() {
+            Construct::Argument(_) => {
+                arguments+=1;
+                generator.down();
+                let valid = match generator.current() {
+                    Construct::Datatype(Datatype::Pointer) => {
+                        generator.down();
+                        skip_qualifiers(generator);
+                        let valid = match generator.current() {
+                            Construct::Datatype(Datatype::Terminal) => {
+                                generator.down();
+                                skip_qualifiers(generator);
+                                let valid = match generator.current() {
+                                    Construct::Reference(ref_i) => *ref_i == structure_i,
+                                    _ => false,
+                                };
+                                generator.up();
+                                valid
+                            },
+                            _ => false,
+                        };
+                        generator.up();
+                        valid
+                    },
+                    _ => false,
+                };
+                generator.up();
+                if !valid {
+                    panic!("Argument of {}'s drop function must be a pointer to {}", structure, structure);
+                }
+            },
+            Construct::Returned => panic!("Drop function of {} can't return anything", structure),
+            _ => break,
+        }
+        if !generator.next() {
+            break;
+        }
+    }
+    generator.up();
+    if arguments != 1 {
+        panic!("Drop function of {} must take a single argument", structure);
+    }
+}
+
+pub fn generate_structure(generator: &mut Generator) {
+    // Current node = Structure
+    // Children: { attribute } , { member | function }
+    let structure = match generator.current() {
+        Construct::Structure(name, _, _) => String::clone(name),
+        _ => panic!("Node at generate_structure() is not a structure"),
+    };
+    let structure_i = generator.get_ref_id();
+    if !generator.down() {
+        return;
+    }
+    let mut methods: Vec<String> = Vec::new();
+    loop {
+        if let Construct::Function(name, _) = generator.current() {
+            let name = String::clone(name);
+            if methods.contains(&name) {
+                panic!("Struct {} declares more than one function {}", structure, name);
+            }
+            if name == "drop" {
+                check_drop_signature(generator, structure_i, &structure);
+            }
+            generate_function(generator);
+            methods.push(name);
+        }
+        if !generator.next() {
+            break;
+        }
+    }
+    generator.up();
+}
@@ -0,0 +1,117 @@
+
+use super::construct::*;
+use super::generator::Generator;
+use super::instructions::*;
+
+use super::datatype::get_datatype_info;
+use super::datatype::validate_datatypes;
+use super::resolve::evaluate_constant;
+use super::resolve::get_datatype_primitive;
+use super::expression::generate_expression_operand;
+use super::drop::find_drop_function;
+use super::function::get_global_label;
+
+
+fn generate_global(generator: &mut Generator, name: &String) {
+    // Current node = Variable
+    // Children: datatype , [ expression ]
+    let label = get_global_label(generator, generator.get_ref_id());
+    generator.down();
+    if find_drop_function(generator, generator.get_ref_id()).is_some() {
+        panic!("Global variable {} can't be a struct with a drop function", name);
+    }
+    let info = get_datatype_info(generator);
+    let primitive = get_datatype_primitive(generator);
+    let defined = generator.next() && match generator.current() {
+        Construct::Expression(Expression::Undefined) => false,
+        // Static storage starts zeroed, which is null
+        Construct::Expression(Expression::Null) => match info.regtype {
+            Regtype::Pointer => false,
+            _ => panic!("Global variable {} can't be null, it isn't a pointer", name),
+        },
+        _ => true,
+    };
+    let initial = if defined {
+        let primitive = match primitive {
+            Some(primitive) => primitive,
+            None => panic!("Global variable {} can't be given an initial value, only primitives can", name),
+        };
+        match evaluate_constant(generator, &primitive) {
+            Ok(constant) => Some(constant),
+            Err(error) => panic!("Invalid initial value for global variable {}: {}", name, error),
+        }
+    } else {
+        None
+    };
+    generator.up();
+
+    generator.add_element(Element::Instruction(Instruction::Static));
+    generator.add_element(Element::Operand(Operand::Label(label)));
+    generator.add_element(Element::Operand(Operand::Integer(info.size as i64)));
+    generator.add_element(Element::Operand(Operand::Integer(info.alignment as i64)));
+    if let Some(constant) = initial {
+        generator.add_element(Element::Operand(Operand::Constant(constant)));
+    }
+}
+
+fn generate_local(generator: &mut Generator, name: &String) {
+    // Current node = Variable
+    // Children: datatype , [ expression ]
+    generator.down();
+    let datatype_i = generator.get_ref_id();
+    let info = get_datatype_info(generator);
+    let droppable = find_drop_function(generator, datatype_i).is_some();
+    if !generator.next() {
+        // No initial value, must be assigned before it's read or dropped
+        generator.up();
+        generator.set_symbol_assigned(name, false);
+        if droppable {
+            generator.set_symbol_moved(name, true);
+            generator.push_drop(name);
+        }
+        return;
+    }
+    if let Construct::Expression(Expression::Undefined) = generator.current() {
+        // Nothing to generate, whatever is on the stack is the value
+        generator.up();
+        if droppable {
+            panic!("{} has a drop function, so can't be undefined", name);
+        }
+        return;
+    }
+    let (src, src_datatype_i) = generate_expression_operand(generator, datatype_i);
+    generator.up();
+
+    // Initialising doesn't require the variable to be mutable
+    if !validate_datatypes(generator, datatype_i, src_datatype_i, false, false) {
+        panic!("Datatypes don't match in initialisation of {}", name);
+    }
+
+    let dest = Symbol {
+        name: String::clone(name),
+        version: generator.get_symbol_version(name, true),
+        declaration: generator.find_symbol(name).unwrap(),
+        size: info.size,
+        alignment: info.alignment,
+        regtype: info.regtype,
+        global: false,
+    };
+    generator.add_element(Element::Instruction(Instruction::Move));
+    generator.add_element(Element::Operand(src));
+    generator.add_element(Element::Operand(Operand::Symbol(dest)));
+    if droppable {
+        generator.push_drop(name);
+    }
+}
+
+pub fn generate_variable(generator: &mut Generator) {
+    let name = match generator.current() {
+        Construct::Variable(name) => String::clone(name),
+        _ => panic!("Node at generate_variable() is not a variable"),
+    };
+    if generator.is_global_scope() {
+        generate_global(generator, &name);
+    } else {
+        generate_local(generator, &name);
+    }
+}
@@ -4,6 +4,8 @@ use crate::parser::ast::Ast;
 use crate::parser::construct::Construct;
 use std::collections::HashMap;
 use super::instructions::*;
+use crate::options::Options;
+use crate::lexer::token::Location;
 
 // Mappings: [ main, func1, global_var, argc, argv, x, ... ]
 //           <--  scope 0 ----------> < --- scope 1 --> etc
@@ -22,6 +24,15 @@ struct Mapping {
     // Depth of internal functions on creation. = 0 if unrestricted access to internal functions.
     pub function_depth: usize,
     pub version: usize,
+    // Set when the value has been moved out, or hasn't been given one yet,
+    // so it mustn't be used or dropped.
+    pub moved: bool,
+    // Whether the symbol has been written to on every path to the code
+    // being generated, so can be read from.
+    pub assigned: bool,
+    // Fields of a struct written to on every path, see get_member_fields(),
+    // so a value can be given one member at a time. All set once assigned.
+    pub fields: u64,
 }
 
 pub struct Generator<'a> {
@@ -34,10 +45,60 @@ pub struct Generator<'a> {
     function_stack: Vec<usize>, // Stack of index within instructions for function start
     return_datatypes: Vec<usize>,
     temp_version: usize,
+    defers: Vec<Vec<Deferred>>, // stack of deferred statements and drops for each scope
+    function_scopes: Vec<usize>, // stack of the scope depth of each function
+    enclosing: Vec<FunctionState>, // state of each function a function is nested in
+    loops: Vec<Loop>,
+    label_count: usize,
+    reachable: bool, // false after a jump, until the next label
+    pub labels: HashMap<usize, String>, // function or global variable node -> mangled label
+    pub options: Options,
+}
+
+// Work to do when leaving a scope. Either a deferred statement node, or
+// dropping a variable, given by the index of its mapping.
+#[derive(Clone, Copy)]
+pub enum Deferred {
+    Statement(usize),
+    Drop(usize),
+}
+
+// State of a mapping that depends on the path taken to the code being
+// generated, so has to be merged where paths join.
+#[derive(Clone, Copy)]
+pub struct Flow {
+    moved: bool,
+    assigned: bool,
+    fields: u64,
+}
+
+// State belonging to the function being generated, put aside while
+// generating a function nested in it.
+struct FunctionState {
+    return_datatypes: Vec<usize>,
+    temp_version: usize,
+    loops: Vec<Loop>,
+    reachable: bool,
+}
+
+// Labels to jump to for continue and break, and the scope depth of the
+// loop, so deferred statements in the loop body can be generated.
+// Live is the mappings outside the loop that hold a value on entry, which
+// must still hold one when jumping back to the start.
+// Exit is the state of the mappings outside the loop, the first mappings
+// of them, merged over every path out of it, so is the state after it.
+#[derive(Clone)]
+pub struct Loop {
+    pub start: String,
+    pub end: String,
+    pub depth: usize,
+    pub live: Vec<usize>,
+    pub mappings: usize,
+    pub exit: Option<Vec<Flow>>,
 }
 
 impl<'a> Generator<'a> {
-    pub fn new(ast: &'a mut Ast, instructions: &'a mut Vec<Element>) -> Generator<'a> {
+    pub fn new(ast: &'a mut Ast, instructions: &'a mut Vec<Element>, options: &Options) -> Generator<'a> {
         let start_i = ast.nodes.len() - 1;
         let mut generator = Generator {
             ast: ast,
@@ -49,6 +110,14 @@ impl<'a> Generator<'a> {
             function_stack: Vec::new(),
             return_datatypes: Vec::new(),
             temp_version: 0,
+            defers: Vec::new(),
+            function_scopes: Vec::new(),
+            enclosing: Vec::new(),
+            loops: Vec::new(),
+            label_count: 0,
+            reachable: true,
+            labels: HashMap::new(),
+            options: Options::clone(options),
         };
         generator.tree_stack.push(start_i);
         return generator;
@@ -120,14 +189,40 @@ impl<'a> Generator<'a> {
         }
     }
 
+    // Symbols added at the program scope, which are given static storage
+    pub fn is_global_symbol(&self, name: &String) -> bool {
+        match self.table.get(name) {
+            Some(index) => match self.scope.first() {
+                Some(start) => *index < *start,
+                None => true,
+            },
+            None => false,
+        }
+    }
+
     pub fn get_symbol_version(&mut self, name: &String, increment: bool) -> usize {
+        return self.get_member_version(name, increment, None);
+    }
+
+    // Version of a symbol accessed through a member, given by its fields
+    // and all the fields of the symbol. Writing the member only assigns
+    // the symbol once every field has been written.
+    pub fn get_member_version(&mut self, name: &String, increment: bool, fields: Option<(u64, u64)>) -> usize {
         match self.table.get(name) {
             Some(index) => {
                 let mapping = &mut self.mappings[*index];
                 if mapping.function_depth == 0 || mapping.function_depth == self.function_stack.len() {
                     let version = mapping.version;
+                    let (member, all) = fields.unwrap_or((u64::MAX, u64::MAX));
                     if increment {
                         mapping.version+=1;
+                        mapping.fields |= member;
+                        if mapping.fields & all == all {
+                            mapping.fields = u64::MAX;
+                        }
+                        mapping.assigned = mapping.fields == u64::MAX;
+                    } else if mapping.fields & member != member {
+                        panic!("{} is read before being assigned on some path. Initialise it with = undefined if this is intended", name);
                     }
                     return version;
                 } else {
@@ -151,18 +246,64 @@ impl<'a> Generator<'a> {
             name: String::clone(name),
             function_depth: if block_function_access {self.function_stack.len()} else {0},
             version: 0,
+            moved: false,
+            assigned: true,
+            fields: u64::MAX,
         };
         self.table.insert(String::clone(name), self.mappings.len());
         self.mappings.push(mapping);
     }
 
+    pub fn is_symbol_moved(&self, name: &String) -> bool {
+        match self.table.get(name) {
+            Some(index) => return self.mappings[*index].moved,
+            None => panic!("Tried to call is_symbol_moved() on unknown symbol {}", name),
+        }
+    }
+
+    pub fn set_symbol_moved(&mut self, name: &String, moved: bool) {
+        match self.table.get(name) {
+            Some(index) => self.mappings[*index].moved = moved,
+            None => panic!("Tried to call set_symbol_moved() on unknown symbol {}", name),
+        }
+    }
+
+    pub fn is_symbol_assigned(&self, name: &String) -> bool {
+        match self.table.get(name) {
+            Some(index) => return self.mappings[*index].assigned,
+            None => panic!("Tried to call is_symbol_assigned() on unknown symbol {}", name),
+        }
+    }
+
+    pub fn set_symbol_assigned(&mut self, name: &String, assigned: bool) {
+        match self.table.get(name) {
+            Some(index) => {
+                let mapping = &mut self.mappings[*index];
+                mapping.assigned = assigned;
+                mapping.fields = if assigned { u64::MAX } else { 0 };
+            },
+            None => panic!("Tried to call set_symbol_assigned() on unknown symbol {}", name),
+        }
+    }
+
+    // Name and node of a mapping, if it still holds a value to drop
+    pub fn get_drop_mapping(&self, mapping_i: usize) -> Option<(String, usize)> {
+        let mapping = &self.mappings[mapping_i];
+        if mapping.moved {
+            return None;
+        }
+        return Some((String::clone(&mapping.name), mapping.node_i));
+    }
+
     pub fn increase_scope(&mut self) {
         self.scope.push(self.mappings.len());
+        self.defers.push(Vec::new());
     }
 
     pub fn decrease_scope(&mut self) {
         let start = self.scope.pop()
             .expect("Decreasing scope before increasing it");
+        self.defers.pop();
         while self.mappings.len() > start {
             let mapping = self.mappings.pop()
                 .expect("Shouldn't be here");
@@ -177,15 +318,33 @@ impl<'a> Generator<'a> {
         }
     }
 
+    // Outside of any function, where variables are given static storage
+    pub fn is_global_scope(&self) -> bool {
+        return self.function_stack.is_empty();
+    }
+
     pub fn increase_scope_function(&mut self) {
         self.increase_scope();
+        self.function_scopes.push(self.scope.len() - 1);
         self.function_stack.push(self.instructions.len());
-        self.return_datatypes.clear();
+        self.enclosing.push(FunctionState {
+            return_datatypes: mem::take(&mut self.return_datatypes),
+            temp_version: self.temp_version,
+            loops: mem::take(&mut self.loops),
+            reachable: self.reachable,
+        });
         self.temp_version = 0;
+        self.reachable = true;
     }
 
     pub fn decrease_scope_function(&mut self) {
         self.decrease_scope();
+        self.function_scopes.pop();
+        let state = self.enclosing.pop().expect("Invalid enclosing function state");
+        self.return_datatypes = state.return_datatypes;
+        self.temp_version = state.temp_version;
+        self.loops = state.loops;
+        self.reachable = state.reachable;
         let internal = self.function_stack.pop().expect("Invalid function_stack");
         let parent = match self.function_stack.pop() {
             Some(parent) => parent,
@@ -196,23 +355,7 @@ impl<'a> Generator<'a> {
         // Want:
         // [<- internal -><- parent -> (can continue with parent) ]
         let internal_size = self.instructions.len() - internal;
-        let parent_size = internal - parent;
-
-        // Easiest way to do this is:
-        // [<- parent -><- internal ->]
-        // [<- parent ->--------------<- internal ->]
-        // [--------------<- parent -><- internal ->]
-        // [<- internal -><- parent ->]
-        for i in 0..internal_size {
-            let element = mem::take(&mut self.instructions[internal+i]);
-            self.instructions.push(element);
-        }
-        for i in parent_size-1..0 { // Reverse order to avoid overwriting self
-            self.instructions[internal-parent_size+i] = mem::take(&mut self.instructions[parent+i]);
-        }
-        for i in internal_size-1..0 {
-            self.instructions[parent+i] = self.instructions.pop().expect("");
-        }
+        self.instructions[parent..].rotate_right(internal_size);
 
         let new_parent = parent + internal_size;
         self.function_stack.push(new_parent);
@@ -224,6 +367,43 @@ impl<'a> Generator<'a> {
         self.ast.nodes[node_i].construct = Construct::clone(construct);
     }
 
+    // Replace the current node with a terminal construct, discarding its
+    // children. Used to store the result of evaluating a node.
+    pub fn fold_construct(&mut self, construct: &Construct) {
+        let node_i = *self.tree_stack.last()
+            .expect("Tried to call fold_construct() on an empty tree_stack");
+        self.ast.nodes[node_i].construct = Construct::clone(construct);
+        self.ast.nodes[node_i].child = None;
+    }
+
+    // Add a node that isn't part of the tree, for datatypes that are
+    // implied by an expression. Returns the node index, to refer to it.
+    pub fn add_node(&mut self, construct: &Construct, children: &[usize]) -> usize {
+        let node_i = self.ast.nodes.len();
+        let location = self.get_location();
+        self.ast.set_node(node_i, construct, children, location);
+        return node_i;
+    }
+
+    // Where the current node is in the source file
+    pub fn get_location(&self) -> Location {
+        let node_i = *self.tree_stack.last()
+            .expect("Tried to call get_location() on an empty tree_stack");
+        return self.ast.nodes[node_i].location;
+    }
+
+    // Errors are reported at the current node
+    pub fn error(&self, message: &str) -> ! {
+        panic!("{}:{}: {}", self.options.input, self.get_location(), message);
+    }
+
+    // Replace the children of the current node, in the given order
+    pub fn set_children(&mut self, children: &[usize]) {
+        let node_i = *self.tree_stack.last()
+            .expect("Tried to call set_children() on an empty tree_stack");
+        self.ast.set_children(node_i, children);
+    }
+
     pub fn get_ref_id(&self) -> usize {
         return *self.tree_stack.last()
             .expect("Tried to call get_ref_id() on an empty tree_stack");
@@ -239,8 +419,155 @@ impl<'a> Generator<'a> {
         self.return_datatypes.push(node_i);
     }
 
-    pub fn get_return_datatype(&self, index: usize) -> usize {
-        return self.return_datatypes[index];
+    pub fn get_return_datatype(&self, index: usize) -> Option<usize> {
+        return self.return_datatypes.get(index).copied();
+    }
+
+    // Current node = statement to defer until the current scope is exited
+    pub fn push_defer(&mut self) {
+        let node_i = *self.tree_stack.last().expect("");
+        self.defers.last_mut()
+            .expect("Deferring a statement outside of any scope")
+            .push(Deferred::Statement(node_i));
+    }
+
+    // Drop the symbol when the current scope is exited, unless it has been
+    // moved by then
+    pub fn push_drop(&mut self, name: &String) {
+        let mapping_i = *self.table.get(name)
+            .expect("Tried to call push_drop() on unknown symbol");
+        self.defers.last_mut()
+            .expect("Dropping a symbol outside of any scope")
+            .push(Deferred::Drop(mapping_i));
+    }
+
+    // Deferred statements and drops to generate when leaving every scope
+    // from the current scope down to the given depth, in the order to
+    // generate them.
+    pub fn get_defers(&self, depth: usize) -> Vec<Deferred> {
+        let mut defers: Vec<Deferred> = Vec::new();
+        for scope in self.defers[depth..].iter().rev() {
+            for deferred in scope.iter().rev() {
+                defers.push(*deferred);
+            }
+        }
+        return defers;
+    }
+
+    pub fn get_scope_depth(&self) -> usize {
+        return self.scope.len();
+    }
+
+    pub fn get_function_scope_depth(&self) -> usize {
+        return *self.function_scopes.last()
+            .expect("Tried to call get_function_scope_depth() outside of a function");
+    }
+
+    pub fn get_flow(&self) -> Vec<Flow> {
+        return self.mappings.iter()
+            .map(|mapping| Flow { moved: mapping.moved, assigned: mapping.assigned, fields: mapping.fields })
+            .collect();
+    }
+
+    pub fn set_flow(&mut self, flow: &Vec<Flow>) {
+        for (mapping, flow) in self.mappings.iter_mut().zip(flow.iter()) {
+            mapping.moved = flow.moved;
+            mapping.assigned = flow.assigned;
+            mapping.fields = flow.fields;
+        }
+    }
+
+    // State where two paths join. Symbols are only assigned if they are
+    // assigned on both paths. Whether a value needs dropping has to be known
+    // when generating, so it must be moved on both paths or neither.
+    pub fn merge_flow(&self, lhs: &Vec<Flow>, rhs: &Vec<Flow>) -> Vec<Flow> {
+        let mut flow: Vec<Flow> = Vec::new();
+        for (mapping_i, (lhs, rhs)) in lhs.iter().zip(rhs.iter()).enumerate() {
+            if lhs.moved != rhs.moved {
+                panic!("Value of {} is moved on some paths but not others", self.mappings[mapping_i].name);
+            }
+            flow.push(Flow {
+                moved: lhs.moved,
+                assigned: lhs.assigned && rhs.assigned,
+                fields: lhs.fields & rhs.fields,
+            });
+        }
+        return flow;
+    }
+
+    pub fn push_loop(&mut self, start: &String, end: &String) {
+        let live = (0..self.mappings.len())
+            .filter(|mapping_i| !self.mappings[*mapping_i].moved)
+            .collect();
+        self.loops.push(Loop {
+            start: String::clone(start),
+            end: String::clone(end),
+            depth: self.scope.len(),
+            live: live,
+            mappings: self.mappings.len(),
+            exit: None,
+        });
+    }
+
+    // After the loop, the state is merged from every path out of it. Without
+    // any, the end is never reached.
+    pub fn pop_loop(&mut self) {
+        let current = self.loops.pop().expect("Tried to call pop_loop() outside of a loop");
+        if let Some(exit) = current.exit {
+            self.set_flow(&exit);
+        }
+    }
+
+    // Current state is a path out of the current loop. Only mappings from
+    // outside the loop are still around after it.
+    pub fn push_loop_exit(&mut self) {
+        let current = self.loops.last()
+            .expect("Tried to call push_loop_exit() outside of a loop");
+        let mut flow = self.get_flow();
+        flow.truncate(current.mappings);
+        let exit = match &current.exit {
+            Some(exit) => self.merge_flow(exit, &flow),
+            None => flow,
+        };
+        self.loops.last_mut().unwrap().exit = Some(exit);
+    }
+
+    // Check values that were live on entry to the current loop haven't been
+    // moved, before jumping back to the start.
+    pub fn check_loop_moves(&self) {
+        let current = self.loops.last()
+            .expect("Tried to call check_loop_moves() outside of a loop");
+        for mapping_i in &current.live {
+            let mapping = &self.mappings[*mapping_i];
+            if mapping.moved {
+                panic!("Value of {} is moved in a previous iteration of the loop", mapping.name);
+            }
+        }
+    }
+
+    pub fn current_loop(&self) -> Option<Loop> {
+        // Loops in an enclosing function can't be broken out of
+        match (self.loops.last(), self.function_scopes.last()) {
+            (Some(current), Some(function_depth)) if current.depth > *function_depth => Some(Loop::clone(current)),
+            _ => None,
+        }
+    }
+
+    // Labels local to a function start with "."
+    pub fn new_label(&mut self) -> String {
+        let label = format!(".L{}", self.label_count);
+        self.label_count+=1;
+        return label;
+    }
+
+    // Whether the code being generated can be reached by falling through
+    // from the previous instruction
+    pub fn is_reachable(&self) -> bool {
+        return self.reachable;
+    }
+
+    pub fn set_reachable(&mut self, reachable: bool) {
+        self.reachable = reachable;
     }
 
     pub fn get_temp_version(&mut self) -> usize {
@@ -7,8 +7,15 @@ use std::fmt;
 pub enum ALUOp {
     Add,
     Sub,
+    Multiply,
+    Divide(bool), // Signed if true, for integers
+    Modulo(bool), // Signed if true
+    ShiftLeft,
+    ShiftRight(bool), // Arithmetic if signed
+    And,
+    Or,
     Negate,
-    // TODO
+    Compare(Condition), // 1 if Condition(RegA, RegB), otherwise 0
 }
 
 
@@ -17,7 +24,15 @@ impl fmt::Display for ALUOp {
         match self {
             ALUOp::Add => write!(fmt, "ALUOp(Add)"),
             ALUOp::Sub => write!(fmt, "ALUOp(Sub)"),
+            ALUOp::Multiply => write!(fmt, "ALUOp(Multiply)"),
+            ALUOp::Divide(signed) => write!(fmt, "ALUOp(Divide(signed: {}))", signed),
+            ALUOp::Modulo(signed) => write!(fmt, "ALUOp(Modulo(signed: {}))", signed),
+            ALUOp::ShiftLeft => write!(fmt, "ALUOp(ShiftLeft)"),
+            ALUOp::ShiftRight(signed) => write!(fmt, "ALUOp(ShiftRight(signed: {}))", signed),
+            ALUOp::And => write!(fmt, "ALUOp(And)"),
+            ALUOp::Or => write!(fmt, "ALUOp(Or)"),
             ALUOp::Negate => write!(fmt, "ALUOp(Negate)"),
+            ALUOp::Compare(condition) => write!(fmt, "ALUOp(Compare({}))", condition),
         }
     }
 }
@@ -25,15 +40,32 @@ impl fmt::Display for ALUOp {
 #[derive(Clone, Copy)]
 pub enum Condition {
     Equal,
+    NotEqual,
+    // Signed
     LessThan,
-    // TODO
+    LessEqual,
+    GreaterThan,
+    GreaterEqual,
+    // Unsigned
+    Below,
+    BelowEqual,
+    Above,
+    AboveEqual,
 }
 
 impl fmt::Display for Condition {
     fn fmt (&self, fmt: &mut std::fmt::Formatter<'_>) -> fmt::Result {
         match self {
             Condition::Equal => write!(fmt, "Branch(Equal)"),
-            Condition::LessThan => write!(fmt, "ALUOp(LessThan)"),
+            Condition::NotEqual => write!(fmt, "Branch(NotEqual)"),
+            Condition::LessThan => write!(fmt, "Branch(LessThan)"),
+            Condition::LessEqual => write!(fmt, "Branch(LessEqual)"),
+            Condition::GreaterThan => write!(fmt, "Branch(GreaterThan)"),
+            Condition::GreaterEqual => write!(fmt, "Branch(GreaterEqual)"),
+            Condition::Below => write!(fmt, "Branch(Below)"),
+            Condition::BelowEqual => write!(fmt, "Branch(BelowEqual)"),
+            Condition::Above => write!(fmt, "Branch(Above)"),
+            Condition::AboveEqual => write!(fmt, "Branch(AboveEqual)"),
         }
     }
 }
@@ -41,32 +73,64 @@ impl fmt::Display for Condition {
 #[derive(Clone, Copy)]
 pub enum Instruction {
     Move,  // Move src -> dst
+    // Move an integer src -> dst of another size, extended by its sign if
+    // signed is true: src , dst
+    Convert(bool),
     Load,  // Move Mem[src] -> dst
     Store, // Move src -> Mem[dst]
+    Address, // Move address(src) -> dst
     GetArgument, // Move argument(location) -> symbol
     SetReturned, // Move symbol -> returned(location)
+    GetReturned, // Move returned(location) -> symbol, after a call
     ALUOp(ALUOp),  // Result(args) -> Register
+    // ALUOp on integers, signed if true, calling the panic routine if the
+    // result overflows: { operand } , result , message label , message length
+    CheckedALUOp(ALUOp, bool),
     Jump,  // Unconditional jump to a label
     Branch(Condition), // Jump to label if Condition(RegA, RegB)
-    Call,  // Call procedure
+    Call,  // Call procedure: label , { argument , pass location }
+    // Call a variadic procedure, like Call. Arguments after the named ones
+    // may be passed wider than their value, which is promoted.
+    VariadicCall,
     Return, // Return from procedure
     Label, // Put a label here
+    Static, // Reserve static storage: label, size, alignment, [ initial value ]
+    ReadOnly, // Put read only data in the program: label, bytes
+    Panic, // Call the panic routine, which doesn't return: message label , message length
+    // Add the entry point of the program, which calls main with argc and
+    // argv, then exits with the status it returns: label , [ pass location ]
+    Start,
+    Extern, // Declare a label defined outside the program: label
+    // Make a system call, storing what it returns in result:
+    // number , { argument } , result
+    Syscall,
 }
 
 impl fmt::Display for Instruction {
     fn fmt (&self, fmt: &mut std::fmt::Formatter<'_>) -> fmt::Result {
         match self {
             Instruction::Move => write!(fmt, "Instruction(Move)"),
+            Instruction::Convert(signed) => write!(fmt, "Instruction(Convert(signed: {}))", signed),
             Instruction::Load => write!(fmt, "Instruction(Load)"),
             Instruction::Store => write!(fmt, "Instruction(Store)"),
+            Instruction::Address => write!(fmt, "Instruction(Address)"),
             Instruction::GetArgument => write!(fmt, "Instruction(GetArgument)"),
             Instruction::SetReturned => write!(fmt, "Instruction(SetReturned)"),
+            Instruction::GetReturned => write!(fmt, "Instruction(GetReturned)"),
             Instruction::ALUOp(alu_op) => write!(fmt, "Instruction({})", alu_op),
+            Instruction::CheckedALUOp(alu_op, signed) => write!(fmt, "Instruction(Checked({}, signed: {}))", alu_op, signed),
             Instruction::Jump => write!(fmt, "Instruction(Jump)"),
             Instruction::Branch(condition) => write!(fmt, "Instruction({})", condition),
             Instruction::Call => write!(fmt, "Instruction(Call)"),
+            Instruction::VariadicCall => write!(fmt, "Instruction(VariadicCall)"),
             Instruction::Return => write!(fmt, "Instruction(Return)"),
             Instruction::Label => write!(fmt, "Instruction(Label)"),
+            Instruction::Static => write!(fmt, "Instruction(Static)"),
+            Instruction::ReadOnly => write!(fmt, "Instruction(ReadOnly)"),
+            Instruction::Panic => write!(fmt, "Instruction(Panic)"),
+            Instruction::Start => write!(fmt, "Instruction(Start)"),
+            Instruction::Extern => write!(fmt, "Instruction(Extern)"),
+            Instruction::Syscall => write!(fmt, "Instruction(Syscall)"),
         }
     }
 }
@@ -74,7 +138,7 @@ impl fmt::Display for Instruction {
 
 // ===== Operands to instructions relating to memory =====
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, PartialEq)]
 pub enum Regtype {
     Integer,
     Float,
@@ -102,32 +166,41 @@ pub struct PassLocation {
     pub index: usize,
     pub size: usize,
     pub regtype: Regtype,
+    pub signed: bool, // Narrow integers are sign extended, instead of zero extended
 }
 
 impl fmt::Display for PassLocation {
     fn fmt (&self, fmt: &mut std::fmt::Formatter<'_>) -> fmt::Result {
         write!(fmt,
-               "PassLocation(index: {}, size: {}, regtype: {})",
-               self.index, self.size, self.regtype)
+               "PassLocation(index: {}, size: {}, regtype: {}, signed: {})",
+               self.index, self.size, self.regtype, self.signed)
     }
 }
 
-// Generic reference to a local variable.
+// Generic reference to a variable.
 // Stores variable name and a version number (for SSA).
 // Also stores size and datatype to inform instructions that operate on them.
+// Global symbols live in static storage under their name, instead of being
+// local to the function.
+// Declaration is the node declaring a variable, which tells apart variables
+// with the same name in different scopes. Temporaries are told apart by
+// version instead.
 #[derive(Clone)]
 pub struct Symbol {
     pub name: String,
     pub version: usize,
+    pub declaration: usize,
+    pub alignment: usize,
     pub size: usize,
     pub regtype: Regtype,
+    pub global: bool,
 }
 
 impl fmt::Display for Symbol {
     fn fmt (&self, fmt: &mut std::fmt::Formatter<'_>) -> fmt::Result {
         write!(fmt,
-               "Symbol(name: {}, version: {}, size: {}, regtype: {}",
-               self.name, self.version, self.size, self.regtype)
+               "Symbol(name: {}, version: {}, declaration: {}, size: {}, alignment: {}, regtype: {}, global: {}",
+               self.name, self.version, self.declaration, self.size, self.alignment, self.regtype, self.global)
     }
 }
 
@@ -154,6 +227,7 @@ pub enum Operand {
     Symbol(Symbol),      // Generic symbol
     Constant(Constant),
     Integer(i64),        // Offset or stride
+    Bytes(Vec<u8>),      // Contents of read only data
 }
 
 impl fmt::Display for Operand {
@@ -164,6 +238,7 @@ impl fmt::Display for Operand {
             Operand::Symbol(symbol) => write!(fmt, "Operand({})", symbol),
             Operand::Constant(constant) => write!(fmt, "Operand({})", constant),
             Operand::Integer(integer) => write!(fmt, "Operand(Integer({}))", integer),
+            Operand::Bytes(bytes) => write!(fmt, "Operand(Bytes({:?}))", bytes),
         }
     }
 }
@@ -6,26 +6,44 @@ mod resolve;
 
 use crate::parser::ast::Ast;
 use crate::parser::construct;
+use crate::options::Options;
 
 use instructions::Element;
 use generator::Generator;
 
 
-pub fn generate_instructions(ast: &mut Ast) -> Vec<Element> {
+pub fn generate_instructions(ast: &mut Ast, options: &Options) -> Vec<Element> {
     let mut instructions: Vec<Element> = Vec::new();
-    let mut generator = Generator::new(ast, &mut instructions);
+    let mut generator = Generator::new(ast, &mut instructions, options);
 
     match generator.current() {
         construct::Construct::Program => (),
         _ => panic!("Node at generate_program() is not a program"),
     }
+    generate::label_symbols(&mut generator);
     resolve::resolve_content(&mut generator);
-    // TODO: Generate code for global variables?
     generate::generate_content(&mut generator);
 
     return instructions;
 }
 
+// Checks the whole program, then declares its structs and exported
+// functions for C.
+pub fn generate_c_header(ast: &mut Ast, options: &Options) -> String {
+    let mut instructions: Vec<Element> = Vec::new();
+    let mut generator = Generator::new(ast, &mut instructions, options);
+
+    match generator.current() {
+        construct::Construct::Program => (),
+        _ => panic!("Node at generate_c_header() is not a program"),
+    }
+    generate::label_symbols(&mut generator);
+    resolve::resolve_content(&mut generator);
+    generate::generate_content(&mut generator);
+
+    return generate::generate_header(&mut generator);
+}
+
 pub fn print_instructions(instructions: &Vec<Element>) {
     for element in instructions {
         println!("{}", element);
@@ -0,0 +1,461 @@
+
+use super::construct::*;
+use super::generator::Generator;
+use super::instructions::Constant;
+use crate::lexer::token;
+
+use super::content::resolve_datatype;
+use super::structure::get_datatype_layout;
+
+// Constant expressions are evaluated with the primitive of the value they are
+// assigned to. Each operation is evaluated exactly, then the result must fit
+// within the primitive, otherwise it is reported as an overflow.
+//
+// Integer constants are stored as the bit pattern of the value, so u64
+// values above i64::MAX are stored as negative values. Bools are stored as
+// 0 or 1, but can only be given by true and false.
+//
+// Integer literals, and expressions folded into literals, hold their exact
+// value. A literal with a suffix can only be used as that primitive.
+
+#[derive(Clone, Copy)]
+enum Value {
+    Int(i128),
+    Float(f64),
+}
+
+fn get_int_range(primitive: &Primitive) -> Option<(i128, i128)> {
+    match primitive {
+        Primitive::I8 => Some((i8::MIN as i128, i8::MAX as i128)),
+        Primitive::I16 => Some((i16::MIN as i128, i16::MAX as i128)),
+        Primitive::I32 => Some((i32::MIN as i128, i32::MAX as i128)),
+        Primitive::I64 => Some((i64::MIN as i128, i64::MAX as i128)),
+        Primitive::U8 => Some((0, u8::MAX as i128)),
+        Primitive::U16 => Some((0, u16::MAX as i128)),
+        Primitive::U32 => Some((0, u32::MAX as i128)),
+        Primitive::U64 => Some((0, u64::MAX as i128)),
+        Primitive::C8 => Some((0, u8::MAX as i128)),
+        Primitive::Bool => Some((0, 1)),
+        Primitive::F32 => None,
+        Primitive::F64 => None,
+    }
+}
+
+fn get_int_bits(primitive: &Primitive) -> i128 {
+    match primitive {
+        Primitive::I8 | Primitive::U8 | Primitive::C8 | Primitive::Bool => 8,
+        Primitive::I16 | Primitive::U16 => 16,
+        Primitive::I32 | Primitive::U32 | Primitive::F32 => 32,
+        Primitive::I64 | Primitive::U64 | Primitive::F64 => 64,
+    }
+}
+
+fn check_int(value: Option<i128>, primitive: &Primitive) -> Result<Value, String> {
+    let (min, max) = get_int_range(primitive)
+        .expect("check_int() called with floating point primitive");
+    match value {
+        Some(value) if value >= min && value <= max => Ok(Value::Int(value)),
+        Some(value) => Err(format!("Overflow in constant expression, {} doesn't fit in {}", value, primitive)),
+        None => Err(format!("Overflow in constant expression of type {}", primitive)),
+    }
+}
+
+fn check_float(value: f64, primitive: &Primitive) -> Result<Value, String> {
+    let value = match primitive {
+        Primitive::F32 => value as f32 as f64,
+        _ => value,
+    };
+    if !value.is_finite() {
+        return Err(format!("Overflow in constant expression of type {}", primitive));
+    }
+    return Ok(Value::Float(value));
+}
+
+fn from_constant(constant: &Constant, primitive: &Primitive) -> Result<Value, String> {
+    match constant {
+        Constant::Int(value) => match primitive {
+            Primitive::F32 | Primitive::F64 => Err(format!(
+                "Integer constant {} used as {}, no implicit casting", value, primitive)),
+            Primitive::U64 => Ok(Value::Int(*value as u64 as i128)),
+            _ => check_int(Some(*value as i128), primitive),
+        },
+        Constant::Float(value) => match primitive {
+            Primitive::F32 | Primitive::F64 => check_float(*value, primitive),
+            _ => Err(format!(
+                "Floating point constant {} used as {}, no implicit casting", value, primitive)),
+        },
+    }
+}
+
+fn from_int_literal(value: i128, primitive: &Primitive) -> Result<Value, String> {
+    match primitive {
+        Primitive::F32 | Primitive::F64 => Err(format!(
+            "Integer constant {} used as {}, no implicit casting", value, primitive)),
+        _ => check_int(Some(value), primitive),
+    }
+}
+
+// Primitive given by the suffix of a literal
+fn check_suffix(generator: &mut Generator, primitive: &Primitive) -> Result<(), String> {
+    // Current node = Expression::Constant
+    // Children: [ primitive ]
+    if !generator.down() {
+        return Ok(());
+    }
+    let result = match generator.current() {
+        Construct::Primitive(suffix) if suffix != primitive => Err(format!(
+            "Literal with suffix {} used as {}, no implicit casting", suffix, primitive)),
+        _ => Ok(()),
+    };
+    generator.up();
+    return result;
+}
+
+fn to_constant(value: Value) -> Constant {
+    match value {
+        Value::Int(value) => Constant::Int(value as i64),
+        Value::Float(value) => Constant::Float(value),
+    }
+}
+
+pub fn get_datatype_primitive(generator: &mut Generator) -> Option<Primitive> {
+    // Current node = Datatype
+    // Follows aliases to find the primitive. None if not a primitive.
+    let primitive = match generator.current() {
+        Construct::Datatype(Datatype::Terminal) => {
+            generator.down();
+            while let Construct::Qualifier(_) = generator.current() {
+                if !generator.next() {
+                    break;
+                }
+            }
+            let primitive = match generator.current() {
+                Construct::Primitive(primitive) => Some(Primitive::clone(primitive)),
+                Construct::Reference(ref_i_) => {
+                    let ref_i = *ref_i_;
+                    generator.down_ref(ref_i);
+                    let primitive = match generator.current() {
+                        Construct::Datatype(_) => get_datatype_primitive(generator),
+                        Construct::Alias(_, _) => {
+                            generator.down();
+                            let primitive = get_datatype_primitive(generator);
+                            generator.up();
+                            primitive
+                        },
+                        _ => None,
+                    };
+                    generator.up();
+                    primitive
+                },
+                _ => None,
+            };
+            generator.up();
+            primitive
+        },
+        _ => None,
+    };
+    return primitive;
+}
+
+fn find_structure(generator: &mut Generator) -> Option<usize> {
+    // Current node = Datatype
+    // Follows aliases to find the structure node. None if not a structure.
+    let structure = match generator.current() {
+        Construct::Datatype(Datatype::Terminal) => {
+            generator.down();
+            while let Construct::Qualifier(_) = generator.current() {
+                if !generator.next() {
+                    break;
+                }
+            }
+            let structure = match generator.current() {
+                Construct::Reference(ref_i_) => {
+                    let ref_i = *ref_i_;
+                    generator.down_ref(ref_i);
+                    let structure = match generator.current() {
+                        Construct::Structure(..) => Some(ref_i),
+                        Construct::Datatype(_) => find_structure(generator),
+                        Construct::Alias(_, _) => {
+                            generator.down();
+                            let structure = find_structure(generator);
+                            generator.up();
+                            structure
+                        },
+                        _ => None,
+                    };
+                    generator.up();
+                    structure
+                },
+                _ => None,
+            };
+            generator.up();
+            structure
+        },
+        _ => None,
+    };
+    return structure;
+}
+
+fn find_member_offset(generator: &mut Generator, structure_i: usize, member: &String) -> Option<usize> {
+    generator.down_ref(structure_i);
+    let mut offset = None;
+    if generator.down() {
+        loop {
+            if let Construct::Member(name, member_offset) = generator.current() {
+                if name == member {
+                    offset = Some(*member_offset);
+                    break;
+                }
+            }
+            if !generator.next() {
+                break;
+            }
+        }
+        generator.up();
+    }
+    generator.up();
+    return offset;
+}
+
+fn evaluate_layout(generator: &mut Generator, expression: &Expression, primitive: &Primitive) -> Result<Value, String> {
+    // Current node = Expression::Sizeof, Alignof or Offsetof
+    // Children: datatype , [ identifier ]
+    if get_int_range(primitive).is_none() || *primitive == Primitive::Bool {
+        return Err(format!("{} used as {}, no implicit casting", expression, primitive));
+    }
+    resolve_datatype(generator);
+    generator.down();
+    // Also makes sure structures are fully defined
+    let layout = get_datatype_layout(generator);
+    let result = match expression {
+        Expression::Sizeof => Ok(layout.size),
+        Expression::Alignof => Ok(layout.alignment),
+        Expression::Offsetof => {
+            let structure = find_structure(generator);
+            generator.next();
+            let member = match generator.current() {
+                Construct::Identifier(member) => String::clone(member),
+                _ => panic!("Expected member identifier in offsetof"),
+            };
+            match structure {
+                Some(structure_i) => match find_member_offset(generator, structure_i, &member) {
+                    Some(offset) => Ok(offset),
+                    None => Err(format!("No member {} in datatype given to offsetof", member)),
+                },
+                None => Err(String::from("Datatype given to offsetof is not a struct")),
+            }
+        },
+        _ => panic!("evaluate_layout() called with {}", expression),
+    };
+    generator.up();
+    return check_int(Some(result? as i128), primitive);
+}
+
+fn evaluate_binary_op(op: &BinaryOp, lhs: Value, rhs: Value, primitive: &Primitive) -> Result<Value, String> {
+    if *primitive == Primitive::Bool {
+        return match (op, lhs, rhs) {
+            (BinaryOp::LogicalAnd, Value::Int(lhs), Value::Int(rhs)) => Ok(Value::Int(lhs & rhs)),
+            (BinaryOp::LogicalOr, Value::Int(lhs), Value::Int(rhs)) => Ok(Value::Int(lhs | rhs)),
+            _ => Err(format!("{} can't be applied to bool constants", op)),
+        };
+    }
+    match (lhs, rhs) {
+        (Value::Int(lhs), Value::Int(rhs)) => match op {
+            BinaryOp::Add => check_int(lhs.checked_add(rhs), primitive),
+            BinaryOp::Subtract => check_int(lhs.checked_sub(rhs), primitive),
+            BinaryOp::Multiply => check_int(lhs.checked_mul(rhs), primitive),
+            BinaryOp::Divide | BinaryOp::Modulo => {
+                if rhs == 0 {
+                    return Err(String::from("Division by zero in constant expression"));
+                }
+                match op {
+                    BinaryOp::Divide => check_int(Some(lhs / rhs), primitive),
+                    _ => check_int(Some(lhs % rhs), primitive),
+                }
+            },
+            BinaryOp::ShiftLeft | BinaryOp::ShiftRight => {
+                if rhs < 0 || rhs >= get_int_bits(primitive) {
+                    return Err(format!("Shift by {} is out of range for {}", rhs, primitive));
+                }
+                match op {
+                    BinaryOp::ShiftLeft => check_int(lhs.checked_mul(1 << rhs), primitive),
+                    _ => check_int(Some(lhs >> rhs), primitive),
+                }
+            },
+            BinaryOp::BitwiseAnd => check_int(Some(lhs & rhs), primitive),
+            BinaryOp::BitwiseOr => check_int(Some(lhs | rhs), primitive),
+            _ => Err(format!("{} is not allowed in a constant expression", op)),
+        },
+        (Value::Float(lhs), Value::Float(rhs)) => match op {
+            BinaryOp::Add => check_float(lhs + rhs, primitive),
+            BinaryOp::Subtract => check_float(lhs - rhs, primitive),
+            BinaryOp::Multiply => check_float(lhs * rhs, primitive),
+            BinaryOp::Divide => {
+                if rhs == 0.0 {
+                    return Err(String::from("Division by zero in constant expression"));
+                }
+                check_float(lhs / rhs, primitive)
+            },
+            _ => Err(format!("{} can't be applied to floating point constants", op)),
+        },
+        _ => panic!("Mismatched values in constant expression"),
+    }
+}
+
+fn evaluate_identifier(generator: &mut Generator, primitive: &Primitive, visiting: &mut Vec<usize>) -> Result<Value, String> {
+    // Current node = Identifier
+    let name = match generator.current() {
+        Construct::Identifier(name) => String::clone(name),
+        _ => panic!("Expected identifier in Expression::Identifier"),
+    };
+    let symbol_i = match generator.find_symbol(&name) {
+        Some(symbol_i) => symbol_i,
+        None => return Err(format!("Couldn't find symbol for identifier {}", name)),
+    };
+    generator.down_ref(symbol_i);
+    let result = match generator.current() {
+        Construct::Const(_) => evaluate_const(generator, visiting),
+        _ => Err(format!("{} is not a compile-time constant", name)),
+    };
+    generator.up();
+
+    let (const_primitive, constant) = result?;
+    if const_primitive != *primitive {
+        return Err(format!("Const {} has type {}, expected {}, no implicit casting", name, const_primitive, primitive));
+    }
+    return from_constant(&constant, primitive);
+}
+
+fn evaluate(generator: &mut Generator, primitive: &Primitive, visiting: &mut Vec<usize>) -> Result<Value, String> {
+    // Current node = Expression
+    let expression = match generator.current() {
+        Construct::Expression(expression) => Expression::clone(expression),
+        _ => panic!("Node at evaluate() is not an expression"),
+    };
+    match expression {
+        Expression::Constant(constant) => {
+            check_suffix(generator, primitive)?;
+            match (constant, primitive) {
+                (token::Constant::Bool(value), Primitive::Bool) => Ok(Value::Int(value as i128)),
+                (token::Constant::Bool(value), _) => Err(format!(
+                    "Bool constant {} used as {}, no implicit casting", value, primitive)),
+                (token::Constant::Int(value), Primitive::Bool) => Err(format!(
+                    "Integer constant {} used as bool, no implicit casting", value)),
+                (token::Constant::Int(value), _) => from_int_literal(value, primitive),
+                (token::Constant::Float(value), _) => from_constant(&Constant::Float(value), primitive),
+                (token::Constant::Char(value), Primitive::C8) => Ok(Value::Int(value as i128)),
+                (token::Constant::Char(value), _) => Err(format!(
+                    "Character constant '{}' used as {}, no implicit casting", std::ascii::escape_default(value), primitive)),
+                (token::Constant::Str(_), _) => Err(String::from("String literals can't be used in constant expressions")),
+            }
+        },
+        Expression::Identifier => {
+            generator.down();
+            let result = evaluate_identifier(generator, primitive, visiting);
+            generator.up();
+            result
+        },
+        Expression::UnaryOp(UnaryOp::LogicalNot) => {
+            if *primitive != Primitive::Bool {
+                return Err(format!("! can't be applied to {} constants", primitive));
+            }
+            generator.down();
+            let result = evaluate(generator, primitive, visiting);
+            generator.up();
+            match result? {
+                Value::Int(value) => Ok(Value::Int(1 - value)),
+                Value::Float(_) => panic!("Floating point value in bool constant expression"),
+            }
+        },
+        Expression::UnaryOp(UnaryOp::Negate) if *primitive == Primitive::Bool => {
+            Err(String::from("- can't be applied to bool constants"))
+        },
+        Expression::UnaryOp(UnaryOp::Negate) => {
+            generator.down();
+            // A negated integer literal is a single value, so the minimum of a
+            // signed primitive (eg: -128 for i8) is allowed.
+            if let Construct::Expression(Expression::Constant(token::Constant::Int(value))) = generator.current() {
+                let value = -*value;
+                let suffix = check_suffix(generator, primitive);
+                generator.up();
+                suffix?;
+                return from_int_literal(value, primitive);
+            }
+            let result = evaluate(generator, primitive, visiting);
+            generator.up();
+            match result? {
+                Value::Int(value) => check_int(Some(-value), primitive),
+                Value::Float(value) => check_float(-value, primitive),
+            }
+        },
+        Expression::Sizeof | Expression::Alignof | Expression::Offsetof => {
+            evaluate_layout(generator, &expression, primitive)
+        },
+        Expression::BinaryOp(op) => {
+            generator.down();
+            let lhs = evaluate(generator, primitive, visiting);
+            generator.next();
+            let rhs = evaluate(generator, primitive, visiting);
+            generator.up();
+            evaluate_binary_op(&op, lhs?, rhs?, primitive)
+        },
+        _ => Err(format!("{} is not allowed in a constant expression", expression)),
+    }
+}
+
+fn evaluate_folded(generator: &mut Generator, primitive: &Primitive, visiting: &mut Vec<usize>) -> Result<Constant, String> {
+    // Current node = Expression
+    // Replace the expression with the result, so it is only evaluated once.
+    let value = evaluate(generator, primitive, visiting)?;
+    let folded = match value {
+        Value::Int(value) if *primitive == Primitive::Bool => token::Constant::Bool(value != 0),
+        Value::Int(value) => token::Constant::Int(value),
+        Value::Float(value) => token::Constant::Float(value),
+    };
+    generator.fold_construct(&Construct::Expression(Expression::Constant(folded)));
+    return Ok(to_constant(value));
+}
+
+fn evaluate_const(generator: &mut Generator, visiting: &mut Vec<usize>) -> Result<(Primitive, Constant), String> {
+    // Current node = Const
+    // Children: datatype , expression
+    let name = match generator.current() {
+        Construct::Const(name) => String::clone(name),
+        _ => panic!("Node at evaluate_const() is not a const"),
+    };
+    let node_i = generator.get_ref_id();
+    if visiting.contains(&node_i) {
+        return Err(format!("Const {} is defined in terms of itself", name));
+    }
+    visiting.push(node_i);
+
+    generator.down();
+    let result = match get_datatype_primitive(generator) {
+        Some(primitive) => {
+            generator.next();
+            match evaluate_folded(generator, &primitive, visiting) {
+                Ok(constant) => Ok((primitive, constant)),
+                Err(error) => Err(error),
+            }
+        },
+        None => Err(format!("Const {} must have a primitive datatype", name)),
+    };
+    generator.up();
+
+    visiting.pop();
+    return result;
+}
+
+pub fn evaluate_constant(generator: &mut Generator, primitive: &Primitive) -> Result<Constant, String> {
+    // Current node = Expression
+    let mut visiting: Vec<usize> = Vec::new();
+    return evaluate_folded(generator, primitive, &mut visiting);
+}
+
+pub fn resolve_const(generator: &mut Generator) {
+    // Current node = Const
+    let mut visiting: Vec<usize> = Vec::new();
+    if let Err(error) = evaluate_const(generator, &mut visiting) {
+        panic!("{}", error);
+    }
+}
@@ -3,6 +3,7 @@ use super::construct::*;
 use super::generator::Generator;
 
 use super::structure::fully_define_structure;
+use super::constant::resolve_const;
 
 
 fn resolve_datatype_terminal(generator: &mut Generator) {
@@ -10,6 +11,11 @@ fn resolve_datatype_terminal(generator: &mut Generator) {
     // Child is identifier, terminal (or reference when resolved)
     // If it is an identifier, change construct to a reference
     generator.down();
+    while let Construct::Qualifier(_) = generator.current() {
+        if !generator.next() {
+            panic!("Datatype::Terminal has no terminal child");
+        }
+    }
     let identifier = match generator.current() {
         Construct::Identifier(identifier) => identifier,
         _ => {
@@ -18,31 +24,54 @@ fn resolve_datatype_terminal(generator: &mut Generator) {
         },
     };
 
-    let ref_node_i = match generator.find_symbol(identifier) {
+    let mut ref_node_i = match generator.find_symbol(identifier) {
         Some(node_i) => node_i,
         None => panic!("Couldn't find symbol for identifier {}", identifier),
     };
+
+    // A non-distinct alias is interchangeable with its underlying datatype,
+    // so refer directly to the alias's datatype node.
+    // A distinct alias is its own nominal type, so refer to the alias itself.
+    generator.down_ref(ref_node_i);
+    if let Construct::Alias(_, false) = generator.current() {
+        generator.down();
+        ref_node_i = generator.get_ref_id();
+        generator.up();
+    }
+    generator.up();
+
     let construct = Construct::Reference(ref_node_i);
 
     generator.replace_construct(&construct);
     generator.up();
 }
 
-fn resolve_datatype(generator: &mut Generator) {
+pub fn resolve_datatype(generator: &mut Generator) {
     // Current node has datatype as one of its children
     // If a datatype is terminal, it will have a primitive or identifier as child
-    // If a datatype is a pointer, one of its children will also be a datatype
-    generator.down();
+    // If a datatype is a pointer or slice, one of its children will also be a datatype
+    // If a datatype is a function, its children are datatypes and returned
+    if !generator.down() {
+        return;
+    }
     loop {
-        if let Construct::Datatype(datatype) = generator.current() {
-            match datatype {
+        match generator.current() {
+            Construct::Datatype(datatype) => match datatype {
                 Datatype::Terminal => {
                     resolve_datatype_terminal(generator);
                 },
                 Datatype::Pointer => {
                     resolve_datatype(generator);
-                }
-            }
+                },
+                Datatype::Function => {
+                    resolve_datatype(generator);
+                },
+                Datatype::Slice => {
+                    resolve_datatype(generator);
+                },
+            },
+            Construct::Returned => resolve_datatype(generator),
+            _ => (),
         }
         if !generator.next() {
             break;
@@ -51,24 +80,121 @@ fn resolve_datatype(generator: &mut Generator) {
     generator.up();
 }
 
+// Whether a datatype refers back to an alias, given by its node and its
+// datatype's node. A non-distinct alias is expanded wherever it is used, so
+// can't refer to itself anywhere in its datatype. A distinct alias is a name
+// of its own, so it can refer to itself behind a pointer, but its layout is
+// that of its datatype, so it can't be that datatype directly.
+fn refers_to_alias(generator: &mut Generator, datatype_i: usize, alias: (usize, usize), distinct: bool, visited: &mut Vec<usize>) -> bool {
+    generator.down_ref(datatype_i);
+    let mut refers = false;
+    match generator.current() {
+        Construct::Datatype(Datatype::Terminal) => {
+            generator.down();
+            while let Construct::Qualifier(_) = generator.current() {
+                if !generator.next() {
+                    break;
+                }
+            }
+            if let Construct::Reference(ref_i) = generator.current() {
+                let ref_i = *ref_i;
+                if ref_i == alias.0 || ref_i == alias.1 {
+                    refers = true;
+                } else if !visited.contains(&ref_i) {
+                    visited.push(ref_i);
+                    generator.down_ref(ref_i);
+                    let next_i = match generator.current() {
+                        // Non-distinct alias, refers directly to its datatype
+                        Construct::Datatype(_) => Some(ref_i),
+                        Construct::Alias(_, _) if distinct => {
+                            generator.down();
+                            let next_i = generator.get_ref_id();
+                            generator.up();
+                            Some(next_i)
+                        },
+                        _ => None,
+                    };
+                    generator.up();
+                    if let Some(next_i) = next_i {
+                        refers = refers_to_alias(generator, next_i, alias, distinct, visited);
+                    }
+                }
+            }
+            generator.up();
+        },
+        // Children: { qualifier } , { datatype | returned }
+        Construct::Datatype(_) if !distinct => {
+            if generator.down() {
+                loop {
+                    let child_i = match generator.current() {
+                        Construct::Datatype(_) => Some(generator.get_ref_id()),
+                        Construct::Returned => {
+                            generator.down();
+                            let child_i = generator.get_ref_id();
+                            generator.up();
+                            Some(child_i)
+                        },
+                        _ => None,
+                    };
+                    if let Some(child_i) = child_i {
+                        if refers_to_alias(generator, child_i, alias, distinct, visited) {
+                            refers = true;
+                            break;
+                        }
+                    }
+                    if !generator.next() {
+                        break;
+                    }
+                }
+                generator.up();
+            }
+        },
+        _ => (),
+    }
+    generator.up();
+    return refers;
+}
+
+fn check_alias(generator: &mut Generator) {
+    // Current node = Alias
+    // Children: datatype
+    let (name, distinct) = match generator.current() {
+        Construct::Alias(name, distinct) => (String::clone(name), *distinct),
+        _ => panic!("Node at check_alias() is not an alias"),
+    };
+    let alias_i = generator.get_ref_id();
+    generator.down();
+    let datatype_i = generator.get_ref_id();
+    generator.up();
+    if refers_to_alias(generator, datatype_i, (alias_i, datatype_i), distinct, &mut Vec::new()) {
+        panic!("Alias {} is defined in terms of itself", name);
+    }
+}
+
 // Standard symbols that can be declared in a scope.
 // Excludes arguments and return values
 fn check_for_symbol(generator: &mut Generator) {
     let name: String;
     match generator.current() {
-        Construct::Function(name_) => name = String::clone(name_),
-        Construct::Structure(name_, _) => name = String::clone(name_),
+        Construct::Function(name_, _) => name = String::clone(name_),
+        Construct::Structure(name_, _, _) => name = String::clone(name_),
         Construct::Variable(name_) => name = String::clone(name_),
+        Construct::Alias(name_, _) => name = String::clone(name_),
+        Construct::Const(name_) => name = String::clone(name_),
         _ => return,
     };
     generator.add_symbol(&name, false);
 }
 
 fn resolve_function(generator: &mut Generator) {
-    generator.down();
+    if !generator.down() {
+        // Extern function without arguments or returns
+        return;
+    }
     loop {
         match generator.current() {
             Construct::Argument(_) => (),
+            Construct::Variadic => (),
             Construct::Returned => (),
             _ => break,
         }
@@ -81,13 +207,17 @@ fn resolve_function(generator: &mut Generator) {
 }
 
 fn resolve_structure(generator: &mut Generator) {
-    generator.down();
+    if !generator.down() {
+        // Empty struct
+        return;
+    }
     loop {
         match generator.current() {
-            Construct::Member(_, _) => (),
+            Construct::Member(_, _) => resolve_datatype(generator),
+            Construct::Attribute(_) => (),
+            Construct::Function(..) => resolve_function(generator),
             _ => break,
         }
-        resolve_datatype(generator);
         if !generator.next() {
             break;
         }
@@ -97,9 +227,11 @@ fn resolve_structure(generator: &mut Generator) {
 
 fn resolve_symbol_datatypes(generator: &mut Generator) {
     match generator.current() {
-        Construct::Function(_) => resolve_function(generator),
-        Construct::Structure(_, _) => resolve_structure(generator),
+        Construct::Function(..) => resolve_function(generator),
+        Construct::Structure(..) => resolve_structure(generator),
         Construct::Variable(_) => resolve_datatype(generator),
+        Construct::Alias(_, _) => resolve_datatype(generator),
+        Construct::Const(_) => resolve_datatype(generator),
         _ => return,
     }
 }
@@ -108,7 +240,10 @@ fn resolve_symbol_datatypes(generator: &mut Generator) {
 pub fn resolve_content(generator: &mut Generator) {
     // Current node = program or body
 
-    generator.down();
+    if !generator.down() {
+        // Empty body
+        return;
+    }
 
     // Add symbols in current scope
     loop {
@@ -127,6 +262,17 @@ pub fn resolve_content(generator: &mut Generator) {
         }
     }
 
+    // Check aliases can be expanded, before anything follows them
+    generator.restart();
+    loop {
+        if let Construct::Alias(_, _) = generator.current() {
+            check_alias(generator);
+        }
+        if !generator.next() {
+            break;
+        }
+    }
+
     // Fully define structs
     generator.restart();
     loop {
@@ -141,5 +287,17 @@ pub fn resolve_content(generator: &mut Generator) {
         }
     }
 
+    // Evaluate constants
+    generator.restart();
+    loop {
+        match generator.current() {
+            Construct::Const(_) => resolve_const(generator),
+            _ => (),
+        }
+        if !generator.next() {
+            break;
+        }
+    }
+
     generator.up();
 }
@@ -1,8 +1,15 @@
 
 mod content;
 mod structure;
+mod constant;
 
 use super::construct;
 use super::generator;
+use super::instructions;
 
 pub use content::resolve_content;
+pub use content::resolve_datatype;
+pub use structure::get_datatype_layout;
+pub use structure::fully_define_structure;
+pub use constant::evaluate_constant;
+pub use constant::get_datatype_primitive;
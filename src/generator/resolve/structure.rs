@@ -16,49 +16,85 @@ fn get_primitive_size(primitive: &Primitive) -> usize {
         Primitive::F32 => 4,
         Primitive::F64 => 8,
         Primitive::C8 => 1,
+        Primitive::Bool => 1,
     }
 }
 
-fn get_reference_size(generator: &mut Generator, ref_id: usize) -> usize {
+// Size and alignment of a datatype, in bytes
+#[derive(Clone, Copy)]
+pub struct Layout {
+    pub size: usize,
+    pub alignment: usize,
+}
+
+fn get_reference_layout(generator: &mut Generator, ref_id: usize) -> Layout {
     // Current node = Primitive::Reference(ref_id)
     // Instead of down to child, follows ref to new ref_id
     // on up() again, will return to ref node.
     generator.down_ref(ref_id); 
-    let mut size = match generator.current() {
-        Construct::Structure(_, size) => *size,
-        _ => panic!("Reference doesn't point to a structure node"),
+    let layout = match generator.current() {
+        Construct::Structure(..) => fully_define_structure(generator),
+        Construct::Alias(_, _) => {
+            // Distinct alias, has the layout of the underlying datatype
+            generator.down();
+            let layout = get_datatype_layout(generator);
+            generator.up();
+            layout
+        },
+        // Non-distinct alias, refers directly to the underlying datatype
+        Construct::Datatype(_) => get_datatype_layout(generator),
+        _ => panic!("Reference doesn't point to a structure or alias node"),
     };
-    if size == 0 {
-        // Need to calculate size for this structure
-        size = fully_define_structure(generator);
-    }
     generator.up();
-    return size;
+    return layout;
+}
+
+pub fn get_datatype_layout(generator: &mut Generator) -> Layout {
+    // Current node = Datatype
+    match generator.current() {
+        Construct::Datatype(datatype) => match datatype {
+            Datatype::Terminal => {
+                generator.down();
+                loop {
+                    match generator.current() {
+                        Construct::Qualifier(_) => (),
+                        _ => break,
+                    }
+                    if !generator.next() {
+                        panic!("Datatype::Terminal has no terminal child");
+                    }
+                }
+                let layout = match generator.current() {
+                    Construct::Primitive(primitive) => {
+                        // Primitives are aligned to their size
+                        let size = get_primitive_size(primitive);
+                        Layout { size: size, alignment: size }
+                    },
+                    Construct::Reference(ref_id_) => {
+                        let ref_id = *ref_id_;
+                        get_reference_layout(generator, ref_id)
+                    }
+                    Construct::Identifier(_) => panic!("Struct datatype not resolved"),
+                    _ => panic!("Unexpected child node of Datatype::Terminal"),
+                };
+                generator.up();
+                layout
+            },
+            Datatype::Pointer => Layout { size: 8, alignment: 8 },
+            Datatype::Function => Layout { size: 8, alignment: 8 },
+            Datatype::Slice => Layout { size: 16, alignment: 8 },
+        },
+        _ => panic!("Node at get_datatype_layout() is not a datatype"),
+    }
 }
 
-fn find_member_size(generator: &mut Generator) -> usize {
+fn find_member_layout(generator: &mut Generator) -> Layout {
     generator.down();
     loop {
-        if let Construct::Datatype(datatype) = generator.current() {
-            let size = match datatype {
-                Datatype::Terminal => {
-                    generator.down();
-                    let size = match generator.current() {
-                        Construct::Primitive(primitive) => get_primitive_size(primitive),
-                        Construct::Reference(ref_id_) => {
-                            let ref_id = *ref_id_;
-                            get_reference_size(generator, ref_id)
-                        }
-                        Construct::Identifier(_) => panic!("Struct datatype not resolved"),
-                        _ => panic!("Unexpected child node of Datatype::Terminal"),
-                    };
-                    generator.up();
-                    size
-                },
-                Datatype::Pointer => 8,
-            };
+        if let Construct::Datatype(_) = generator.current() {
+            let layout = get_datatype_layout(generator);
             generator.up();
-            return size;
+            return layout;
         }
         if !generator.next() {
             break;
@@ -67,47 +103,109 @@ fn find_member_size(generator: &mut Generator) -> usize {
     panic!("Member had no datatype child");
 }
 
-pub fn fully_define_structure(generator: &mut Generator) -> usize {
+struct MemberLayout {
+    node_i: usize,
+    name: String,
+    layout: Layout,
+}
+
+pub fn fully_define_structure(generator: &mut Generator) -> Layout {
     // Current node = structure
 
-    let (identifier, mut size) = match generator.current() {
-        Construct::Structure(identifier, size) => (String::clone(identifier), *size),
+    let (identifier, size, alignment) = match generator.current() {
+        Construct::Structure(identifier, size, alignment) => (String::clone(identifier), *size, *alignment),
         _ => panic!("Node not Structure at fully_define_structure()"),
     };
-    if size != 0 {
+    if alignment != 0 {
         // Already fully defined
-        return size;
+        return Layout { size: size, alignment: alignment };
     }
-    let mut alignment: usize = 0;
-    generator.down();
-    loop {
-        let member_size = find_member_size(generator);
-        // Need to offset size to align the member
-        if member_size > alignment {
-            alignment = member_size;
+
+    // Children = { attribute } , { member | function }
+    let mut attributes: Vec<usize> = Vec::new();
+    let mut functions: Vec<usize> = Vec::new();
+    let mut members: Vec<MemberLayout> = Vec::new();
+    let mut packed = false;
+    let mut c_layout = false;
+    let mut min_alignment: usize = 1;
+    if generator.down() {
+        loop {
+            match generator.current() {
+                Construct::Attribute(attribute) => {
+                    match attribute {
+                        Attribute::Packed => packed = true,
+                        Attribute::Align(alignment) => min_alignment = *alignment,
+                        Attribute::CLayout => c_layout = true,
+                    }
+                    attributes.push(generator.get_ref_id());
+                },
+                Construct::Member(name, _) => {
+                    let name = String::clone(name);
+                    let layout = find_member_layout(generator);
+                    members.push(MemberLayout {
+                        node_i: generator.get_ref_id(),
+                        name: name,
+                        layout: layout,
+                    });
+                },
+                Construct::Function(..) => functions.push(generator.get_ref_id()),
+                _ => panic!("Child of structure node is not a member"),
+            }
+            if !generator.next() {
+                break;
+            }
         }
-        size += (member_size - size % member_size) % member_size;
+        generator.up();
+    }
 
-        let construct = match generator.current() {
-            Construct::Member(identifier, _) => Construct::Member(String::clone(identifier), size),
-            _ => panic!("Child of structure node is not a member"),
-        };
-        generator.replace_construct(&construct);
-        
-        size += member_size;
+    // Placing the most aligned members first minimises padding, since
+    // alignments are powers of two.
+    if generator.options.reorder_structs && !c_layout && !packed {
+        members.sort_by(|a, b| b.layout.alignment.cmp(&a.layout.alignment));
+        let mut children = attributes;
+        for member in &members {
+            children.push(member.node_i);
+        }
+        children.extend(functions);
+        generator.set_children(&children);
+    }
 
-        if !generator.next() {
-            break;
+    // A struct is aligned to its most aligned member, or the align attribute
+    // if larger. A packed struct has no alignment requirement for members.
+    let mut size: usize = 0;
+    let mut alignment = min_alignment;
+    for (i, member) in members.iter().enumerate() {
+        let member_alignment = if packed { 1 } else { member.layout.alignment };
+        if member_alignment > alignment {
+            alignment = member_alignment;
         }
+        // Need to offset size to align the member
+        let padding = (member_alignment - size % member_alignment) % member_alignment;
+        if padding > 0 {
+            println!(
+                "Warning: {} bytes of padding in struct {} between members {} and {}",
+                padding, identifier, members[i-1].name, member.name);
+        }
+        size += padding;
+
+        generator.down_ref(member.node_i);
+        generator.replace_construct(&Construct::Member(String::clone(&member.name), size));
+        generator.up();
+
+        size += member.layout.size;
     }
-    generator.up();
     // Need size to be a multiple of alignment
-    size += (alignment - size % alignment) % alignment;
+    // Don't warn about padding that was asked for with the align attribute.
+    let padding = (alignment - size % alignment) % alignment;
+    if padding > 0 && !members.is_empty() && alignment > min_alignment {
+        println!(
+            "Warning: {} bytes of padding in struct {} after member {}",
+            padding, identifier, members[members.len()-1].name);
+    }
+    size += padding;
 
-    let construct = Construct::Structure(identifier, size);
+    let construct = Construct::Structure(identifier, size, alignment);
     generator.replace_construct(&construct);
 
-    return size;
-
-    // TODO: Give warning when structure isn't tightly packed
+    return Layout { size: size, alignment: alignment };
 }
@@ -0,0 +1,415 @@
+use super::lexer::CToken;
+use super::importer::Importer;
+use super::declaration::{parse_members, parse_enum, write_structure};
+
+
+#[derive(Clone)]
+pub enum CType {
+    Void,
+    Primitive(&'static str), // our primitive
+    Struct(String), // name it was declared as
+    Opaque,
+    // Struct that isn't defined, so can only be pointed to
+
+    Anonymous(Vec<(String, CType)>), // members
+    // Struct without a tag, which is only declared if it's given a name by
+    // a typedef
+
+    Alias(String, Box<CType>), // name it was declared as, aliased type
+    Pointer(Box<CType>, bool), // pointee, pointee is const
+    Function(Vec<(Option<String>, CType)>, Box<CType>, bool), // arguments, returned, variadic
+    Array(Box<CType>, bool), // element, element is const
+}
+
+pub struct Specifiers {
+    pub ctype: CType,
+    pub constant: bool,
+    pub typedef: bool,
+}
+
+fn is_attribute(word: &str) -> bool {
+    matches!(word, "__attribute__" | "__attribute" | "__asm__" | "__asm" | "asm" | "__declspec")
+}
+
+fn is_ignored_specifier(word: &str) -> bool {
+    matches!(word,
+        "extern" | "static" | "inline" | "__inline" | "__inline__" | "register" | "auto" |
+        "volatile" | "__volatile__" | "restrict" | "__restrict" | "__restrict__" |
+        "__extension__" | "_Noreturn" | "__const")
+}
+
+fn is_primitive_word(word: &str) -> bool {
+    matches!(word,
+        "void" | "char" | "short" | "int" | "long" | "float" | "double" |
+        "signed" | "__signed__" | "unsigned" | "_Bool" | "bool")
+}
+
+// Skips an attribute or asm label, and its arguments in brackets
+pub fn skip_attribute(importer: &mut Importer) {
+    importer.consume_token();
+    if importer.is_symbol("(") {
+        skip_brackets(importer);
+    }
+}
+
+fn skip_attributes(importer: &mut Importer) {
+    while let Some(CToken::Identifier(word)) = importer.peek_token() {
+        if !is_attribute(word) {
+            break;
+        }
+        skip_attribute(importer);
+    }
+}
+
+// Skips from an opening bracket to after the bracket that closes it
+pub fn skip_brackets(importer: &mut Importer) {
+    let mut depth: usize = 0;
+    while let Some(token) = importer.consume_token() {
+        match token {
+            CToken::Symbol("(" | "[" | "{") => depth += 1,
+            CToken::Symbol(")" | "]" | "}") => {
+                depth -= 1;
+                if depth == 0 {
+                    return;
+                }
+            },
+            _ => (),
+        }
+    }
+}
+
+fn get_primitive(words: &Vec<String>) -> Result<CType, String> {
+    let count = |word: &str| words.iter().filter(|w| w.as_str() == word).count();
+    let unsigned = count("unsigned") > 0;
+    let signed = count("signed") + count("__signed__") > 0;
+    let primitive = if words.is_empty() {
+        return Err(String::from("expected a type"));
+    } else if count("void") > 0 {
+        return Ok(CType::Void);
+    } else if count("_Bool") + count("bool") > 0 {
+        "bool"
+    } else if count("float") > 0 {
+        "f32"
+    } else if count("double") > 0 {
+        if count("long") > 0 {
+            return Err(String::from("long double isn't supported"));
+        }
+        "f64"
+    } else if count("char") > 0 {
+        if unsigned { "u8" } else if signed { "i8" } else { "c8" }
+    } else if count("short") > 0 {
+        if unsigned { "u16" } else { "i16" }
+    } else if count("long") > 0 {
+        if unsigned { "u64" } else { "i64" }
+    } else {
+        if unsigned { "u32" } else { "i32" }
+    };
+    return Ok(CType::Primitive(primitive));
+}
+
+// Whether a word is the start of a datatype, rather than a name
+pub fn is_type_word(importer: &Importer, word: &String) -> bool {
+    is_primitive_word(word) || is_ignored_specifier(word) || importer.typedefs.contains_key(word) ||
+        matches!(word.as_str(), "const" | "struct" | "union" | "enum")
+}
+
+fn parse_struct(importer: &mut Importer) -> Result<CType, String> {
+    // "struct" , [ identifier ] , [ "{" , { member } , "}" ]
+    let tag = match importer.peek_token() {
+        Some(CToken::Identifier(tag)) if !is_attribute(tag) => {
+            let tag = String::clone(tag);
+            importer.consume_token();
+            Some(tag)
+        },
+        Some(CToken::Identifier(_)) => return Err(String::from("attributes on structs aren't supported")),
+        _ => None,
+    };
+    if !importer.consume_symbol("{") {
+        return match tag {
+            Some(tag) => Ok(importer.tags.get(&tag).cloned().unwrap_or(CType::Opaque)),
+            None => Err(String::from("struct has no name or members")),
+        };
+    }
+    let tag = match tag {
+        Some(tag) => tag,
+        None => return Ok(CType::Anonymous(parse_members(importer)?)),
+    };
+
+    // Members can point to the struct they're in
+    importer.tags.insert(String::clone(&tag), CType::Struct(String::clone(&tag)));
+    let result = parse_members(importer)
+        .and_then(|members| write_structure(importer, &tag, &members));
+    if let Err(message) = result {
+        importer.tags.insert(String::clone(&tag), CType::Opaque);
+        return Err(format!("struct {}: {}", tag, message));
+    }
+    return Ok(CType::Struct(tag));
+}
+
+// Unions have no equivalent, so their members are skipped, and they can
+// only be pointed to
+fn parse_union(importer: &mut Importer) -> CType {
+    // "union" , [ identifier ] , [ "{" , { member } , "}" ]
+    let tag = match importer.peek_token() {
+        Some(CToken::Identifier(tag)) if !is_attribute(tag) => {
+            let tag = format!("union {}", tag);
+            importer.consume_token();
+            tag
+        },
+        _ => String::from("union without a name"),
+    };
+    if importer.is_symbol("{") {
+        skip_brackets(importer);
+        println!("Warning: {} isn't imported, it can only be pointed to", tag);
+    }
+    return CType::Opaque;
+}
+
+pub fn parse_specifiers(importer: &mut Importer) -> Result<Specifiers, String> {
+    let mut words: Vec<String> = Vec::new();
+    let mut ctype: Option<CType> = None;
+    let mut constant = false;
+    let mut typedef = false;
+    loop {
+        let word = match importer.peek_token() {
+            Some(CToken::Identifier(word)) => String::clone(word),
+            _ => break,
+        };
+        if is_attribute(&word) {
+            skip_attribute(importer);
+            continue;
+        }
+        match word.as_str() {
+            "const" => constant = true,
+            "typedef" => typedef = true,
+            "struct" => {
+                importer.consume_token();
+                ctype = Some(parse_struct(importer)?);
+                continue;
+            },
+            "enum" => {
+                importer.consume_token();
+                ctype = Some(parse_enum(importer)?);
+                continue;
+            },
+            "union" => {
+                importer.consume_token();
+                ctype = Some(parse_union(importer));
+                continue;
+            },
+            _ if is_ignored_specifier(&word) => (),
+            _ if is_primitive_word(&word) => words.push(word),
+            _ => {
+                // Typedef name, unless there's already a type, so this is
+                // the name being declared.
+                if ctype.is_some() || !words.is_empty() {
+                    break;
+                }
+                ctype = match importer.typedefs.get(&word) {
+                    Some(ctype) => Some(CType::clone(ctype)),
+                    None => return Err(format!("{} isn't a known type", word)),
+                };
+            },
+        }
+        importer.consume_token();
+    }
+    let ctype = match ctype {
+        Some(_) if !words.is_empty() => return Err(format!("{} can't be combined with another type", words.join(" "))),
+        Some(ctype) => ctype,
+        None => get_primitive(&words)?,
+    };
+    return Ok(Specifiers { ctype: ctype, constant: constant, typedef: typedef });
+}
+
+// Qualifiers after a *, which apply to the pointer
+fn parse_qualifiers(importer: &mut Importer) -> bool {
+    let mut constant = false;
+    loop {
+        let word = match importer.peek_token() {
+            Some(CToken::Identifier(word)) => String::clone(word),
+            _ => break,
+        };
+        if is_attribute(&word) {
+            skip_attribute(importer);
+            continue;
+        }
+        match word.as_str() {
+            "const" => constant = true,
+            _ if is_ignored_specifier(&word) => (),
+            _ => break,
+        }
+        importer.consume_token();
+    }
+    return constant;
+}
+
+fn parse_parameters(importer: &mut Importer) -> Result<(Vec<(Option<String>, CType)>, bool), String> {
+    // Current token is after "("
+    let mut parameters: Vec<(Option<String>, CType)> = Vec::new();
+    let mut variadic = false;
+    if importer.consume_symbol(")") {
+        return Ok((parameters, variadic));
+    }
+    if let (Some(CToken::Identifier(word)), Some(CToken::Symbol(")"))) = (importer.peek_token(), importer.peek_ahead(1)) {
+        if word == "void" {
+            importer.consume_token();
+            importer.consume_token();
+            return Ok((parameters, variadic));
+        }
+    }
+    loop {
+        if importer.consume_symbol("...") {
+            variadic = true;
+            importer.expect_symbol(")")?;
+            break;
+        }
+        let specifiers = parse_specifiers(importer)?;
+        let (name, ctype) = parse_declarator(importer, specifiers.ctype, specifiers.constant)?;
+        // Arrays and functions are passed as pointers
+        let ctype = match ctype {
+            CType::Array(element, constant) => CType::Pointer(element, constant),
+            CType::Function(..) => CType::Pointer(Box::new(ctype), true),
+            _ => ctype,
+        };
+        parameters.push((name, ctype));
+        if importer.consume_symbol(",") {
+            continue;
+        }
+        importer.expect_symbol(")")?;
+        break;
+    }
+    return Ok((parameters, variadic));
+}
+
+enum Suffix {
+    Array,
+    Function(Vec<(Option<String>, CType)>, bool),
+}
+
+fn parse_suffixes(importer: &mut Importer, ctype: CType, constant: bool) -> Result<CType, String> {
+    let mut suffixes: Vec<Suffix> = Vec::new();
+    loop {
+        if importer.is_symbol("[") {
+            skip_brackets(importer);
+            suffixes.push(Suffix::Array);
+        } else if importer.consume_symbol("(") {
+            let (parameters, variadic) = parse_parameters(importer)?;
+            suffixes.push(Suffix::Function(parameters, variadic));
+        } else {
+            break;
+        }
+    }
+    // The suffix next to the name applies last, eg: a[2][3] is 2 arrays of 3
+    let mut ctype = ctype;
+    let mut constant = constant;
+    for suffix in suffixes.into_iter().rev() {
+        ctype = match suffix {
+            Suffix::Array => CType::Array(Box::new(ctype), constant),
+            Suffix::Function(parameters, variadic) => CType::Function(parameters, Box::new(ctype), variadic),
+        };
+        constant = false;
+    }
+    return Ok(ctype);
+}
+
+// Name and type of a declarator, given the type from the specifiers before
+// it. The name is optional, for arguments and casts.
+pub fn parse_declarator(importer: &mut Importer, ctype: CType, constant: bool) -> Result<(Option<String>, CType), String> {
+    // { "*" , { qualifier } } , ( identifier | "(" , declarator , ")" ) , { suffix }
+    let mut ctype = ctype;
+    let mut constant = constant;
+    while importer.consume_symbol("*") {
+        ctype = CType::Pointer(Box::new(ctype), constant);
+        constant = parse_qualifiers(importer);
+    }
+
+    // A nested declarator applies to the type after the suffixes outside it,
+    // eg: (*f)(int) is a pointer to a function. Otherwise brackets start
+    // the arguments.
+    let mut name = None;
+    let mut nested = None;
+    let is_nested = importer.is_symbol("(") && match importer.peek_ahead(1) {
+        Some(CToken::Symbol("*" | "(")) => true,
+        Some(CToken::Identifier(word)) => !is_type_word(importer, word),
+        _ => false,
+    };
+    if is_nested {
+        nested = Some(importer.get_position() + 1);
+        skip_brackets(importer);
+    } else if let Some(CToken::Identifier(word)) = importer.peek_token() {
+        if !is_attribute(word) {
+            name = Some(String::clone(word));
+            importer.consume_token();
+        }
+    }
+    skip_attributes(importer);
+
+    let ctype = parse_suffixes(importer, ctype, constant)?;
+    skip_attributes(importer);
+    if let Some(position) = nested {
+        let end = importer.get_position();
+        importer.set_position(position);
+        let (name, ctype) = parse_declarator(importer, ctype, false)?;
+        importer.expect_symbol(")")?;
+        importer.set_position(end);
+        return Ok((name, ctype));
+    }
+    return Ok((name, ctype));
+}
+
+fn resolve_alias(ctype: &CType) -> &CType {
+    match ctype {
+        CType::Alias(_, aliased) => resolve_alias(aliased),
+        _ => ctype,
+    }
+}
+
+pub fn is_void(ctype: &CType) -> bool {
+    matches!(resolve_alias(ctype), CType::Void)
+}
+
+pub fn is_struct(ctype: &CType) -> bool {
+    matches!(resolve_alias(ctype), CType::Struct(_) | CType::Anonymous(_))
+}
+
+fn get_function_datatype(arguments: &Vec<(Option<String>, CType)>, returned: &CType, variadic: bool) -> Result<String, String> {
+    if variadic && arguments.is_empty() {
+        return Err(String::from("variadic functions must have an argument before ..."));
+    }
+    let mut datatypes: Vec<String> = Vec::new();
+    for (_, argument) in arguments {
+        datatypes.push(get_datatype(argument)?);
+    }
+    if variadic {
+        datatypes.push(String::from("..."));
+    }
+    let mut datatype = format!("function({})", datatypes.join(", "));
+    if !is_void(returned) {
+        datatype.push_str(&format!(" -> ({})", get_datatype(returned)?));
+    }
+    return Ok(datatype);
+}
+
+// Our datatype equivalent to a C type. Pointers to void or to an undefined
+// struct are pointers to bytes, and a function datatype is already a
+// pointer.
+pub fn get_datatype(ctype: &CType) -> Result<String, String> {
+    match ctype {
+        CType::Void => Err(String::from("void can only be returned or pointed to")),
+        CType::Primitive(primitive) => Ok(String::from(*primitive)),
+        CType::Struct(name) => Ok(String::clone(name)),
+        CType::Opaque => Err(String::from("a struct that isn't defined can only be pointed to")),
+        CType::Anonymous(_) => Err(String::from("a struct without a name can only be given one by typedef")),
+        CType::Alias(name, _) => Ok(String::clone(name)),
+        CType::Pointer(pointee, constant) => {
+            let qualifier = if *constant { "" } else { "mut " };
+            match resolve_alias(pointee) {
+                CType::Function(arguments, returned, variadic) => get_function_datatype(arguments, returned, *variadic),
+                CType::Void | CType::Opaque => Ok(format!("&{}u8", qualifier)),
+                _ => Ok(format!("&{}{}", qualifier, get_datatype(pointee)?)),
+            }
+        },
+        CType::Function(..) => Err(String::from("functions can only be pointed to")),
+        CType::Array(..) => Err(String::from("arrays aren't supported")),
+    }
+}
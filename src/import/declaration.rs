@@ -0,0 +1,403 @@
+use std::fmt::Write;
+
+use crate::lexer::is_keyword;
+use super::lexer::CToken;
+use super::importer::Importer;
+use super::datatype::{
+    CType, parse_specifiers, parse_declarator, get_datatype, is_void, is_struct,
+};
+
+
+// Member and argument names only need to be identifiers in our language,
+// which can't start with _
+fn get_identifier(name: &String) -> String {
+    let name = name.trim_start_matches('_');
+    if name.is_empty() || name.starts_with(|c: char| c.is_ascii_digit()) {
+        return format!("x{}", name);
+    }
+    if is_keyword(&String::from(name)) {
+        return format!("{}_", name);
+    }
+    return String::from(name);
+}
+
+pub fn parse_members(importer: &mut Importer) -> Result<Vec<(String, CType)>, String> {
+    // Current token is after "{"
+    // { specifiers , declarator , { "," , declarator } , ";" } , "}"
+    let mut members: Vec<(String, CType)> = Vec::new();
+    while !importer.consume_symbol("}") {
+        if importer.at_end() {
+            return Err(String::from("struct has no closing }"));
+        }
+        let specifiers = parse_specifiers(importer)?;
+        loop {
+            let (name, ctype) = parse_declarator(importer, CType::clone(&specifiers.ctype), specifiers.constant)?;
+            let name = match name {
+                Some(name) => name,
+                None => return Err(String::from("members without a name aren't supported")),
+            };
+            if importer.is_symbol(":") {
+                return Err(format!("bit field {} isn't supported", name));
+            }
+            members.push((name, ctype));
+            if !importer.consume_symbol(",") {
+                break;
+            }
+        }
+        importer.expect_symbol(";")?;
+    }
+    return Ok(members);
+}
+
+// Members are kept in the order C lays them out
+pub fn write_structure(importer: &mut Importer, name: &String, members: &Vec<(String, CType)>) -> Result<(), String> {
+    let mut declarations: Vec<String> = Vec::new();
+    for (member, ctype) in members {
+        let datatype = get_datatype(ctype)
+            .map_err(|message| format!("member {}: {}", member, message))?;
+        declarations.push(format!("    {}: {};", get_identifier(member), datatype));
+    }
+    importer.declare_name(name)?;
+    writeln!(importer.output, "#[c_layout]").unwrap();
+    writeln!(importer.output, "{}: struct {{", name).unwrap();
+    for declaration in declarations {
+        writeln!(importer.output, "{}", declaration).unwrap();
+    }
+    writeln!(importer.output, "}}").unwrap();
+    writeln!(importer.output).unwrap();
+    return Ok(());
+}
+
+fn write_constant(importer: &mut Importer, name: &String, primitive: &'static str, expression: &String) -> Result<(), String> {
+    importer.declare_name(name)?;
+    importer.constants.insert(String::clone(name), primitive);
+    writeln!(importer.output, "{}: const {} = {};", name, primitive, expression).unwrap();
+    writeln!(importer.output).unwrap();
+    return Ok(());
+}
+
+// Type of an integer literal in C, the first of int, long and unsigned long
+// it fits in, or only the unsigned ones with a u suffix.
+fn get_int_primitive(value: u128, suffix: &String) -> Result<&'static str, String> {
+    let primitives: &[(&'static str, u128)] = match (suffix.contains('u'), suffix.contains('l')) {
+        (false, false) => &[("i32", i32::MAX as u128), ("i64", i64::MAX as u128), ("u64", u64::MAX as u128)],
+        (true, false) => &[("u32", u32::MAX as u128), ("u64", u64::MAX as u128)],
+        (false, true) => &[("i64", i64::MAX as u128), ("u64", u64::MAX as u128)],
+        (true, true) => &[("u64", u64::MAX as u128)],
+    };
+    for (primitive, max) in primitives {
+        if value <= *max {
+            return Ok(primitive);
+        }
+    }
+    return Err(format!("{} is too large", value));
+}
+
+const INT_RANKS: [&str; 4] = ["i32", "u32", "i64", "u64"];
+
+// Our constant expression for a C one, and its primitive. There's no
+// implicit casting, so any constants it uses must all have the primitive.
+fn convert_expression(importer: &Importer, tokens: &[CToken]) -> Result<(String, &'static str), String> {
+    let mut expression = String::new();
+    let mut after_operator = true; // A - here is negation
+    let mut after_negation = false;
+    let mut constant_primitive: Option<&'static str> = None;
+    let mut int_primitive: Option<&'static str> = None;
+    let mut float_primitive: Option<&'static str> = None;
+    for token in tokens {
+        let (part, operator) = match token {
+            CToken::Int(value, suffix) => {
+                let primitive = get_int_primitive(*value, suffix)?;
+                let rank = |primitive| INT_RANKS.iter().position(|p| *p == primitive);
+                if int_primitive.map_or(true, |current| rank(primitive) > rank(current)) {
+                    int_primitive = Some(primitive);
+                }
+                (value.to_string(), false)
+            },
+            CToken::Float(value, single) => {
+                if !single || float_primitive.is_none() {
+                    float_primitive = Some(if *single { "f32" } else { "f64" });
+                }
+                (format!("{:?}", value), false)
+            },
+            CToken::Identifier(name) => {
+                let primitive = match importer.constants.get(name) {
+                    Some(primitive) => *primitive,
+                    None => return Err(format!("{} isn't a known constant", name)),
+                };
+                if constant_primitive.map_or(false, |current| current != primitive) {
+                    return Err(format!("{} is a {}, which is mixed with other types", name, primitive));
+                }
+                constant_primitive = Some(primitive);
+                (String::clone(name), false)
+            },
+            CToken::Symbol(symbol @ ("(" | ")" | "+" | "-" | "*" | "/" | "%" | "<<" | ">>" | "&" | "|")) => {
+                (String::from(*symbol), *symbol != ")")
+            },
+            _ => return Err(format!("{} can't be used in a constant", token)),
+        };
+        // A negation is kept next to its operand
+        let negation = after_operator && part == "-";
+        if !expression.is_empty() && !after_negation && !expression.ends_with('(') && part != ")" {
+            expression.push(' ');
+        }
+        expression.push_str(&part);
+        after_negation = negation;
+        after_operator = operator;
+    }
+    if expression.is_empty() {
+        return Err(String::from("it has no value"));
+    }
+
+    let primitive = match (constant_primitive, int_primitive, float_primitive) {
+        (_, Some(_), Some(_)) => return Err(String::from("it mixes integers and floating point")),
+        (Some(primitive), None, None) => primitive,
+        (Some(primitive), Some(_), None) if primitive.starts_with('f') => return Err(format!("integers are mixed with {}", primitive)),
+        (Some(primitive), None, Some(_)) if !primitive.starts_with('f') => return Err(format!("floating point is mixed with {}", primitive)),
+        (Some(primitive), _, _) => primitive,
+        (None, Some(primitive), None) => primitive,
+        (None, None, Some(primitive)) => primitive,
+        (None, None, None) => return Err(String::from("it has no value")),
+    };
+    return Ok((expression, primitive));
+}
+
+fn import_define(importer: &mut Importer, name: &String, body: &Vec<CToken>) -> Result<(), String> {
+    if body.is_empty() || importer.constants.contains_key(name) {
+        // Only used to test whether it's defined, or defined again
+        return Ok(());
+    }
+    let (expression, primitive) = convert_expression(importer, body)
+        .map_err(|message| format!("#define {}: {}", name, message))?;
+    return write_constant(importer, name, primitive, &expression);
+}
+
+// Enum constants are int, so are declared as i32 constants
+pub fn parse_enum(importer: &mut Importer) -> Result<CType, String> {
+    // "enum" , [ identifier ] , [ "{" , enumerator , { "," , enumerator } , [ "," ] , "}" ]
+    if let Some(CToken::Identifier(_)) = importer.peek_token() {
+        importer.consume_token();
+    }
+    if !importer.consume_symbol("{") {
+        return Ok(CType::Primitive("i32"));
+    }
+    let mut previous: Option<String> = None;
+    while !importer.consume_symbol("}") {
+        let name = match importer.consume_token() {
+            Some(CToken::Identifier(name)) => name,
+            _ => return Err(String::from("expected a name in enum")),
+        };
+        let mut tokens: Vec<CToken> = Vec::new();
+        if importer.consume_symbol("=") {
+            let mut depth: usize = 0;
+            loop {
+                match importer.peek_token() {
+                    Some(CToken::Symbol("," | "}")) if depth == 0 => break,
+                    Some(CToken::Symbol("(")) => depth += 1,
+                    Some(CToken::Symbol(")")) => depth = depth.saturating_sub(1),
+                    None => return Err(String::from("enum has no closing }")),
+                    _ => (),
+                }
+                tokens.push(importer.consume_token().unwrap());
+            }
+        } else {
+            // One more than the previous value
+            tokens = match &previous {
+                Some(previous) => vec![
+                    CToken::Identifier(String::clone(previous)),
+                    CToken::Symbol("+"),
+                    CToken::Int(1, String::new()),
+                ],
+                None => vec![CToken::Int(0, String::new())],
+            };
+        }
+        let result = convert_expression(importer, &tokens)
+            .and_then(|(expression, primitive)| match primitive {
+                "i32" => write_constant(importer, &name, "i32", &expression),
+                _ => Err(format!("value is a {}, not an int", primitive)),
+            });
+        match result {
+            Ok(()) => previous = Some(name),
+            Err(message) => {
+                println!("Warning: skipped enum constant {}: {}", name, message);
+                previous = None;
+            },
+        }
+        if !importer.consume_symbol(",") {
+            importer.expect_symbol("}")?;
+            break;
+        }
+    }
+    return Ok(CType::Primitive("i32"));
+}
+
+fn import_typedef(importer: &mut Importer, name: &String, ctype: CType) -> Result<(), String> {
+    let ctype = match ctype {
+        CType::Anonymous(members) => {
+            write_structure(importer, name, &members)?;
+            CType::Struct(String::clone(name))
+        },
+        // Names that are the same as the struct or can't be identifiers,
+        // and types without an equivalent datatype are replaced with their
+        // type where used
+        CType::Struct(ref tag) if tag == name => ctype,
+        CType::Void | CType::Opaque | CType::Function(..) => ctype,
+        _ if name.starts_with('_') => ctype,
+        _ => {
+            let datatype = get_datatype(&ctype)?;
+            importer.declare_name(name)?;
+            writeln!(importer.output, "{}: type = {};", name, datatype).unwrap();
+            writeln!(importer.output).unwrap();
+            CType::Alias(String::clone(name), Box::new(ctype))
+        },
+    };
+    importer.typedefs.insert(String::clone(name), ctype);
+    return Ok(());
+}
+
+fn import_function(importer: &mut Importer, name: &String, arguments: &Vec<(Option<String>, CType)>, returned: &CType, variadic: bool) -> Result<(), String> {
+    if variadic && arguments.is_empty() {
+        return Err(format!("{} is variadic without an argument before ...", name));
+    }
+    let mut declarations: Vec<String> = Vec::new();
+    for (i, (argument, ctype)) in arguments.iter().enumerate() {
+        let argument = match argument {
+            Some(argument) => get_identifier(argument),
+            None => format!("arg{}", i),
+        };
+        // Structs are passed to C in a different way, which isn't supported
+        if is_struct(ctype) {
+            return Err(format!("argument {} of {} is a struct passed by value, which isn't supported", argument, name));
+        }
+        let datatype = get_datatype(ctype)
+            .map_err(|message| format!("argument {} of {}: {}", argument, name, message))?;
+        declarations.push(format!("{}: {}", argument, datatype));
+    }
+    if variadic {
+        declarations.push(String::from("..."));
+    }
+    let returns = if is_void(returned) {
+        String::new()
+    } else if is_struct(returned) {
+        return Err(format!("{} returns a struct by value, which isn't supported", name));
+    } else {
+        let datatype = get_datatype(returned)
+            .map_err(|message| format!("return of {}: {}", name, message))?;
+        format!(" -> ({})", datatype)
+    };
+    importer.declare_name(name)?;
+    writeln!(importer.output, "{}: extern function({}){};", name, declarations.join(", "), returns).unwrap();
+    writeln!(importer.output).unwrap();
+    return Ok(());
+}
+
+// Headers that can be included from C++ wrap their declarations in
+// extern "C" { }, or start each declaration with extern "C"
+fn import_linkage(importer: &mut Importer) -> bool {
+    // "extern" , literal , [ "{" ] | "}"
+    if importer.is_symbol("}") && importer.linkage_blocks > 0 {
+        importer.consume_token();
+        importer.linkage_blocks -= 1;
+        return true;
+    }
+    let linkage = match (importer.peek_token(), importer.peek_ahead(1)) {
+        (Some(CToken::Identifier(word)), Some(CToken::Literal)) => word == "extern",
+        _ => false,
+    };
+    if !linkage {
+        return false;
+    }
+    importer.consume_token();
+    importer.consume_token();
+    if importer.consume_symbol("{") {
+        importer.linkage_blocks += 1;
+    }
+    return true;
+}
+
+pub fn import_declaration(importer: &mut Importer) -> Result<(), String> {
+    // #define | linkage | specifiers , [ declarator , { "," , declarator } ] , ";"
+    if let Some(CToken::Define(name, body)) = importer.peek_token() {
+        let (name, body) = (String::clone(name), body.clone());
+        importer.consume_token();
+        return import_define(importer, &name, &body);
+    }
+    if import_linkage(importer) {
+        return Ok(());
+    }
+    if importer.consume_symbol(";") {
+        return Ok(());
+    }
+    let specifiers = parse_specifiers(importer)?;
+    if importer.consume_symbol(";") {
+        // Only defines a struct or enum
+        return Ok(());
+    }
+    loop {
+        let (name, ctype) = parse_declarator(importer, CType::clone(&specifiers.ctype), specifiers.constant)?;
+        let name = match name {
+            Some(name) => name,
+            None => return Err(String::from("expected a name in declaration")),
+        };
+        if importer.is_symbol("{") {
+            return Err(format!("{} is defined in the header, so can't be linked with", name));
+        }
+        if specifiers.typedef {
+            import_typedef(importer, &name, ctype)?;
+        } else {
+            match ctype {
+                CType::Function(arguments, returned, variadic) => import_function(importer, &name, &arguments, &returned, variadic)?,
+                _ => return Err(format!("{} is a variable, which isn't imported", name)),
+            }
+        }
+        if !importer.consume_symbol(",") {
+            break;
+        }
+    }
+    return importer.expect_symbol(";");
+}
+
+// Skips the rest of a declaration that can't be imported, up to its ";",
+// or the end of a function body. A "}" closing a block the declaration is
+// in ends it too, and is left for the block.
+pub fn skip_declaration(importer: &mut Importer) {
+    if let Some(CToken::Define(..)) = importer.peek_token() {
+        importer.consume_token();
+        return;
+    }
+    if importer.consume_symbol("}") {
+        // Doesn't close anything
+        return;
+    }
+    let mut depth: usize = 0;
+    let mut braces: usize = 0;
+    let mut body = false;
+    let mut previous: Option<CToken> = None;
+    while let Some(token) = importer.peek_token() {
+        if braces == 0 && *token == CToken::Symbol("}") {
+            return;
+        }
+        let token = importer.consume_token().unwrap();
+        match token {
+            CToken::Symbol("(" | "[") => depth += 1,
+            CToken::Symbol(")" | "]") => depth = depth.saturating_sub(1),
+            CToken::Symbol("{") => {
+                if depth == 0 && previous == Some(CToken::Symbol(")")) {
+                    body = true;
+                }
+                depth += 1;
+                braces += 1;
+            },
+            CToken::Symbol("}") => {
+                depth = depth.saturating_sub(1);
+                braces -= 1;
+                if depth == 0 && body {
+                    return;
+                }
+            },
+            CToken::Symbol(";") if depth == 0 => return,
+            _ => (),
+        }
+        previous = Some(token);
+    }
+}
@@ -0,0 +1,112 @@
+use std::collections::HashMap;
+use std::collections::HashSet;
+
+use crate::lexer::is_keyword;
+use super::lexer::CToken;
+use super::datatype::CType;
+
+
+pub struct Importer<'a> {
+    tokens: &'a Vec<CToken>,
+    position: usize,
+    pub output: String,
+    pub typedefs: HashMap<String, CType>,
+    pub tags: HashMap<String, CType>, // struct tag -> type it was declared as
+    pub constants: HashMap<String, &'static str>, // name -> primitive
+    pub linkage_blocks: usize, // extern "C" { blocks that haven't been closed
+    names: HashSet<String>, // Everything declared in the output
+}
+
+// Types from the standard headers, which headers use without defining
+const STANDARD_TYPEDEFS: [(&str, &str); 16] = [
+    ("int8_t", "i8"), ("int16_t", "i16"), ("int32_t", "i32"), ("int64_t", "i64"),
+    ("uint8_t", "u8"), ("uint16_t", "u16"), ("uint32_t", "u32"), ("uint64_t", "u64"),
+    ("intptr_t", "i64"), ("uintptr_t", "u64"), ("intmax_t", "i64"), ("uintmax_t", "u64"),
+    ("size_t", "u64"), ("ssize_t", "i64"), ("ptrdiff_t", "i64"), ("off_t", "i64"),
+];
+
+impl<'a> Importer<'a> {
+    pub fn new(tokens: &'a Vec<CToken>) -> Importer<'a> {
+        let mut typedefs = HashMap::new();
+        for (name, primitive) in STANDARD_TYPEDEFS {
+            typedefs.insert(String::from(name), CType::Primitive(primitive));
+        }
+        Importer {
+            tokens: tokens,
+            position: 0,
+            output: String::new(),
+            typedefs: typedefs,
+            tags: HashMap::new(),
+            constants: HashMap::new(),
+            linkage_blocks: 0,
+            names: HashSet::new(),
+        }
+    }
+
+    pub fn peek_token(&self) -> Option<&CToken> {
+        self.tokens.get(self.position)
+    }
+
+    pub fn peek_ahead(&self, offset: usize) -> Option<&CToken> {
+        self.tokens.get(self.position + offset)
+    }
+
+    pub fn consume_token(&mut self) -> Option<CToken> {
+        let token = self.tokens.get(self.position).cloned();
+        if token.is_some() {
+            self.position += 1;
+        }
+        token
+    }
+
+    pub fn is_symbol(&self, symbol: &str) -> bool {
+        match self.peek_token() {
+            Some(CToken::Symbol(next)) => *next == symbol,
+            _ => false,
+        }
+    }
+
+    pub fn consume_symbol(&mut self, symbol: &str) -> bool {
+        if self.is_symbol(symbol) {
+            self.position += 1;
+            return true;
+        }
+        return false;
+    }
+
+    pub fn expect_symbol(&mut self, symbol: &str) -> Result<(), String> {
+        if self.consume_symbol(symbol) {
+            return Ok(());
+        }
+        match self.peek_token() {
+            Some(token) => Err(format!("expected {} but found {}", symbol, token)),
+            None => Err(format!("expected {} at the end of the header", symbol)),
+        }
+    }
+
+    pub fn get_position(&self) -> usize {
+        self.position
+    }
+
+    pub fn set_position(&mut self, position: usize) {
+        self.position = position;
+    }
+
+    pub fn at_end(&self) -> bool {
+        self.position >= self.tokens.len()
+    }
+
+    // Claims a name in the output, which must be unique
+    pub fn declare_name(&mut self, name: &String) -> Result<(), String> {
+        if is_keyword(name) {
+            return Err(format!("{} is a keyword", name));
+        }
+        if name.starts_with('_') {
+            return Err(format!("{} starts with _, which identifiers can't", name));
+        }
+        if !self.names.insert(String::clone(name)) {
+            return Err(format!("{} is already declared", name));
+        }
+        Ok(())
+    }
+}
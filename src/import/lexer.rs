@@ -0,0 +1,207 @@
+use std::fmt;
+
+#[derive(Clone, PartialEq)]
+pub enum CToken {
+    Identifier(String),
+    Int(u128, String), // value, suffix in lower case
+    Float(f64, bool), // value, has the f suffix
+    Literal, // string or character literal, which are never imported
+    Symbol(&'static str),
+    Define(String, Vec<CToken>), // name, body of an object-like macro
+}
+
+impl fmt::Display for CToken {
+    fn fmt (&self, fmt: &mut std::fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CToken::Identifier(identifier) => write!(fmt, "{}", identifier),
+            CToken::Int(value, suffix) => write!(fmt, "{}{}", value, suffix),
+            CToken::Float(value, _) => write!(fmt, "{:?}", value),
+            CToken::Literal => write!(fmt, "literal"),
+            CToken::Symbol(symbol) => write!(fmt, "{}", symbol),
+            CToken::Define(name, _) => write!(fmt, "#define {}", name),
+        }
+    }
+}
+
+// Longest first, so the longest symbol is matched
+const SYMBOLS: [&str; 49] = [
+    "...", "<<=", ">>=",
+    "<<", ">>", "->", "++", "--", "&&", "||", "==", "!=", "<=", ">=",
+    "+=", "-=", "*=", "/=", "%=", "&=", "|=", "^=", "##",
+    "{", "}", "[", "]", "(", ")", "*", ",", ";", ":", "=", "+", "-", "/",
+    "%", "&", "|", "^", "~", "!", "<", ">", "?", ".", "#", "\\",
+];
+
+struct CLexer {
+    chars: Vec<char>,
+    position: usize,
+    line_start: bool, // Only whitespace since the start of the line
+    tokens: Vec<CToken>,
+}
+
+impl CLexer {
+    fn peek_char(&self, offset: usize) -> Option<char> {
+        self.chars.get(self.position + offset).copied()
+    }
+
+    fn read_tokens(&mut self) {
+        while let Some(c) = self.peek_char(0) {
+            if c == '\n' {
+                self.position += 1;
+                self.line_start = true;
+            } else if c.is_whitespace() {
+                self.position += 1;
+            } else if c == '/' && self.peek_char(1) == Some('/') {
+                while !matches!(self.peek_char(0), Some('\n') | None) {
+                    self.position += 1;
+                }
+            } else if c == '/' && self.peek_char(1) == Some('*') {
+                self.position += 2;
+                while self.peek_char(0).is_some() && !(self.peek_char(0) == Some('*') && self.peek_char(1) == Some('/')) {
+                    self.position += 1;
+                }
+                self.position += 2;
+            } else if c == '#' && self.line_start {
+                self.read_directive();
+            } else {
+                self.line_start = false;
+                let token = self.read_token(c);
+                self.tokens.push(token);
+            }
+        }
+    }
+
+    // Only object-like #define is kept, everything else given to the
+    // preprocessor is ignored.
+    fn read_directive(&mut self) {
+        self.position += 1;
+        let mut line = String::new();
+        while let Some(c) = self.peek_char(0) {
+            if c == '\n' {
+                break;
+            }
+            self.position += 1;
+            if c == '\\' && self.peek_char(0) == Some('\n') {
+                self.position += 1;
+                line.push(' ');
+                continue;
+            }
+            line.push(c);
+        }
+
+        let line = line.trim_start();
+        let directive: String = line.chars().take_while(|c| c.is_ascii_alphanumeric()).collect();
+        if directive != "define" {
+            return;
+        }
+        let line = line[directive.len()..].trim_start();
+        let name: String = line.chars().take_while(|c| c.is_ascii_alphanumeric() || *c == '_').collect();
+        let body = &line[name.len()..];
+        if name.is_empty() || body.starts_with('(') {
+            // Function-like macro
+            return;
+        }
+        let body = read_tokens(body, false);
+        self.tokens.push(CToken::Define(name, body));
+    }
+
+    fn read_token(&mut self, c: char) -> CToken {
+        if c.is_ascii_alphabetic() || c == '_' {
+            let mut word = String::new();
+            while let Some(c) = self.peek_char(0) {
+                if !(c.is_ascii_alphanumeric() || c == '_') {
+                    break;
+                }
+                word.push(c);
+                self.position += 1;
+            }
+            return CToken::Identifier(word);
+        }
+        if c.is_ascii_digit() || (c == '.' && self.peek_char(1).map_or(false, |c| c.is_ascii_digit())) {
+            return self.read_number();
+        }
+        if c == '"' || c == '\'' {
+            self.position += 1;
+            while let Some(next) = self.peek_char(0) {
+                self.position += 1;
+                if next == '\\' {
+                    self.position += 1;
+                } else if next == c || next == '\n' {
+                    break;
+                }
+            }
+            return CToken::Literal;
+        }
+        for symbol in SYMBOLS {
+            let length = symbol.len();
+            if self.position + length <= self.chars.len() && self.chars[self.position..self.position + length].iter().copied().eq(symbol.chars()) {
+                self.position += length;
+                return CToken::Symbol(symbol);
+            }
+        }
+        self.position += 1;
+        return CToken::Literal;
+    }
+
+    fn read_number(&mut self) -> CToken {
+        // Read like the preprocessor, then work out what kind of number it is
+        let mut string = String::new();
+        while let Some(c) = self.peek_char(0) {
+            let exponent_sign = (c == '+' || c == '-') && matches!(string.chars().last(), Some('e' | 'E' | 'p' | 'P'));
+            if !(c.is_ascii_alphanumeric() || c == '_' || c == '.' || exponent_sign) {
+                break;
+            }
+            string.push(c);
+            self.position += 1;
+        }
+        return parse_number(&string.to_ascii_lowercase());
+    }
+}
+
+fn parse_number(string: &String) -> CToken {
+    let hex = string.starts_with("0x");
+    let float = if hex {
+        string.contains('.') || string.contains('p')
+    } else {
+        string.contains('.') || string.contains('e')
+    };
+    if float {
+        if hex || string.ends_with('l') {
+            // Hexadecimal float or long double
+            return CToken::Literal;
+        }
+        let single = string.ends_with('f');
+        let digits = string.trim_end_matches('f');
+        return match digits.parse::<f64>() {
+            Ok(value) => CToken::Float(value, single),
+            Err(_) => CToken::Literal,
+        };
+    }
+
+    let digits = string.trim_end_matches(|c| c == 'u' || c == 'l');
+    let suffix = String::from(&string[digits.len()..]);
+    let (digits, radix) = if hex {
+        (&digits[2..], 16)
+    } else if digits.starts_with("0b") {
+        (&digits[2..], 2)
+    } else if digits.len() > 1 && digits.starts_with('0') {
+        (&digits[1..], 8)
+    } else {
+        (digits, 10)
+    };
+    return match u128::from_str_radix(digits, radix) {
+        Ok(value) => CToken::Int(value, suffix),
+        Err(_) => CToken::Literal,
+    };
+}
+
+pub fn read_tokens(content: &str, line_start: bool) -> Vec<CToken> {
+    let mut lexer = CLexer {
+        chars: content.chars().collect(),
+        position: 0,
+        line_start: line_start,
+        tokens: Vec::new(),
+    };
+    lexer.read_tokens();
+    return lexer.tokens;
+}
@@ -0,0 +1,27 @@
+mod lexer;
+mod importer;
+mod datatype;
+mod declaration;
+
+use lexer::read_tokens;
+use importer::Importer;
+use declaration::{import_declaration, skip_declaration};
+
+
+// Extern declarations for what a C header declares, as far as there's an
+// equivalent: function prototypes, structs, enums, typedefs and #defines of
+// constants. Anything else is skipped with a warning. The header should be
+// preprocessed first if it relies on macros.
+pub fn import_c_header(content: &String) -> String {
+    let tokens = read_tokens(content, true);
+    let mut importer = Importer::new(&tokens);
+    while !importer.at_end() {
+        let start = importer.get_position();
+        if let Err(message) = import_declaration(&mut importer) {
+            println!("Warning: skipped declaration: {}", message);
+            importer.set_position(start);
+            skip_declaration(&mut importer);
+        }
+    }
+    return importer.output;
+}
@@ -5,27 +5,51 @@ use std::iter::Peekable;
 use super::token::*;
 
 pub struct Lexer<'a> {
+    file: &'a str, // Name of the source file, for errors
     input_iter: Peekable<Chars<'a>>,
     tokens: &'a mut Vec<Token>,
+    locations: &'a mut Vec<Location>, // Where each token starts
+    pending: Option<Token>,
+    location: Location,
+    token_location: Location,
 }
 
 impl<'a> Lexer<'a> {
-    pub fn new(input: &'a String, tokens: &'a mut Vec<Token>) -> Lexer<'a> {
+    pub fn new(file: &'a str, input: &'a String, tokens: &'a mut Vec<Token>, locations: &'a mut Vec<Location>) -> Lexer<'a> {
         Lexer {
+            file: file,
             input_iter: input.chars().peekable(),
             tokens: tokens,
+            locations: locations,
+            pending: None,
+            location: Location { line: 1, column: 1 },
+            token_location: Location { line: 1, column: 1 },
         }
     }
 
+    // Errors are reported at the start of the token being read
+    fn error(&self, message: &str) -> ! {
+        panic!("{}:{}: {}", self.file, self.token_location, message);
+    }
+
     fn read_char(&mut self) -> Option<char> {
-        self.input_iter.next()
+        let c = self.input_iter.next();
+        match c {
+            Some('\n') => {
+                self.location.line += 1;
+                self.location.column = 1;
+            },
+            Some(_) => self.location.column += 1,
+            None => (),
+        }
+        c
     }
 
     fn is_letter(c: char) -> bool {
         c.is_alphabetic() || c == '_'
     }
 
-    fn lookup_keyword(id: &String) -> Option<Keyword> {
+    pub fn lookup_keyword(id: &String) -> Option<Keyword> {
         match id.as_str() {
             "u8" => Some(Keyword::U8),
             "u16" => Some(Keyword::U16),
@@ -38,10 +62,31 @@ impl<'a> Lexer<'a> {
             "f32" => Some(Keyword::F32),
             "f64" => Some(Keyword::F64),
             "c8" => Some(Keyword::C8),
+            "bool" => Some(Keyword::Bool),
             "mut" => Some(Keyword::Mut),
             "return" => Some(Keyword::Return),
             "function" => Some(Keyword::Function),
             "struct" => Some(Keyword::Struct),
+            "type" => Some(Keyword::Type),
+            "distinct" => Some(Keyword::Distinct),
+            "const" => Some(Keyword::Const),
+            "sizeof" => Some(Keyword::Sizeof),
+            "alignof" => Some(Keyword::Alignof),
+            "offsetof" => Some(Keyword::Offsetof),
+            "defer" => Some(Keyword::Defer),
+            "loop" => Some(Keyword::Loop),
+            "break" => Some(Keyword::Break),
+            "continue" => Some(Keyword::Continue),
+            "new" => Some(Keyword::New),
+            "cast" => Some(Keyword::Cast),
+            "syscall" => Some(Keyword::Syscall),
+            "undefined" => Some(Keyword::Undefined),
+            "null" => Some(Keyword::Null),
+            "if" => Some(Keyword::If),
+            "else" => Some(Keyword::Else),
+            "while" => Some(Keyword::While),
+            "extern" => Some(Keyword::Extern),
+            "pub" => Some(Keyword::Pub),
             _ => None,
         }
     }
@@ -73,50 +118,213 @@ impl<'a> Lexer<'a> {
         word
     }
 
-    fn read_string(&mut self) -> String {
-        let mut string = String::new();
-        let mut escape = false;
+    fn read_hex_digit(&mut self, sequence: &str) -> u32 {
+        match self.read_char().and_then(|c| c.to_digit(16)) {
+            Some(digit) => digit,
+            None => self.error(&format!("Expected hex digit in {} escape sequence", sequence)),
+        }
+    }
+
+    // Escape sequence after a backslash, appended as bytes. \xNN is a single
+    // byte, \u{...} is a unicode code point encoded as UTF-8.
+    fn read_escape(&mut self, bytes: &mut Vec<u8>) {
+        let byte = match self.read_char() {
+            Some('n') => b'\n',
+            Some('t') => b'\t',
+            Some('r') => b'\r',
+            Some('0') => 0,
+            Some('\\') => b'\\',
+            Some('"') => b'"',
+            Some('\'') => b'\'',
+            Some('x') => {
+                let high = self.read_hex_digit("\\x");
+                let low = self.read_hex_digit("\\x");
+                (high * 16 + low) as u8
+            },
+            Some('u') => {
+                match self.read_char() {
+                    Some('{') => (),
+                    _ => self.error("Expected '{' after \\u"),
+                }
+                let mut value: u32 = 0;
+                let mut digits = 0;
+                loop {
+                    if let Some('}') = self.peek_char() {
+                        let _ = self.read_char();
+                        break;
+                    }
+                    if digits == 6 {
+                        self.error("\\u{...} escape sequence has more than 6 hex digits");
+                    }
+                    value = value * 16 + self.read_hex_digit("\\u{...}");
+                    digits += 1;
+                }
+                if digits == 0 {
+                    self.error("\\u{} escape sequence has no hex digits");
+                }
+                let c = match char::from_u32(value) {
+                    Some(c) => c,
+                    None => self.error(&format!("\\u{{{:x}}} isn't a unicode code point", value)),
+                };
+                let mut buffer = [0; 4];
+                bytes.extend_from_slice(c.encode_utf8(&mut buffer).as_bytes());
+                return;
+            },
+            Some(c) => self.error(&format!("Unknown escape sequence \\{}", c)),
+            None => self.error("Unterminated escape sequence"),
+        };
+        bytes.push(byte);
+    }
+
+    // Character within a string or character literal, appended as bytes
+    fn read_literal_char(&mut self, c: char, bytes: &mut Vec<u8>) {
+        if c == '\\' {
+            self.read_escape(bytes);
+        } else {
+            let mut buffer = [0; 4];
+            bytes.extend_from_slice(c.encode_utf8(&mut buffer).as_bytes());
+        }
+    }
+
+    fn read_string(&mut self) -> Vec<u8> {
+        let mut string: Vec<u8> = Vec::new();
+        loop {
+            match self.read_char() {
+                Some('"') => break,
+                Some(c) => self.read_literal_char(c, &mut string),
+                None => self.error("Unterminated string literal"),
+            }
+        }
+        string
+    }
+
+    // A character literal is a single byte, for c8
+    fn read_character(&mut self) -> u8 {
+        let mut bytes: Vec<u8> = Vec::new();
+        match self.read_char() {
+            Some('\'') => self.error("Empty character literal"),
+            Some(c) => self.read_literal_char(c, &mut bytes),
+            None => self.error("Unterminated character literal"),
+        }
+        match self.read_char() {
+            Some('\'') => (),
+            Some(_) => self.error("Character literal contains more than one character"),
+            None => self.error("Unterminated character literal"),
+        }
+        if bytes.len() != 1 {
+            self.error(&format!("Character literal doesn't fit in a c8, it is {} bytes as UTF-8", bytes.len()));
+        }
+        bytes[0]
+    }
+
+    // Digits of a number in the given base. Underscores can be used to
+    // separate digits, and are skipped. A decimal digit that isn't valid in
+    // the base is an error, rather than the start of another number.
+    fn read_digits(&mut self, radix: u32, digits: &mut String) {
         while let Some(&c) = self.peek_char() {
-            if !escape && c == '"' {
-                let _ = self.read_char();
-                break;
-            } if !escape && c == '\\' {
+            if c.is_digit(radix) {
+                digits.push(self.read_char().unwrap());
+            } else if c.is_ascii_digit() {
+                self.error(&format!("Invalid digit {} in base {} number literal", c, radix));
+            } else if c == '_' {
                 let _ = self.read_char();
-                escape = true;
             } else {
-                string.push(self.read_char().unwrap());
-                escape = false;
+                break;
             }
         }
-        string
+    }
+
+    // A suffix gives the primitive of a literal, eg: 255u8, 1.5f32.
+    // It is passed on as the keyword for the primitive, after the constant.
+    fn read_suffix(&mut self, float: bool) -> Option<Keyword> {
+        match self.peek_char() {
+            Some(&c) if Self::is_letter(c) => (),
+            _ => return None,
+        }
+        let c = self.read_char().unwrap();
+        let suffix = self.read_word(c);
+        let keyword = match Self::lookup_keyword(&suffix) {
+            Some(keyword @ (Keyword::U8 | Keyword::U16 | Keyword::U32 | Keyword::U64
+                            | Keyword::I8 | Keyword::I16 | Keyword::I32 | Keyword::I64
+                            | Keyword::F32 | Keyword::F64)) => keyword,
+            _ => self.error(&format!("Invalid suffix {} on number literal", suffix)),
+        };
+        match keyword {
+            Keyword::F32 | Keyword::F64 => (),
+            _ if float => self.error(&format!("Floating point literal can't have integer suffix {}", suffix)),
+            _ => (),
+        }
+        Some(keyword)
     }
 
     fn read_number(&mut self, c: char) -> Constant {
+        // Integers can be given in hexadecimal, octal or binary with a prefix
+        let radix = match (c, self.peek_char()) {
+            ('0', Some('x')) => Some(16),
+            ('0', Some('o')) => Some(8),
+            ('0', Some('b')) => Some(2),
+            _ => None,
+        };
         let mut string = String::new();
         let mut float = false;
-        string.push(c);
-        while let Some(&c) = self.peek_char() {
-            if c.is_digit(10) {
+        if let Some(radix) = radix {
+            let prefix = self.read_char().unwrap();
+            self.read_digits(radix, &mut string);
+            if string.is_empty() {
+                self.error(&format!("Expected digits after 0{} in number literal", prefix));
+            }
+        } else {
+            string.push(c);
+            self.read_digits(10, &mut string);
+            if let Some('.') = self.peek_char() {
+                float = true;
                 string.push(self.read_char().unwrap());
-            } else if c == '.' {
-                if float {
-                    panic!("Two periods in number");
-                }
+                self.read_digits(10, &mut string);
+            }
+            if let Some('e' | 'E') = self.peek_char() {
                 float = true;
                 string.push(self.read_char().unwrap());
-            } else {
-                break;
+                if let Some(&sign @ ('+' | '-')) = self.peek_char() {
+                    let _ = self.read_char();
+                    string.push(sign);
+                }
+                let digits = string.len();
+                self.read_digits(10, &mut string);
+                if string.len() == digits {
+                    self.error(&format!("Expected digits in exponent of {}", string));
+                }
             }
         }
-        if float {
-            Constant::Float(string.parse::<f64>().unwrap())
+
+        let suffix = self.read_suffix(float);
+        let float_suffix = matches!(suffix, Some(Keyword::F32 | Keyword::F64));
+        if let Some(keyword) = suffix {
+            self.pending = Some(Token::Keyword(keyword));
+        }
+
+        // Integer literals are kept exact, so they can be range checked
+        // against the primitive they are used as
+        if float || float_suffix {
+            let value = match radix {
+                Some(radix) => u64::from_str_radix(&string, radix).unwrap() as f64,
+                None => string.parse::<f64>().unwrap(),
+            };
+            Constant::Float(value)
         } else {
-            Constant::Int(string.parse::<i64>().unwrap())
+            match u64::from_str_radix(&string, radix.unwrap_or(10)) {
+                Ok(value) => Constant::Int(value as i128),
+                Err(_) => self.error(&format!("Integer literal {} doesn't fit in 64 bits", string)),
+            }
         }
     }
 
     fn next_token(&mut self) -> Option<Token> {
+        // Some tokens are read together with the previous one
+        if let Some(token) = self.pending.take() {
+            return Some(token);
+        }
         self.skip_whitespace();
+        self.token_location = self.location;
         if let Some(c) = self.read_char() {
             match c {
                 // Handle multi-character symbols first.
@@ -140,19 +348,25 @@ impl<'a> Lexer<'a> {
                 '*' => Some(Token::Asterisk),
                 '\\' => Some(Token::LSlash),
                 '/' => Some(Token::RSlash),
-                '<' => Some(Token::GreaterThan),
-                '>' => Some(Token::LessThan),
-                '\'' => Some(Token::Apostrophe),
+                '<' => Some(Token::LessThan),
+                '>' => Some(Token::GreaterThan),
+                '\'' => Some(Token::Constant(Constant::Char(self.read_character()))),
                 '~' => Some(Token::Tilde),
                 '_' => Some(Token::Underscore),
                 '$' => Some(Token::Dollar),
                 '!' => Some(Token::Exclamation),
                 '?' => Some(Token::Question),
                 '`' => Some(Token::Grave),
+                '#' => Some(Token::Hash),
                 '"' => Some(Token::Constant(Constant::Str(self.read_string()))),
                 _ => {
                     if Self::is_letter(c) {
                         let word = self.read_word(c);
+                        match word.as_str() {
+                            "true" => return Some(Token::Constant(Constant::Bool(true))),
+                            "false" => return Some(Token::Constant(Constant::Bool(false))),
+                            _ => (),
+                        }
                         match Self::lookup_keyword(&word) {
                             Some(keyword) => Some(Token::Keyword(keyword)),
                             None => Some(Token::Identifier(word)),
@@ -172,11 +386,15 @@ impl<'a> Lexer<'a> {
     pub fn read_tokens(&mut self) {
         loop {
             match self.next_token() {
-                Some(token) => self.tokens.push(token),
+                Some(token) => {
+                    self.tokens.push(token);
+                    self.locations.push(self.token_location);
+                },
                 None => break,
             }
         }
         self.tokens.push(Token::End);
+        self.locations.push(self.location);
     }
 }
 
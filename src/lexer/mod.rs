@@ -3,13 +3,24 @@ pub mod token;
 mod lexer;
 
 use token::Token;
+use token::Location;
 use lexer::Lexer;
 
-pub fn read_tokens(content: &String) -> Vec<Token> {
+// Returns the tokens, and where each one starts
+pub fn read_tokens(file: &str, content: &String) -> (Vec<Token>, Vec<Location>) {
     let mut tokens: Vec<Token> = Vec::new();
-    let mut lexer = Lexer::new(&content, &mut tokens);
+    let mut locations: Vec<Location> = Vec::new();
+    let mut lexer = Lexer::new(file, &content, &mut tokens, &mut locations);
     lexer.read_tokens();
-    tokens
+    (tokens, locations)
+}
+
+// Whether a word is reserved, so can't be used as an identifier
+pub fn is_keyword(word: &String) -> bool {
+    match word.as_str() {
+        "true" | "false" => true,
+        _ => Lexer::lookup_keyword(word).is_some(),
+    }
 }
 
 pub fn print_tokens(tokens: &Vec<Token>) {
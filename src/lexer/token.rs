@@ -17,6 +17,27 @@ pub enum Keyword {
     Return,
     Function,
     Struct,
+    Type,
+    Distinct,
+    Const,
+    Sizeof,
+    Alignof,
+    Offsetof,
+    Defer,
+    Loop,
+    Break,
+    Continue,
+    New,
+    Cast,
+    Syscall,
+    Undefined,
+    Null,
+    Bool,
+    If,
+    Else,
+    While,
+    Extern,
+    Pub,
 }
 
 impl fmt::Display for Keyword {
@@ -37,15 +58,38 @@ impl fmt::Display for Keyword {
             Keyword::Return => write!(fmt, "Keyword(return)"),
             Keyword::Function => write!(fmt, "Keyword(function)"),
             Keyword::Struct => write!(fmt, "Keyword(struct)"),
+            Keyword::Type => write!(fmt, "Keyword(type)"),
+            Keyword::Distinct => write!(fmt, "Keyword(distinct)"),
+            Keyword::Const => write!(fmt, "Keyword(const)"),
+            Keyword::Sizeof => write!(fmt, "Keyword(sizeof)"),
+            Keyword::Alignof => write!(fmt, "Keyword(alignof)"),
+            Keyword::Offsetof => write!(fmt, "Keyword(offsetof)"),
+            Keyword::Defer => write!(fmt, "Keyword(defer)"),
+            Keyword::Loop => write!(fmt, "Keyword(loop)"),
+            Keyword::Break => write!(fmt, "Keyword(break)"),
+            Keyword::Continue => write!(fmt, "Keyword(continue)"),
+            Keyword::New => write!(fmt, "Keyword(new)"),
+            Keyword::Cast => write!(fmt, "Keyword(cast)"),
+            Keyword::Syscall => write!(fmt, "Keyword(syscall)"),
+            Keyword::Undefined => write!(fmt, "Keyword(undefined)"),
+            Keyword::Null => write!(fmt, "Keyword(null)"),
+            Keyword::Bool => write!(fmt, "Keyword(bool)"),
+            Keyword::If => write!(fmt, "Keyword(if)"),
+            Keyword::Else => write!(fmt, "Keyword(else)"),
+            Keyword::While => write!(fmt, "Keyword(while)"),
+            Keyword::Extern => write!(fmt, "Keyword(extern)"),
+            Keyword::Pub => write!(fmt, "Keyword(pub)"),
         }
     }
 }
 
 #[derive(Clone)]
 pub enum Constant {
-    Int(i64),
+    Int(i128),
     Float(f64),
-    Str(String),
+    Str(Vec<u8>),
+    Char(u8),
+    Bool(bool),
 }
 
 impl fmt::Display for Constant {
@@ -53,11 +97,27 @@ impl fmt::Display for Constant {
         match self {
             Constant::Int(int) => write!(fmt, "Int({})", int),
             Constant::Float(float) => write!(fmt, "Float({})", float),
-            Constant::Str(string) => write!(fmt, "String({})", string),
+            Constant::Str(string) => write!(fmt, "String({})", String::from_utf8_lossy(string).escape_default()),
+            Constant::Char(c) => write!(fmt, "Char({})", std::ascii::escape_default(*c)),
+            Constant::Bool(boolean) => write!(fmt, "Bool({})", boolean),
         }
     }
 }
 
+// Position of a token in the source file, counting from 1. Columns count
+// characters, not bytes.
+#[derive(Clone, Copy, PartialEq, PartialOrd)]
+pub struct Location {
+    pub line: usize,
+    pub column: usize,
+}
+
+impl fmt::Display for Location {
+    fn fmt (&self, fmt: &mut std::fmt::Formatter<'_>) -> fmt::Result {
+        write!(fmt, "{}:{}", self.line, self.column)
+    }
+}
+
 #[derive(Clone)]
 pub enum Token {
     LParen, //      (
@@ -79,9 +139,8 @@ pub enum Token {
     Asterisk, //    *
     RSlash, //      /
     LSlash, //      \
-    LessThan, //    >
-    GreaterThan, // <
-    Apostrophe, //  '
+    LessThan, //    <
+    GreaterThan, // >
     Tilde, //       ~
     VBar, //        |
     Underscore, //  _
@@ -89,6 +148,7 @@ pub enum Token {
     Exclamation, // !
     Question, //    ?
     Grave, //       `
+    Hash, //        #
     Constant(Constant),
     Keyword(Keyword),
     Identifier(String),
@@ -119,9 +179,8 @@ impl fmt::Display for Token {
             Token::Asterisk => write!(fmt, "*"),
             Token::RSlash => write!(fmt, "/"),
             Token::LSlash => write!(fmt, "\\"),
-            Token::LessThan => write!(fmt, ">"),
-            Token::GreaterThan => write!(fmt, "<"),
-            Token::Apostrophe => write!(fmt, "'"),
+            Token::LessThan => write!(fmt, "<"),
+            Token::GreaterThan => write!(fmt, ">"),
             Token::Tilde => write!(fmt, "~"),
             Token::VBar => write!(fmt, "|"),
             Token::Underscore => write!(fmt, "_"),
@@ -129,6 +188,7 @@ impl fmt::Display for Token {
             Token::Exclamation => write!(fmt, "!"),
             Token::Question => write!(fmt, "?"),
             Token::Grave => write!(fmt, "`"),
+            Token::Hash => write!(fmt, "#"),
             Token::Constant(constant) => write!(fmt, "Constant({})", constant),
             Token::Keyword(keyword) => write!(fmt, "Keyword({})", keyword),
             Token::Identifier(identifier) => write!(fmt, "Identifier({})", identifier),
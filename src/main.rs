@@ -1,62 +1,170 @@
 use std::env;
 use std::fs;
 use std::io::{BufReader, Read, BufWriter, Write};
+use std::path::PathBuf;
+use std::process::{self, Command, Stdio};
 
+mod options;
 mod lexer;
 mod parser;
 mod generator;
-// mod compiler;
+mod compiler;
+mod import;
+mod mangle;
 
+use options::parse_options;
+use options::Options;
+use options::Emit;
 use lexer::read_tokens;
 // use lexer::print_tokens;
 use parser::build_ast;
 use parser::print_ast;
+use parser::ast::Ast;
 use generator::generate_instructions;
+use generator::generate_c_header;
 use generator::print_instructions;
-// use compiler::compile_instructions;
+use compiler::compile_instructions;
+use import::import_c_header;
+use mangle::demangle_text;
 
 fn main() {
     let args: Vec<String> = env::args().collect();
-    if args.len() != 3 {
-        println!("Usage: compiler <input> <output>");
-        return;
-    }
-    assert!(args.len()>=3);
-    let input_name = &args[1];
-    let output_name = &args[2];
+    let options = match parse_options(&args[1..]) {
+        Some(options) => options,
+        None => {
+            println!("Usage: compiler [ --reorder-structs ] [ --overflow-checks ] [ --safety-checks ] [ --libc ] [ --emit=( code | asm | c-header ) ] [ --import-c-header ] [ --demangle ] {{ -l<library> | -L<directory> }} <input> {{ <module> }} <output>");
+            return;
+        },
+    };
+    let input_name = &options.input;
+    let output_name = &options.output;
     println!("Compiling {}", input_name);
 
     // 1. Read source file to a string
+    let content = read_input(input_name);
+
+    // Eg: the output of nm or objdump, for programs compiled with this
+    if options.demangle {
+        write_output(output_name, &demangle_text(&content));
+        println!("End");
+        return;
+    }
+
+    // A C header is converted to declarations, instead of being compiled
+    if options.import_c_header {
+        let declarations = import_c_header(&content);
+        write_output(output_name, &declarations);
+        println!("End");
+        return;
+    }
+
+    // 2 - 4. Compile the input, or declare it for C instead
+    if options.emit == Emit::CHeader {
+        let mut ast = build_module_ast(input_name, &content);
+        let header = generate_c_header(&mut ast, &options);
+        write_output(output_name, &header);
+        println!("End");
+        return;
+    }
+    let mut codes = vec![compile_module(&content, &options)];
+    for module in &options.modules {
+        println!("Compiling {}", module);
+        let mut module_options = Options::clone(&options);
+        module_options.input = String::clone(module);
+        codes.push(compile_module(&read_input(module), &module_options));
+    }
+
+    // 5. Write code to file, or link it into an executable
+    if options.emit == Emit::Code && !output_name.ends_with(".s") {
+        link(&codes, &options);
+        println!("End");
+        return;
+    }
+    if codes.len() > 1 {
+        panic!("Only one input can be written as assembly");
+    }
+    write_output(output_name, &codes[0]);
+
+    println!("End");
+}
+
+fn read_input(input_name: &String) -> String {
     let input_file = fs::File::open(input_name)
         .expect("Failed to open file.");
     let mut reader = BufReader::new(input_file);
     let mut content = String::new();
     reader.read_to_string(&mut content)
         .expect("Failed to read file.");
+    return content;
+}
 
+fn build_module_ast(input_name: &String, content: &String) -> Ast {
     // 2. Read tokens
-    let tokens = read_tokens(&content);
+    let (tokens, locations) = read_tokens(input_name, content);
     // print_tokens(&tokens);
 
     // 3. Build abstract syntax tree
-    let mut ast = build_ast(&tokens)
+    let ast = build_ast(&tokens, &locations)
         .expect("Failed to build ast");
     print_ast(&ast);
+    return ast;
+}
+
+// Compile the file given by options.input to assembly
+fn compile_module(content: &String, options: &Options) -> String {
+    let mut ast = build_module_ast(&options.input, content);
 
     // 4. Resolve ast
-    let instructions = generate_instructions(&mut ast);
+    let instructions = generate_instructions(&mut ast, options);
     print_ast(&ast);
     print_instructions(&instructions);
 
     // 4. Compile ast to string
-    let code = String::new(); //compile_instructions(&instructions);
+    return compile_instructions(&instructions);
+}
 
-    // 5. Write code to file
+fn write_output(output_name: &String, content: &String) {
     let output_file = fs::File::create(output_name)
         .expect("Failed to create output file.");
     let mut writer = BufWriter::new(output_file);
-    writer.write_all(code.as_bytes())
+    writer.write_all(content.as_bytes())
         .expect("Unable to write data");
+}
 
-    println!("End");
+// Assemble and link with the system's C compiler driver. Without libc,
+// the program has its own entry point, so the C runtime isn't linked.
+// The input is given on stdin, and each module in its own file, so their
+// local labels don't clash.
+fn link(codes: &[String], options: &Options) {
+    let mut command = Command::new("cc");
+    command.args(["-x", "assembler", "-", "-o", &options.output]);
+    let mut module_files: Vec<PathBuf> = Vec::new();
+    for (i, code) in codes[1..].iter().enumerate() {
+        let module_file = env::temp_dir().join(format!("compiler-{}-{}.s", process::id(), i));
+        write_output(&module_file.to_string_lossy().into_owned(), code);
+        command.arg(&module_file);
+        module_files.push(module_file);
+    }
+    if !options.libc {
+        command.arg("-nostdlib");
+    }
+    command.args(&options.link_flags);
+    let mut child = command.stdin(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("Failed to run cc to link.");
+    child.stdin.take()
+        .expect("Failed to open stdin of cc")
+        .write_all(codes[0].as_bytes())
+        .expect("Failed to write code to cc");
+    let output = child.wait_with_output()
+        .expect("Failed to wait for cc");
+    for module_file in module_files {
+        let _ = fs::remove_file(module_file);
+    }
+    // Errors name functions by their label
+    eprint!("{}", demangle_text(&String::from_utf8_lossy(&output.stderr)));
+    if !output.status.success() {
+        panic!("Linking failed");
+    }
 }
@@ -0,0 +1,193 @@
+// Symbol names for functions and global variables, which only depend on
+// where they are declared, so stay the same when unrelated code changes and
+// can be linked across files.
+//
+// _M , { length , component } , [ "_" , discriminator ]
+//
+// Components are the module (the source file's path), then each enclosing
+// function or struct, then the symbol's name, each prefixed by its length.
+// The discriminator counts earlier symbols with the same path, eg: helper
+// functions in two blocks of the same function, and is left out for the
+// first one.
+//   add in math.z                  -> _M4math3add        -> math::add
+//   global count in math.z         -> _M4math5count      -> math::count
+//   drop of Node in list.z         -> _M4list4Node4drop  -> list::Node::drop
+//   second inner in outer in a.z   -> _M1a5outer5inner_1 -> a::outer::inner#1
+//   add in lib/math.z              -> _M3lib4math3add    -> lib::math::add
+
+const PREFIX: &str = "_M";
+
+// Module from the source file's path, with a component for each directory
+// and the file's name without its extension, so files with the same name
+// in different directories are different modules. Empty and "." components
+// are left out.
+//   math.z -> math    lib/math.z -> lib::math    ./a/../b.z -> a::_2e_2e::b
+pub fn get_module_path(path: &String) -> Vec<String> {
+    let mut names: Vec<&str> = Vec::new();
+    for name in path.split('/') {
+        if !name.is_empty() && name != "." {
+            names.push(name);
+        }
+    }
+    if let Some(file_name) = names.pop() {
+        let stem = match file_name.rfind('.') {
+            Some(dot) if dot > 0 => &file_name[..dot],
+            _ => file_name,
+        };
+        names.push(stem);
+    }
+    let mut module: Vec<String> = Vec::new();
+    for name in names {
+        module.push(get_module_name(name));
+    }
+    return module;
+}
+
+// A directory or file name, as an identifier. Characters that can't be in a
+// symbol are escaped as "_" and their bytes in hex, and "_" itself as "__",
+// so different names give different modules. A leading digit would be read
+// as part of the length, so is escaped too.
+//   my-file -> my_2dfile    my_file -> my__file    2d -> _32d
+fn get_module_name(name: &str) -> String {
+    let mut module = String::new();
+    for c in name.chars() {
+        if c == '_' {
+            module.push_str("__");
+        } else if c.is_ascii_alphabetic() || (c.is_ascii_digit() && !module.is_empty()) {
+            module.push(c);
+        } else {
+            let mut bytes = [0; 4];
+            for byte in c.encode_utf8(&mut bytes).bytes() {
+                module.push_str(&format!("_{:02x}", byte));
+            }
+        }
+    }
+    return module;
+}
+
+pub fn mangle(path: &[String], discriminator: usize) -> String {
+    let mut symbol = String::from(PREFIX);
+    for component in path {
+        symbol.push_str(&format!("{}{}", component.len(), component));
+    }
+    if discriminator > 0 {
+        symbol.push_str(&format!("_{}", discriminator));
+    }
+    return symbol;
+}
+
+// Readable name of a mangled symbol, None if it isn't one
+pub fn demangle(symbol: &str) -> Option<String> {
+    let mut rest = symbol.strip_prefix(PREFIX)?;
+    let mut components: Vec<&str> = Vec::new();
+    loop {
+        let digits = rest.chars().take_while(|c| c.is_ascii_digit()).count();
+        if digits == 0 {
+            break;
+        }
+        let length: usize = rest[..digits].parse().ok()?;
+        rest = &rest[digits..];
+        if length == 0 || length > rest.len() || !rest.is_char_boundary(length) {
+            return None;
+        }
+        components.push(&rest[..length]);
+        rest = &rest[length..];
+    }
+    if components.is_empty() {
+        return None;
+    }
+    let mut name = components.join("::");
+    if !rest.is_empty() {
+        let discriminator = rest.strip_prefix('_')?;
+        if discriminator.is_empty() || !discriminator.chars().all(|c| c.is_ascii_digit()) {
+            return None;
+        }
+        name.push('#');
+        name.push_str(discriminator);
+    }
+    return Some(name);
+}
+
+// Replaces every mangled symbol in some text, eg: linker errors
+pub fn demangle_text(text: &str) -> String {
+    let mut result = String::new();
+    let mut word = String::new();
+    for c in text.chars() {
+        if c.is_ascii_alphanumeric() || c == '_' {
+            word.push(c);
+            continue;
+        }
+        push_demangled(&mut result, &word);
+        word.clear();
+        result.push(c);
+    }
+    push_demangled(&mut result, &word);
+    return result;
+}
+
+fn push_demangled(result: &mut String, word: &String) {
+    match demangle(word) {
+        Some(name) => result.push_str(&name),
+        None => result.push_str(word),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn module(path: &str) -> Vec<String> {
+        return get_module_path(&String::from(path));
+    }
+
+    fn components(names: &[&str]) -> Vec<String> {
+        return names.iter().map(|name| String::from(*name)).collect();
+    }
+
+    #[test]
+    fn module_path() {
+        assert_eq!(module("math.z"), components(&["math"]));
+        assert_eq!(module("./lib/math.z"), components(&["lib", "math"]));
+        assert_eq!(module("/src//a/util.z"), components(&["src", "a", "util"]));
+        assert_eq!(module("a/../b.z"), components(&["a", "_2e_2e", "b"]));
+        assert_eq!(module(".hidden"), components(&["_2ehidden"]));
+        assert_ne!(module("a/util.z"), module("b/util.z"));
+    }
+
+    #[test]
+    fn module_name_escapes() {
+        assert_eq!(module("my-file.z"), components(&["my_2dfile"]));
+        assert_eq!(module("my_file.z"), components(&["my__file"]));
+        assert_eq!(module("2d.z"), components(&["_32d"]));
+        assert_eq!(module("d2.z"), components(&["d2"]));
+        assert_eq!(module("é.z"), components(&["_c3_a9"]));
+        // Escaping is reversible, so different names stay different
+        assert_ne!(module("a_2d.z"), module("a-.z"));
+    }
+
+    #[test]
+    fn round_trip() {
+        let path = components(&["lib", "math", "Vector", "add"]);
+        assert_eq!(mangle(&path, 0), "_M3lib4math6Vector3add");
+        assert_eq!(demangle(&mangle(&path, 0)), Some(String::from("lib::math::Vector::add")));
+        let path = components(&["a", "outer", "inner"]);
+        assert_eq!(mangle(&path, 1), "_M1a5outer5inner_1");
+        assert_eq!(demangle(&mangle(&path, 12)), Some(String::from("a::outer::inner#12")));
+        let path = components(&["my__file", "x0123456789"]);
+        assert_eq!(demangle(&mangle(&path, 0)), Some(String::from("my__file::x0123456789")));
+    }
+
+    #[test]
+    fn rejects_malformed() {
+        for symbol in ["_M", "_Mx", "_M0", "_M5abc", "_M3abc_", "_M3abc_x", "_M3abc_1x", "_M3abcdef", "M3abc", "main", "_M99999999999999999999a"] {
+            assert_eq!(demangle(symbol), None, "{} was demangled", symbol);
+        }
+    }
+
+    #[test]
+    fn text() {
+        assert_eq!(demangle_text("undefined reference to `_M4math3add'"), "undefined reference to `math::add'");
+        assert_eq!(demangle_text("_M1a1f_2+_Mx main"), "a::f#2+_Mx main");
+        assert_eq!(demangle_text(""), "");
+    }
+}
@@ -0,0 +1,104 @@
+
+// Options given on the command line.
+// Usage: compiler [ flags ] <input> { <module> } <output>
+// An output ending in .s is written as assembly, anything else is linked
+// into an executable, unless something else is emitted.
+// Each module is compiled on its own, and linked with the input.
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Emit {
+    Code,
+    // Assembly or executable, depending on the output name
+    Asm,
+    // Assembly, whatever the output name
+    CHeader,
+    // C header declaring the structs and pub extern functions
+}
+
+#[derive(Clone)]
+pub struct Options {
+    // File being compiled, the input or one of the modules
+    pub input: String,
+    pub modules: Vec<String>,
+    pub output: String,
+    // Reorder struct members to minimise padding, unless the struct
+    // has the c_layout attribute.
+    pub reorder_structs: bool,
+    // Trap on integer overflow, instead of wrapping, for debug builds
+    pub overflow_checks: bool,
+    // Check slice indices are in bounds and pointers aren't null before
+    // they're used
+    pub safety_checks: bool,
+    // Link against libc, whose crt calls main, instead of adding a _start
+    // entry point that uses system calls
+    pub libc: bool,
+    // -l<library> and -L<directory> flags, passed on to the linker
+    pub link_flags: Vec<String>,
+    // What to write to the output, from --emit=<kind>
+    pub emit: Emit,
+    // Read the input as a C header, and write extern declarations for it
+    pub import_c_header: bool,
+    // Copy the input to the output, with mangled symbols made readable
+    pub demangle: bool,
+}
+
+pub fn parse_options(args: &[String]) -> Option<Options> {
+    let mut reorder_structs = false;
+    let mut overflow_checks = false;
+    let mut safety_checks = false;
+    let mut libc = false;
+    let mut link_flags: Vec<String> = Vec::new();
+    let mut emit = Emit::Code;
+    let mut import_c_header = false;
+    let mut demangle = false;
+    let mut files: Vec<String> = Vec::new();
+    for arg in args {
+        match arg.as_str() {
+            "--reorder-structs" => reorder_structs = true,
+            "--overflow-checks" => overflow_checks = true,
+            "--safety-checks" => safety_checks = true,
+            "--libc" => libc = true,
+            "--emit=code" => emit = Emit::Code,
+            "--emit=asm" => emit = Emit::Asm,
+            "--emit=c-header" => emit = Emit::CHeader,
+            "--import-c-header" => import_c_header = true,
+            "--demangle" => demangle = true,
+            _ => {
+                if arg.starts_with("-l") || arg.starts_with("-L") {
+                    if arg.len() == 2 {
+                        println!("Expected a name after {}", arg);
+                        return None;
+                    }
+                    link_flags.push(String::clone(arg));
+                    continue;
+                }
+                if arg.starts_with("--") {
+                    println!("Unknown option {}", arg);
+                    return None;
+                }
+                files.push(String::clone(arg));
+            },
+        }
+    }
+    if files.len() < 2 {
+        return None;
+    }
+    let modules = Vec::from(&files[1..files.len() - 1]);
+    if !modules.is_empty() && (emit != Emit::Code || import_c_header || demangle) {
+        println!("Only an executable can be made from more than one input");
+        return None;
+    }
+    Some(Options {
+        input: String::clone(&files[0]),
+        modules: modules,
+        output: String::clone(&files[files.len() - 1]),
+        reorder_structs: reorder_structs,
+        overflow_checks: overflow_checks,
+        safety_checks: safety_checks,
+        libc: libc,
+        link_flags: link_flags,
+        emit: emit,
+        import_c_header: import_c_header,
+        demangle: demangle,
+    })
+}
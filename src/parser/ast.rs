@@ -1,10 +1,12 @@
 
 use super::construct::Construct;
+use crate::lexer::token::Location;
 
 pub struct Node {
     pub construct: Construct,
     pub next: Option<usize>,
     pub child: Option<usize>,
+    pub location: Location, // Where it starts in the source file
 }
 
 pub struct Ast {
@@ -17,25 +19,33 @@ impl Ast {
         }
     }
 
-    pub fn set_node(&mut self, node_i: usize, construct: &Construct, children: &[usize]) {
+    pub fn set_node(&mut self, node_i: usize, construct: &Construct, children: &[usize], location: Location) {
         if node_i == self.nodes.len() {
             self.nodes.push( Node {
                 construct: Construct::clone(construct),
                 next: Option::None,
                 child: Option::None,
+                location: location,
             });
         } else if node_i < self.nodes.len() {
             self.nodes[node_i].construct = Construct::clone(construct);
             self.nodes[node_i].child = Option::None;
             self.nodes[node_i].next = Option::None;
+            self.nodes[node_i].location = location;
         } else {
             panic!("Unexpected node_i in Ast::set_node");
         }
+        self.set_children(node_i, children);
+    }
+
+    pub fn set_children(&mut self, node_i: usize, children: &[usize]) {
+        self.nodes[node_i].child = None;
         if children.len() > 0 {
             self.nodes[node_i].child = Some(children[0]);
             for i in 0..(children.len()-1) {
                 self.nodes[children[i]].next = Some(children[i+1]);
             }
+            self.nodes[children[children.len()-1]].next = None;
         }
     }
 }
@@ -0,0 +1,24 @@
+
+use std::fmt;
+
+#[derive(Clone)]
+pub enum Attribute {
+    Packed,
+    // No padding between members, alignment of 1
+
+    Align(usize), // alignment
+    // Minimum alignment of the struct
+
+    CLayout,
+    // Members are kept in declaration order, following C layout rules
+}
+
+impl fmt::Display for Attribute {
+    fn fmt (&self, fmt: &mut std::fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Attribute::Packed => write!(fmt, "Attribute(packed)"),
+            Attribute::Align(alignment) => write!(fmt, "Attribute(align({}))", alignment),
+            Attribute::CLayout => write!(fmt, "Attribute(c_layout)"),
+        }
+    }
+}
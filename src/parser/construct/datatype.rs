@@ -14,6 +14,7 @@ pub enum Primitive {
     F32,
     F64,
     C8,
+    Bool,
 }
 
 impl fmt::Display for Primitive {
@@ -30,6 +31,7 @@ impl fmt::Display for Primitive {
             Primitive::F32 => write!(fmt, "Primitive(f32)"),
             Primitive::F64 => write!(fmt, "Primitive(f64)"),
             Primitive::C8 => write!(fmt, "Primitive(c8)"),
+            Primitive::Bool => write!(fmt, "Primitive(bool)"),
         }
     }
 }
@@ -41,6 +43,14 @@ pub enum Datatype {
 
     Pointer,
     // { qualifier } , datatype
+
+    Function,
+    // { qualifier } , { datatype } , [ variadic ] , { returned }
+    // Pointer to a function with the given argument and return datatypes
+
+    Slice,
+    // { qualifier } , datatype
+    // Pointer to the first element and the number of elements
 }
 
 impl fmt::Display for Datatype {
@@ -48,6 +58,8 @@ impl fmt::Display for Datatype {
         match self {
             Datatype::Terminal => write!(fmt, "Datatype(Terminal)"),
             Datatype::Pointer => write!(fmt, "Datatype(Pointer)"),
+            Datatype::Function => write!(fmt, "Datatype(Function)"),
+            Datatype::Slice => write!(fmt, "Datatype(Slice)"),
         }
     }
 }
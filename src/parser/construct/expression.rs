@@ -15,10 +15,43 @@ pub enum Expression {
     // left operand , right operand
 
     Constant(Constant),
-    // Terminal
+    // [ primitive ]
+    // Numbers can have a suffix giving their primitive
 
     Identifier,
     // identifier
+
+    Sizeof,
+    // datatype
+
+    Alignof,
+    // datatype
+
+    Offsetof,
+    // datatype , identifier
+
+    New,
+    // datatype , expression(allocator)
+    // Allocates a value of the datatype, giving a mutable pointer to it
+
+    Cast,
+    // datatype , expression(value)
+    // Converts an integer to another integer datatype, or a pointer to
+    // another pointer datatype or its address
+
+    Syscall,
+    // expression(number) , { expression(argument) }
+    // Makes a Linux system call, giving the value it returns as an i64
+
+    Null,
+    // Terminal
+    // Pointer to nothing, with the pointer datatype it is compared with or
+    // assigned to
+
+    Undefined,
+    // Terminal
+    // Only valid as the initial value of a variable, which is left
+    // uninitialised but can be read from
 }
 
 impl fmt::Display for Expression {
@@ -29,6 +62,14 @@ impl fmt::Display for Expression {
             Expression::BinaryOp(op) => write!(fmt, "Expression({})", op),
             Expression::Constant(constant) => write!(fmt, "Expression({})", constant),
             Expression::Identifier => write!(fmt, "Expression(Identifier)"),
+            Expression::Sizeof => write!(fmt, "Expression(Sizeof)"),
+            Expression::Alignof => write!(fmt, "Expression(Alignof)"),
+            Expression::Offsetof => write!(fmt, "Expression(Offsetof)"),
+            Expression::New => write!(fmt, "Expression(New)"),
+            Expression::Cast => write!(fmt, "Expression(Cast)"),
+            Expression::Syscall => write!(fmt, "Expression(Syscall)"),
+            Expression::Null => write!(fmt, "Expression(Null)"),
+            Expression::Undefined => write!(fmt, "Expression(Undefined)"),
         }
     }
 }
@@ -0,0 +1,27 @@
+
+use std::fmt;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Linkage {
+    Internal,
+    // Only called from within the program, by a label unique to the function
+
+    Extern,
+    // Declared without a body, and defined by code the program is linked
+    // with, eg: C libraries. Called by its own name, with the C calling
+    // convention.
+
+    Export,
+    // Defined by the program, and can be called from code it is linked
+    // with by its own name, with the C calling convention.
+}
+
+impl fmt::Display for Linkage {
+    fn fmt (&self, fmt: &mut std::fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Linkage::Internal => write!(fmt, "Linkage(internal)"),
+            Linkage::Extern => write!(fmt, "Linkage(extern)"),
+            Linkage::Export => write!(fmt, "Linkage(pub extern)"),
+        }
+    }
+}
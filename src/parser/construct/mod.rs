@@ -9,24 +9,44 @@ mod statement;
 pub use statement::*;
 mod expression;
 pub use expression::*;
+mod attribute;
+pub use attribute::*;
+mod linkage;
+pub use linkage::*;
 
 
 #[derive(Clone)]
 pub enum Construct {
     Program,
-    // { function | struct }
+    // { function | struct | alias }
 
     // === General symbols ===
     // Anything an identifier can refer to, in general.
 
-    Function(String), // name
-    // { argument } , { returned } , block
+    Function(String, Linkage), // name, linkage
+    // { argument } , [ variadic ] , { returned } , block
+    // Extern functions have no block.
 
-    Structure(String, usize), // name, size
-    // { member }
+    Structure(String, usize, usize), // name, size, alignment
+    // { attribute } , { member | function }
+    // The only function a struct can declare is drop, which is called on
+    // variables of the struct when they go out of scope.
 
     Variable(String), // name
+    // datatype , [ expression ]
+    // Expression is the initial value. At the program scope, this must be
+    // a compile-time constant. Without an initial value, the variable must
+    // be assigned before it is read, unless the value is undefined.
+
+    Const(String), // name
+    // datatype , expression
+    // Expression must be a compile-time constant. Once evaluated, the
+    // expression is replaced with the resulting constant.
+
+    Alias(String, bool), // name, distinct
     // datatype
+    // A distinct alias is a new nominal type, otherwise it is interchangeable
+    // with the underlying datatype.
 
     // === Restricted symbols ===
     // Symbols that are created in specific situations.
@@ -39,6 +59,12 @@ pub enum Construct {
     // datatype
     // Variable created in function return list.
 
+    Variadic,
+    // Terminal
+    // Marks a function taking any number of arguments after the others,
+    // passed with the C calling convention. Only extern functions and
+    // function datatypes can be variadic.
+
     Member(String, usize), // name, offset
     // bytes , datatype
     // Variable created with a declare statement, within a struct
@@ -59,6 +85,9 @@ pub enum Construct {
     Qualifier(Qualifier),
     // Terminal
 
+    Attribute(Attribute),
+    // Terminal
+
     Expression(Expression),
     // ( Expression::Function | ... )
 
@@ -76,18 +105,22 @@ impl fmt::Display for Construct {
     fn fmt (&self, fmt: &mut std::fmt::Formatter<'_>) -> fmt::Result {
         match self {
             Construct::Program => write!(fmt, "Program"),
-            Construct::Function(name) => write!(fmt, "Function({})", name),
-            Construct::Structure(name, size) => write!(fmt, "Structure({}, size={})", name, size),
+            Construct::Function(name, linkage) => write!(fmt, "Function({}, {})", name, linkage),
+            Construct::Structure(name, size, alignment) => write!(fmt, "Structure({}, size={}, alignment={})", name, size, alignment),
             Construct::Variable(name) => write!(fmt, "Variable({})", name),
+            Construct::Const(name) => write!(fmt, "Const({})", name),
+            Construct::Alias(name, distinct) => write!(fmt, "Alias({}, distinct={})", name, distinct),
 
             Construct::Argument(name) => write!(fmt, "Argument({})", name),
             Construct::Returned => write!(fmt, "Returned"),
+            Construct::Variadic => write!(fmt, "Variadic"),
             Construct::Member(name, offset) => write!(fmt, "Member({}, offset={})", name, offset),
 
             Construct::Block => write!(fmt, "Block"),
             Construct::Statement(statement) => write!(fmt, "{}", statement),
             Construct::Datatype(datatype) => write!(fmt, "{}", datatype),
             Construct::Qualifier(qualifier) => write!(fmt, "{}", qualifier),
+            Construct::Attribute(attribute) => write!(fmt, "{}", attribute),
             Construct::Expression(expression) => write!(fmt, "{}", expression),
             Construct::Primitive(primitive) => write!(fmt, "{}", primitive),
             Construct::Identifier(identifier) => write!(fmt, "Identifier({})", identifier),
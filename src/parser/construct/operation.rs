@@ -26,15 +26,24 @@ pub enum BinaryOp {
     Subtract,
     Multiply,
     Divide,
+    Modulo,
+    ShiftLeft,
+    ShiftRight,
 
     LogicalAnd,
     LogicalOr,
     LogicalEquals,
+    NotEquals,
+    LessThan,
+    GreaterThan,
+    LessEquals,
+    GreaterEquals,
 
     BitwiseAnd,
     BitwiseOr,
 
     Access, // my_struct.member
+    Index, // my_slice[index]
 }
 
 impl fmt::Display for BinaryOp {
@@ -44,15 +53,24 @@ impl fmt::Display for BinaryOp {
             BinaryOp::Subtract => write!(fmt, "BinaryOp(Subtract)"),
             BinaryOp::Multiply => write!(fmt, "BinaryOp(Multiply)"),
             BinaryOp::Divide => write!(fmt, "BinaryOp(Divide)"),
+            BinaryOp::Modulo => write!(fmt, "BinaryOp(Modulo)"),
+            BinaryOp::ShiftLeft => write!(fmt, "BinaryOp(ShiftLeft)"),
+            BinaryOp::ShiftRight => write!(fmt, "BinaryOp(ShiftRight)"),
 
             BinaryOp::LogicalAnd => write!(fmt, "BinaryOp(LogicalAnd)"),
             BinaryOp::LogicalOr => write!(fmt, "BinaryOp(LogicalOr)"),
             BinaryOp::LogicalEquals => write!(fmt, "BinaryOp(LogicalEquals)"),
+            BinaryOp::NotEquals => write!(fmt, "BinaryOp(NotEquals)"),
+            BinaryOp::LessThan => write!(fmt, "BinaryOp(LessThan)"),
+            BinaryOp::GreaterThan => write!(fmt, "BinaryOp(GreaterThan)"),
+            BinaryOp::LessEquals => write!(fmt, "BinaryOp(LessEquals)"),
+            BinaryOp::GreaterEquals => write!(fmt, "BinaryOp(GreaterEquals)"),
 
             BinaryOp::BitwiseAnd => write!(fmt, "BinaryOp(BitwiseAnd)"),
             BinaryOp::BitwiseOr => write!(fmt, "BinaryOp(BitwiseOr)"),
 
             BinaryOp::Access => write!(fmt, "BinaryOp(Access)"),
+            BinaryOp::Index => write!(fmt, "BinaryOp(Index)"),
         }
     }
 }
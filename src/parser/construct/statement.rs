@@ -25,19 +25,32 @@ pub enum Statement {
     // expression , "=" ,  expression , ";"
 
     Return,
-    // expression
+    // [ expression ]
     
     Conditional,
-    // expression , block , [ block ]
+    // expression , block , [ block | statement(conditional) ]
+    // Expression must be a bool. The last child is the else branch.
     
     Loop,
     // block
 
+    While,
+    // expression , block
+    // Expression must be a bool, checked before each iteration
+
     Control(Control),
     // Terminal
 
     Block,
     // block
+
+    Defer,
+    // statement
+    // Statement is generated at every exit from the enclosing block
+
+    Expression,
+    // expression
+    // Only function calls can be used as statements
 }
 
 impl fmt::Display for Statement {
@@ -47,8 +60,11 @@ impl fmt::Display for Statement {
             Statement::Return => write!(fmt, "Statement(Return)"),
             Statement::Conditional => write!(fmt, "Statement(Conditional)"),
             Statement::Loop => write!(fmt, "Statement(Loop)"),
+            Statement::While => write!(fmt, "Statement(While)"),
             Statement::Control(control) => write!(fmt, "Statement({})", control),
             Statement::Block => write!(fmt, "Statement(Block)"),
+            Statement::Defer => write!(fmt, "Statement(Defer)"),
+            Statement::Expression => write!(fmt, "Statement(Expression)"),
         }
     }
 }
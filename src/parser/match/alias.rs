@@ -0,0 +1,70 @@
+
+use crate::lexer::token::*;
+use super::construct::*;
+use super::parser::Parser;
+
+use super::datatype::match_datatype;
+
+
+pub fn match_alias(parser: &mut Parser) -> bool {
+    parser.start_node();
+
+    // identifier , ":" , [ "distinct" ] , "type" , "=" , datatype , ";"
+
+    let name = match parser.consume_token() {
+        Token::Identifier(identifier) => identifier,
+        _ => {
+            parser.discard_node();
+            return false;
+        },
+    };
+
+    match parser.consume_token() {
+        Token::Colon => (),
+        _ => {
+            parser.discard_node();
+            return false;
+        },
+    }
+
+    let distinct = match parser.peek_token() {
+        Token::Keyword(Keyword::Distinct) => {
+            parser.consume_token();
+            true
+        },
+        _ => false,
+    };
+
+    match parser.consume_token() {
+        Token::Keyword(keyword) => match keyword {
+            Keyword::Type => (),
+            _ => {
+                parser.discard_node();
+                return false;
+            },
+        },
+        _ => {
+            parser.discard_node();
+            return false;
+        },
+    }
+
+    match parser.consume_token() {
+        Token::Equals => (),
+        _ => panic!("Expected '=' after type in alias declaration"),
+    }
+
+    if !match_datatype(parser) {
+        panic!("Expected datatype in alias declaration");
+    }
+
+    match parser.consume_token() {
+        Token::Semicolon => (),
+        _ => panic!("Expected ';' after alias declaration"),
+    }
+
+    let construct = Construct::Alias(String::clone(name), distinct);
+    parser.confirm_node(&construct);
+
+    return true;
+}
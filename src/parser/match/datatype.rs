@@ -3,6 +3,8 @@ use super::token::*;
 use super::construct::*;
 use super::parser::Parser;
 use super::common::match_identifier;
+use super::function::match_returned;
+use super::function::match_variadic;
 
 
 fn match_qualifier(parser: &mut Parser) -> bool {
@@ -29,7 +31,7 @@ fn match_qualifier(parser: &mut Parser) -> bool {
     return true;
 }
 
-fn match_datatype_primitive(parser: &mut Parser) -> bool {
+pub fn match_datatype_primitive(parser: &mut Parser) -> bool {
     parser.start_node();
 
     let primitive = match parser.consume_token() {
@@ -45,6 +47,7 @@ fn match_datatype_primitive(parser: &mut Parser) -> bool {
             Keyword::F32 => Primitive::F32,
             Keyword::F64 => Primitive::F64,
             Keyword::C8 => Primitive::C8,
+            Keyword::Bool => Primitive::Bool,
             _ => {
                 parser.discard_node();
                 return false;
@@ -62,6 +65,70 @@ fn match_datatype_primitive(parser: &mut Parser) -> bool {
     return true;
 }
 
+fn match_datatype_function(parser: &mut Parser) {
+    // Already consumed "function"
+    // "(" , [ datatype , { "," , datatype } , [ "," , variadic ] ] , ")" , [ "->" , ( returned | ( "(" , returned , { "," , returned } , ")" ) ) ]
+
+    match parser.consume_token() {
+        Token::LParen => (),
+        _ => panic!("Expected '(' after function in datatype"),
+    }
+    if match_datatype(parser) {
+        loop {
+            match parser.peek_token() {
+                Token::Comma => parser.consume_token(),
+                _ => break,
+            };
+            if match_variadic(parser) {
+                break;
+            }
+            if !match_datatype(parser) {
+                panic!("Expected argument datatype after comma");
+            }
+        }
+    } else if match_variadic(parser) {
+        panic!("Variadic function datatype must have an argument before ...");
+    }
+    match parser.consume_token() {
+        Token::RParen => (),
+        _ => panic!("Expected ')' after function argument datatypes"),
+    }
+
+    match parser.peek_token() {
+        Token::Minus => {
+            parser.consume_token();
+            match parser.consume_token() {
+                Token::GreaterThan => (),
+                _ => panic!("Expected '>' after '-' to form ->"),
+            }
+            if match_returned(parser) {
+                return;
+            }
+            match parser.consume_token() {
+                Token::LParen => (),
+                _ => panic!("Expected return type or '(' after function(...)->"),
+            }
+            if !match_returned(parser) {
+                panic!("Expected return type in ->()");
+            }
+            loop {
+                match parser.peek_token() {
+                    Token::Comma => parser.consume_token(),
+                    _ => break,
+                };
+                if !match_returned(parser) {
+                    panic!("Expected return type after ,");
+                }
+            }
+            match parser.consume_token() {
+                Token::RParen => (),
+                _ => panic!("Expected ) after return type list"),
+            }
+        },
+        _ => (),
+    }
+}
+
 fn match_datatype_terminal(parser: &mut Parser) -> bool {
     if match_datatype_primitive(parser) {
         return true;
@@ -91,6 +158,22 @@ pub fn match_datatype(parser: &mut Parser) -> bool {
             }
             Datatype::Pointer
         },
+        Token::LSBracket => {
+            parser.consume_token();
+            match parser.consume_token() {
+                Token::RSBracket => (),
+                _ => panic!("Expected ']' after '[' in slice datatype"),
+            }
+            if !match_datatype(parser) {
+                panic!("Expected element datatype after []");
+            }
+            Datatype::Slice
+        },
+        Token::Keyword(Keyword::Function) => {
+            parser.consume_token();
+            match_datatype_function(parser);
+            Datatype::Function
+        },
         _ => {
             if !match_datatype_terminal(parser) {
                 parser.discard_node();
@@ -3,6 +3,8 @@ use super::token::*;
 use super::construct::*;
 use super::parser::Parser;
 use super::common::match_identifier;
+use super::datatype::match_datatype;
+use super::datatype::match_datatype_primitive;
 
 
 fn match_expression_function(parser: &mut Parser) -> bool {
@@ -26,8 +28,8 @@ fn match_expression_function(parser: &mut Parser) -> bool {
             break;
         };
         loop {
-            match parser.consume_token() {
-                Token::Comma => (),
+            match parser.peek_token() {
+                Token::Comma => parser.consume_token(),
                 _ => break,
             };
             if !match_expression(parser) {
@@ -48,6 +50,48 @@ fn match_expression_function(parser: &mut Parser) -> bool {
     return true;
 }
 
+fn match_expression_syscall(parser: &mut Parser) -> bool {
+    parser.start_node();
+
+    // "syscall" , "(" , expression , { "," , expression } , ")"
+
+    match parser.consume_token() {
+        Token::Keyword(Keyword::Syscall) => (),
+        _ => {
+            parser.discard_node();
+            return false;
+        }
+    };
+
+    match parser.consume_token() {
+        Token::LParen => (),
+        _ => panic!("Expected '(' after syscall"),
+    };
+
+    if !match_expression(parser) {
+        panic!("Expected system call number in syscall");
+    }
+    loop {
+        match parser.peek_token() {
+            Token::Comma => parser.consume_token(),
+            _ => break,
+        };
+        if !match_expression(parser) {
+            panic!("Expected expression after comma in syscall");
+        };
+    }
+
+    match parser.consume_token() {
+        Token::RParen => (),
+        _ => panic!("Expected ')' after syscall arguments"),
+    };
+
+    let construct = Construct::Expression(Expression::Syscall);
+    parser.confirm_node(&construct);
+
+    return true;
+}
+
 pub fn match_expression_parentheses(parser: &mut Parser) -> bool {
     match parser.peek_token() {
         Token::LParen => {
@@ -69,14 +113,38 @@ pub fn match_expression_constant(parser: &mut Parser) -> bool {
     parser.start_node();
 
     let constant = match parser.consume_token() {
-        Token::Constant(constant) => constant,
+        Token::Constant(constant) => Constant::clone(constant),
         _ => {
             parser.discard_node();
             return false;
         },
     };
 
-    let construct = Construct::Expression(Expression::Constant(Constant::clone(constant)));
+    // Numbers can be followed by a suffix giving their primitive
+    match constant {
+        Constant::Int(_) | Constant::Float(_) => {
+            match_datatype_primitive(parser);
+        },
+        _ => (),
+    }
+
+    let construct = Construct::Expression(Expression::Constant(constant));
+    parser.confirm_node(&construct);
+    return true;
+}
+
+fn match_expression_null(parser: &mut Parser) -> bool {
+    parser.start_node();
+
+    match parser.consume_token() {
+        Token::Keyword(Keyword::Null) => (),
+        _ => {
+            parser.discard_node();
+            return false;
+        },
+    }
+
+    let construct = Construct::Expression(Expression::Null);
     parser.confirm_node(&construct);
     return true;
 }
@@ -94,11 +162,92 @@ pub fn match_expression_identifier(parser: &mut Parser) -> bool {
     return true;
 }
 
+fn match_expression_builtin(parser: &mut Parser) -> bool {
+    parser.start_node();
+
+    // ( "sizeof" | "alignof" ) , "(" , datatype , ")"
+    // "offsetof" , "(" , datatype , "," , identifier , ")"
+    // "new" , "(" , datatype , "," , expression , ")"
+    // "cast" , "(" , datatype , "," , expression , ")"
+
+    let expression = match parser.consume_token() {
+        Token::Keyword(keyword) => match keyword {
+            Keyword::Sizeof => Expression::Sizeof,
+            Keyword::Alignof => Expression::Alignof,
+            Keyword::Offsetof => Expression::Offsetof,
+            Keyword::New => Expression::New,
+            Keyword::Cast => Expression::Cast,
+            _ => {
+                parser.discard_node();
+                return false;
+            },
+        },
+        _ => {
+            parser.discard_node();
+            return false;
+        },
+    };
+
+    match parser.consume_token() {
+        Token::LParen => (),
+        _ => panic!("Expected '(' after {}", expression),
+    }
+
+    if !match_datatype(parser) {
+        panic!("Expected datatype in {}", expression);
+    }
+
+    if let Expression::Offsetof = expression {
+        match parser.consume_token() {
+            Token::Comma => (),
+            _ => panic!("Expected ',' after datatype in offsetof"),
+        }
+        if !match_identifier(parser) {
+            panic!("Expected member name in offsetof");
+        }
+    }
+
+    if let Expression::New = expression {
+        match parser.consume_token() {
+            Token::Comma => (),
+            _ => panic!("Expected ',' after datatype in new"),
+        }
+        if !match_expression(parser) {
+            panic!("Expected allocator in new");
+        }
+    }
+
+    if let Expression::Cast = expression {
+        match parser.consume_token() {
+            Token::Comma => (),
+            _ => panic!("Expected ',' after datatype in cast"),
+        }
+        if !match_expression(parser) {
+            panic!("Expected value in cast");
+        }
+    }
+
+    match parser.consume_token() {
+        Token::RParen => (),
+        _ => panic!("Expected ')' after {}", expression),
+    }
+
+    let construct = Construct::Expression(expression);
+    parser.confirm_node(&construct);
+    return true;
+}
+
 // Match an expression that can be evaluated without needing to look at further tokens
 fn match_expression_enclosed(parser: &mut Parser) -> bool {
     if match_expression_unary_op(parser) {
         return true;
     }
+    if match_expression_builtin(parser) {
+        return true;
+    }
+    if match_expression_syscall(parser) {
+        return true;
+    }
     if match_expression_function(parser) {
         return true;
     }
@@ -108,6 +257,9 @@ fn match_expression_enclosed(parser: &mut Parser) -> bool {
     if match_expression_constant(parser) {
         return true;
     }
+    if match_expression_null(parser) {
+        return true;
+    }
     if match_expression_identifier(parser) {
         return true;
     }
@@ -120,7 +272,7 @@ fn match_binary_op(parser: &mut Parser) -> Option<(BinaryOp, u8)> {
             match parser.peek_token() {
                 Token::Ampersand => {
                     parser.consume_token();
-                    (BinaryOp::LogicalAnd, 42)
+                    (BinaryOp::LogicalAnd, 62)
                 }
                 _ => (BinaryOp::BitwiseAnd, 31)
             }
@@ -129,7 +281,7 @@ fn match_binary_op(parser: &mut Parser) -> Option<(BinaryOp, u8)> {
             match parser.peek_token() {
                 Token::VBar => {
                     parser.consume_token();
-                    (BinaryOp::LogicalOr, 43)
+                    (BinaryOp::LogicalOr, 63)
                 }
                 _ => (BinaryOp::BitwiseOr, 32)
             }
@@ -138,17 +290,53 @@ fn match_binary_op(parser: &mut Parser) -> Option<(BinaryOp, u8)> {
             match parser.peek_token() {
                 Token::Equals => {
                     parser.consume_token();
-                    (BinaryOp::LogicalEquals, 41)
+                    (BinaryOp::LogicalEquals, 61)
                 },
                 _ => return None,
             }
         }
+        Token::Exclamation => {
+            match parser.consume_token() {
+                Token::Equals => (BinaryOp::NotEquals, 61),
+                _ => return None,
+            }
+        }
         Token::Plus => (BinaryOp::Add, 53),
         Token::Minus => (BinaryOp::Subtract, 52),
         Token::Asterisk => (BinaryOp::Multiply, 51),
         Token::RSlash => (BinaryOp::Divide, 51),
+        Token::Percent => (BinaryOp::Modulo, 51),
+        // Comparisons bind less tightly than arithmetic, so both sides can
+        // be calculations
+        Token::LessThan => {
+            match parser.peek_token() {
+                Token::LessThan => {
+                    parser.consume_token();
+                    (BinaryOp::ShiftLeft, 54)
+                },
+                Token::Equals => {
+                    parser.consume_token();
+                    (BinaryOp::LessEquals, 60)
+                },
+                _ => (BinaryOp::LessThan, 60),
+            }
+        }
+        Token::GreaterThan => {
+            match parser.peek_token() {
+                Token::GreaterThan => {
+                    parser.consume_token();
+                    (BinaryOp::ShiftRight, 54)
+                },
+                Token::Equals => {
+                    parser.consume_token();
+                    (BinaryOp::GreaterEquals, 60)
+                },
+                _ => (BinaryOp::GreaterThan, 60),
+            }
+        }
 
         Token::Period => (BinaryOp::Access, 1),
+        Token::LSBracket => (BinaryOp::Index, 1),
         _ => return None,
     };
     Some((op, priority))
@@ -197,10 +385,21 @@ pub fn match_binary_expression(parser: &mut Parser, priority: u8) {
 
     parser.start_node_with_prev(1);
     // Add node for right
-    if !match_expression_enclosed(parser) {
-        panic!("Missing expression after binary operator");
+    if let BinaryOp::Index = op {
+        // The index is enclosed by the brackets
+        if !match_expression(parser) {
+            panic!("Missing index within []");
+        }
+        match parser.consume_token() {
+            Token::RSBracket => (),
+            _ => panic!("No closing ] after index"),
+        }
+    } else {
+        if !match_expression_enclosed(parser) {
+            panic!("Missing expression after binary operator");
+        }
+        match_binary_expression(parser, op_priority);
     }
-    match_binary_expression(parser, op_priority);
 
     let construct = Construct::Expression(Expression::BinaryOp(op));
     parser.confirm_node(&construct);
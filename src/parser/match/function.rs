@@ -38,7 +38,28 @@ fn match_argument(parser: &mut Parser) -> bool {
     return true;
 }
 
-fn match_returned(parser: &mut Parser) -> bool {
+pub fn match_variadic(parser: &mut Parser) -> bool {
+    parser.start_node();
+
+    // "..."
+
+    for _ in 0..3 {
+        match parser.consume_token() {
+            Token::Period => (),
+            _ => {
+                parser.discard_node();
+                return false;
+            },
+        }
+    }
+
+    let construct = Construct::Variadic;
+    parser.confirm_node(&construct);
+
+    return true;
+}
+
+pub fn match_returned(parser: &mut Parser) -> bool {
     parser.start_node();
 
     // datatype
@@ -57,7 +78,7 @@ fn match_returned(parser: &mut Parser) -> bool {
 pub fn match_function(parser: &mut Parser) -> bool {
     parser.start_node();
 
-    // identifier , ":" , "function" , ...
+    // identifier , ":" , [ [ "pub" ] , "extern" ] , "function" , ...
 
     let name = match parser.consume_token() {
         Token::Identifier(identifier) => identifier,
@@ -75,6 +96,22 @@ pub fn match_function(parser: &mut Parser) -> bool {
         },
     }
 
+    let linkage = match parser.peek_token() {
+        Token::Keyword(Keyword::Extern) => {
+            parser.consume_token();
+            Linkage::Extern
+        },
+        Token::Keyword(Keyword::Pub) => {
+            parser.consume_token();
+            match parser.consume_token() {
+                Token::Keyword(Keyword::Extern) => (),
+                _ => panic!("Expected extern after pub in function {}", name),
+            }
+            Linkage::Export
+        },
+        _ => Linkage::Internal,
+    };
+
     match parser.consume_token() {
         Token::Keyword(keyword) => match keyword {
             Keyword::Function => (),
@@ -89,7 +126,7 @@ pub fn match_function(parser: &mut Parser) -> bool {
         },
     }
 
-    // ... , "(" , [ argument , { "," , argument } ] , ")" ,
+    // ... , "(" , [ argument , { "," , argument } , [ "," , variadic ] ] , ")" ,
 
     match parser.consume_token() {
         Token::LParen => (),
@@ -99,16 +136,23 @@ pub fn match_function(parser: &mut Parser) -> bool {
         },
     }
 
+    let mut variadic = false;
     if match_argument(parser) {
         loop {
             match parser.peek_token() {
                 Token::Comma => parser.consume_token(),
                 _ => break,
             };
+            if match_variadic(parser) {
+                variadic = true;
+                break;
+            }
             if !match_argument(parser) {
                 panic!("Expected argument after comma");
             };
         }
+    } else if match_variadic(parser) {
+        panic!("Variadic function {} must have an argument before ...", name);
     }
 
     match parser.consume_token() {
@@ -125,7 +169,7 @@ pub fn match_function(parser: &mut Parser) -> bool {
         Token::Minus => {
             parser.consume_token();
             match parser.consume_token() {
-                Token::LessThan => (),
+                Token::GreaterThan => (),
                 _ => panic!("Expected '>' after '-' to form ->"),
             }
             if !match_returned(parser) {
@@ -155,14 +199,30 @@ pub fn match_function(parser: &mut Parser) -> bool {
         _ => (),
     }
 
-    // block
+    // The arguments after the named ones can only be read with C's va_arg
+    if variadic && linkage != Linkage::Extern {
+        panic!("Only extern functions can be variadic, {} isn't", name);
+    }
+
+    // ( block | ";" )
+    // Extern functions are defined elsewhere, so are only declared, and
+    // exported functions are defined here
 
-    if !match_block(parser) {
+    if let Linkage::Extern = linkage {
+        match parser.consume_token() {
+            Token::Semicolon => (),
+            Token::LCBracket => panic!("Extern function {} can't have a body", name),
+            _ => panic!("Expected ';' after extern function {}", name),
+        }
+    } else if !match_block(parser) {
+        if let Linkage::Export = linkage {
+            panic!("Exported function {} must have a body", name);
+        }
         parser.discard_node();
         return false;
     }
 
-    let construct = Construct::Function(String::clone(name));
+    let construct = Construct::Function(String::clone(name), linkage);
     parser.confirm_node(&construct);
 
     return true;
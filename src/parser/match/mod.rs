@@ -1,6 +1,7 @@
 
 pub mod program;
 mod structure;
+mod alias;
 mod function;
 mod symbol;
 mod block;
@@ -4,6 +4,7 @@ use super::construct::*;
 use super::parser::Parser;
 
 use super::expression::match_expression;
+use super::block::match_block;
 
 
 fn match_statement_assign(parser: &mut Parser) -> bool {
@@ -46,7 +47,7 @@ fn match_statement_assign(parser: &mut Parser) -> bool {
 fn match_statement_return(parser: &mut Parser) -> bool {
     parser.start_node();
 
-    // return <expression> ;
+    // return [ <expression> ] ;
 
     match parser.consume_token() {
         Token::Keyword(keyword) => match keyword {
@@ -62,6 +63,179 @@ fn match_statement_return(parser: &mut Parser) -> bool {
         }
     };
 
+    // Expression is optional, for functions that don't return a value
+    match_expression(parser);
+
+    match parser.consume_token() {
+        Token::Semicolon => (),
+        _ => {
+            parser.discard_node();
+            return false;
+        }
+    };
+
+    let construct = Construct::Statement(Statement::Return);
+    parser.confirm_node(&construct);
+
+    return true;
+}
+
+fn match_statement_block(parser: &mut Parser) -> bool {
+    parser.start_node();
+
+    // block
+
+    if !match_block(parser) {
+        parser.discard_node();
+        return false;
+    }
+
+    let construct = Construct::Statement(Statement::Block);
+    parser.confirm_node(&construct);
+
+    return true;
+}
+
+fn match_statement_loop(parser: &mut Parser) -> bool {
+    parser.start_node();
+
+    // loop <block>
+
+    match parser.consume_token() {
+        Token::Keyword(Keyword::Loop) => (),
+        _ => {
+            parser.discard_node();
+            return false;
+        }
+    };
+
+    if !match_block(parser) {
+        panic!("Expected block after loop");
+    }
+
+    let construct = Construct::Statement(Statement::Loop);
+    parser.confirm_node(&construct);
+
+    return true;
+}
+
+fn match_statement_conditional(parser: &mut Parser) -> bool {
+    parser.start_node();
+
+    // if <expression> <block> [ else ( <block> | <conditional> ) ]
+
+    match parser.consume_token() {
+        Token::Keyword(Keyword::If) => (),
+        _ => {
+            parser.discard_node();
+            return false;
+        }
+    };
+
+    if !match_expression(parser) {
+        panic!("Expected condition after if");
+    }
+
+    if !match_block(parser) {
+        panic!("Expected block after if condition");
+    }
+
+    match parser.peek_token() {
+        Token::Keyword(Keyword::Else) => {
+            parser.consume_token();
+            if !match_block(parser) && !match_statement_conditional(parser) {
+                panic!("Expected block or if after else");
+            }
+        },
+        _ => (),
+    }
+
+    let construct = Construct::Statement(Statement::Conditional);
+    parser.confirm_node(&construct);
+
+    return true;
+}
+
+fn match_statement_while(parser: &mut Parser) -> bool {
+    parser.start_node();
+
+    // while <expression> <block>
+
+    match parser.consume_token() {
+        Token::Keyword(Keyword::While) => (),
+        _ => {
+            parser.discard_node();
+            return false;
+        }
+    };
+
+    if !match_expression(parser) {
+        panic!("Expected condition after while");
+    }
+
+    if !match_block(parser) {
+        panic!("Expected block after while condition");
+    }
+
+    let construct = Construct::Statement(Statement::While);
+    parser.confirm_node(&construct);
+
+    return true;
+}
+
+fn match_statement_control(parser: &mut Parser) -> bool {
+    parser.start_node();
+
+    // ( break | continue ) ;
+
+    let control = match parser.consume_token() {
+        Token::Keyword(Keyword::Break) => Control::Break,
+        Token::Keyword(Keyword::Continue) => Control::Continue,
+        _ => {
+            parser.discard_node();
+            return false;
+        }
+    };
+
+    match parser.consume_token() {
+        Token::Semicolon => (),
+        _ => panic!("Expected ';' after {}", control),
+    };
+
+    let construct = Construct::Statement(Statement::Control(control));
+    parser.confirm_node(&construct);
+
+    return true;
+}
+
+fn match_statement_defer(parser: &mut Parser) -> bool {
+    parser.start_node();
+
+    // defer <statement>
+
+    match parser.consume_token() {
+        Token::Keyword(Keyword::Defer) => (),
+        _ => {
+            parser.discard_node();
+            return false;
+        }
+    };
+
+    if !match_statement(parser) {
+        panic!("Expected statement after defer");
+    }
+
+    let construct = Construct::Statement(Statement::Defer);
+    parser.confirm_node(&construct);
+
+    return true;
+}
+
+fn match_statement_expression(parser: &mut Parser) -> bool {
+    parser.start_node();
+
+    // <expression> ;
+
     if !match_expression(parser) {
         parser.discard_node();
         return false;
@@ -75,7 +249,7 @@ fn match_statement_return(parser: &mut Parser) -> bool {
         }
     };
 
-    let construct = Construct::Statement(Statement::Return);
+    let construct = Construct::Statement(Statement::Expression);
     parser.confirm_node(&construct);
 
     return true;
@@ -88,5 +262,26 @@ pub fn match_statement(parser: &mut Parser) -> bool {
     if match_statement_return(parser) {
         return true;
     }
+    if match_statement_block(parser) {
+        return true;
+    }
+    if match_statement_loop(parser) {
+        return true;
+    }
+    if match_statement_conditional(parser) {
+        return true;
+    }
+    if match_statement_while(parser) {
+        return true;
+    }
+    if match_statement_control(parser) {
+        return true;
+    }
+    if match_statement_defer(parser) {
+        return true;
+    }
+    if match_statement_expression(parser) {
+        return true;
+    }
     return false;
 }
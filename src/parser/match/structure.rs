@@ -4,6 +4,7 @@ use super::construct::*;
 use super::parser::Parser;
 
 use super::datatype::match_datatype;
+use super::function::match_function;
 
 
 fn match_member(parser: &mut Parser) -> bool {
@@ -47,10 +48,72 @@ fn match_member(parser: &mut Parser) -> bool {
     return true;
 }
 
+fn match_attribute(parser: &mut Parser) -> bool {
+    parser.start_node();
+
+    // "#" , "[" , identifier , [ "(" , constant , ")" ] , "]"
+
+    match parser.consume_token() {
+        Token::Hash => (),
+        _ => {
+            parser.discard_node();
+            return false;
+        },
+    }
+
+    match parser.consume_token() {
+        Token::LSBracket => (),
+        _ => panic!("Expected '[' after '#'"),
+    }
+
+    let attribute = match parser.consume_token() {
+        Token::Identifier(identifier) => match identifier.as_str() {
+            "packed" => Attribute::Packed,
+            "c_layout" => Attribute::CLayout,
+            "align" => {
+                match parser.consume_token() {
+                    Token::LParen => (),
+                    _ => panic!("Expected '(' after align"),
+                }
+                let alignment = match parser.consume_token() {
+                    Token::Constant(Constant::Int(alignment)) => *alignment,
+                    _ => panic!("Expected integer alignment in align()"),
+                };
+                if alignment <= 0 || alignment & (alignment - 1) != 0 {
+                    panic!("Alignment {} is not a power of two", alignment);
+                }
+                match parser.consume_token() {
+                    Token::RParen => (),
+                    _ => panic!("Expected ')' after alignment"),
+                }
+                Attribute::Align(alignment as usize)
+            },
+            _ => panic!("Unknown attribute {}", identifier),
+        },
+        _ => panic!("Expected attribute name after '#['"),
+    };
+
+    match parser.consume_token() {
+        Token::RSBracket => (),
+        _ => panic!("Expected ']' after attribute"),
+    }
+
+    let construct = Construct::Attribute(attribute);
+    parser.confirm_node(&construct);
+
+    return true;
+}
+
 pub fn match_structure(parser: &mut Parser) -> bool {
     parser.start_node();
 
-    // identifier , ":" , "struct" , { member }
+    // { attribute } , identifier , ":" , "struct" , { member | function }
+
+    loop {
+        if !match_attribute(parser) {
+            break;
+        }
+    }
 
     let name = match parser.consume_token() {
         Token::Identifier(identifier) => identifier,
@@ -90,8 +153,10 @@ pub fn match_structure(parser: &mut Parser) -> bool {
         },
     }
     
+    // Functions are tried first, since a function pointer member starts the
+    // same way and can't backtrack out of its argument datatypes
     loop {
-        if !match_member(parser) {
+        if !match_function(parser) && !match_member(parser) {
             break;
         }
     }
@@ -104,8 +169,8 @@ pub fn match_structure(parser: &mut Parser) -> bool {
         },
     }
 
-    // Size is calculated later
-    let construct = Construct::Structure(String::clone(name), 0);
+    // Size and alignment are calculated later
+    let construct = Construct::Structure(String::clone(name), 0, 0);
     parser.confirm_node(&construct);
 
     return true;
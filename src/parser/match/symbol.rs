@@ -4,10 +4,29 @@ use super::construct::*;
 use super::parser::Parser;
 
 use super::datatype::match_datatype;
+use super::expression::match_expression;
 use super::structure::match_structure;
+use super::alias::match_alias;
 use super::function::match_function;
 
 
+fn match_undefined(parser: &mut Parser) -> bool {
+    parser.start_node();
+
+    match parser.consume_token() {
+        Token::Keyword(Keyword::Undefined) => (),
+        _ => {
+            parser.discard_node();
+            return false;
+        },
+    }
+
+    let construct = Construct::Expression(Expression::Undefined);
+    parser.confirm_node(&construct);
+
+    return true;
+}
+
 fn match_variable(parser: &mut Parser) -> bool {
     parser.start_node();
 
@@ -32,6 +51,18 @@ fn match_variable(parser: &mut Parser) -> bool {
         return false;
     }
 
+    // [ "=" , ( expression | "undefined" ) ] , ";"
+
+    match parser.peek_token() {
+        Token::Equals => {
+            parser.consume_token();
+            if !match_undefined(parser) && !match_expression(parser) {
+                panic!("Expected expression after '=' in variable declaration");
+            }
+        },
+        _ => (),
+    }
+
     match parser.consume_token() {
         Token::Semicolon => (),
         _ => {
@@ -46,6 +77,59 @@ fn match_variable(parser: &mut Parser) -> bool {
     return true;
 }
 
+fn match_const(parser: &mut Parser) -> bool {
+    parser.start_node();
+
+    // identifier , ":" , "const" , datatype , "=" , expression , ";"
+
+    let name = match parser.consume_token() {
+        Token::Identifier(identifier) => identifier,
+        _ => {
+            parser.discard_node();
+            return false;
+        },
+    };
+
+    match parser.consume_token() {
+        Token::Colon => (),
+        _ => {
+            parser.discard_node();
+            return false;
+        },
+    };
+
+    match parser.consume_token() {
+        Token::Keyword(Keyword::Const) => (),
+        _ => {
+            parser.discard_node();
+            return false;
+        },
+    };
+
+    if !match_datatype(parser) {
+        panic!("Expected datatype after const");
+    }
+
+    match parser.consume_token() {
+        Token::Equals => (),
+        _ => panic!("Const {} must be given a value", name),
+    };
+
+    if !match_expression(parser) {
+        panic!("Expected expression after '=' in const declaration");
+    }
+
+    match parser.consume_token() {
+        Token::Semicolon => (),
+        _ => panic!("Expected ';' after const declaration"),
+    };
+
+    let construct = Construct::Const(String::clone(name));
+    parser.confirm_node(&construct);
+
+    return true;
+}
+
 pub fn match_symbol(parser: &mut Parser) -> bool {
     if match_function(parser) {
         return true;
@@ -53,6 +137,12 @@ pub fn match_symbol(parser: &mut Parser) -> bool {
     if match_structure(parser) {
         return true;
     }
+    if match_alias(parser) {
+        return true;
+    }
+    if match_const(parser) {
+        return true;
+    }
     if match_variable(parser) {
         return true;
     }
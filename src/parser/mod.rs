@@ -10,13 +10,14 @@ use ast::Ast;
 use parser::Parser;
 use r#match::program::match_program;
 
-pub fn build_ast(tokens: &Vec<Token>) -> Option<Ast> {
+pub fn build_ast(tokens: &Vec<Token>, locations: &Vec<Location>) -> Option<Ast> {
     let mut ast = Ast::new();
     let mut parser = Parser::new(
         &mut ast,
         tokens,
+        locations,
         128,
-        32,
+        4096,
     );
     if !match_program(&mut parser) {
         println!("Failed to match program");
@@ -1,5 +1,6 @@
 
 use crate::lexer::token::Token;
+use crate::lexer::token::Location;
 use super::ast::Ast;
 use super::construct::Construct;
 
@@ -13,6 +14,7 @@ struct State {
 pub struct Parser<'a> {
     ast: &'a mut Ast,
     tokens: &'a Vec<Token>,
+    locations: &'a Vec<Location>,
     state_stack: Vec<State>,
     children: Vec<usize>,
     state: State,
@@ -23,12 +25,14 @@ impl<'a> Parser<'a> {
     pub fn new(
         ast: &'a mut Ast,
         tokens: &'a Vec<Token>,
+        locations: &'a Vec<Location>,
         max_stack_size: usize,
         max_num_children: usize) -> Parser<'a>
     {
         let mut parser = Parser {
             ast: ast,
             tokens: tokens,
+            locations: locations,
             state_stack: Vec::new(),
             children: Vec::new(),
             state: State { 
@@ -68,8 +72,16 @@ impl<'a> Parser<'a> {
         let start = self.state_stack.pop()
             .expect("Trying to confirm a node without starting one.");
 
+        // A node is where it starts, which is where its first child starts
+        // if it was started with previous nodes
         let child_nodes = &self.children[start.child_i..self.state.child_i];
-        self.ast.set_node(self.state.node_i, construct, child_nodes);
+        let mut location = self.locations[start.token_i];
+        if let Some(&child) = child_nodes.first() {
+            if self.ast.nodes[child].location < location {
+                location = self.ast.nodes[child].location;
+            }
+        }
+        self.ast.set_node(self.state.node_i, construct, child_nodes, location);
 
         self.state.child_i = start.child_i;
         while self.children.len() > self.state.child_i {
@@ -0,0 +1,26 @@
+#ifndef GUARD_H
+#define GUARD_H
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+int before(int value);
+
+typedef struct point {
+    int x;
+    int y;
+} point_t;
+
+int get(const char *name, point_t q, int index);
+struct point make_point(int x, int y);
+static inline int next(int a) { return a + 1; }
+int broken(int a
+
+#ifdef __cplusplus
+}
+#endif
+
+int after(int);
+
+#endif
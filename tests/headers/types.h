@@ -0,0 +1,35 @@
+#include <stddef.h>
+
+#define BUFFER_SIZE 4096
+#define MASK (BUFFER_SIZE - 1)
+#define RATIO 0.5
+#define LIMIT 10u
+#define NAME "name"
+#define MAX(a, b) ((a) > (b) ? (a) : (b))
+
+typedef struct {
+    int x;
+    int y;
+} vec2;
+
+typedef struct node {
+    struct node *next;
+    const char *name;
+    unsigned char flags;
+} node_t;
+
+union value {
+    int i;
+    float f;
+};
+int read_value(union value *v);
+
+typedef int (*compare_fn)(const void *, const void *);
+void sort(void *base, size_t count, size_t size, compare_fn compare);
+void on_event(void (*callback)(int code, void *data), void *data);
+
+enum color { RED, GREEN = 4, BLUE };
+
+vec2 *vec2_add(vec2 *a, const vec2 *b);
+long node_count(const node_t *head);
+int printf(const char *format, ...);
@@ -0,0 +1,374 @@
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use std::process::{Command, Output};
+
+fn compile(name: &str) -> (Output, PathBuf) {
+    return compile_with(name, &[], &[]);
+}
+
+// Modules are paths relative to the crate root, linked in after the program.
+fn compile_with(name: &str, options: &[&str], modules: &[&str]) -> (Output, PathBuf) {
+    let source = format!("{}/tests/programs/{}.z", env!("CARGO_MANIFEST_DIR"), name);
+    let executable = env::temp_dir().join(format!("z-test-{}", name));
+    let _ = fs::remove_file(&executable);
+    let output = Command::new(env!("CARGO_BIN_EXE_compiler"))
+        .args(options)
+        .arg(&source)
+        .args(modules.iter().map(|module| format!("{}/{}", env!("CARGO_MANIFEST_DIR"), module)))
+        .arg(&executable)
+        .output()
+        .expect("Failed to run the compiler");
+    return (output, executable);
+}
+
+// Compiles a program in tests/programs and runs it, returning its exit status.
+fn run(name: &str) -> i32 {
+    return run_with(name, &[], &[], &[]);
+}
+
+// Same as run, with the given options and modules, passing args to the
+// program.
+fn run_with(name: &str, options: &[&str], modules: &[&str], args: &[&str]) -> i32 {
+    let (output, executable) = compile_with(name, options, modules);
+    assert!(output.status.success(), "Failed to compile {}:\n{}",
+            name, String::from_utf8_lossy(&output.stderr));
+    let status = Command::new(&executable)
+        .args(args)
+        .status()
+        .expect("Failed to run the compiled program");
+    let _ = fs::remove_file(&executable);
+    return status.code().expect("Program was killed by a signal");
+}
+
+// Compiles a program in tests/programs against libc and runs it, returning
+// what it prints.
+fn run_libc(name: &str) -> String {
+    return run_libc_with(name, &[]);
+}
+
+// Same as run_libc, linking in the given modules.
+fn run_libc_with(name: &str, modules: &[&str]) -> String {
+    let (output, executable) = compile_with(name, &["--libc"], modules);
+    assert!(output.status.success(), "Failed to compile {}:\n{}",
+            name, String::from_utf8_lossy(&output.stderr));
+    let output = Command::new(&executable)
+        .output()
+        .expect("Failed to run the compiled program");
+    let _ = fs::remove_file(&executable);
+    assert!(output.status.success(), "{} exited with {}", name, output.status);
+    return String::from_utf8_lossy(&output.stdout).into_owned();
+}
+
+// Compiles a program in tests/programs with overflow checks, and runs it
+// expecting a check to fail. Returns the message it panics with.
+fn run_checked(name: &str) -> String {
+    return run_checked_with(name, &["--overflow-checks"]).1;
+}
+
+// Same as run_checked, with the given options. Returns the exit status as
+// well as the message.
+fn run_checked_with(name: &str, options: &[&str]) -> (i32, String) {
+    let (output, executable) = compile_with(name, options, &[]);
+    assert!(output.status.success(), "Failed to compile {}:\n{}",
+            name, String::from_utf8_lossy(&output.stderr));
+    let output = Command::new(&executable)
+        .output()
+        .expect("Failed to run the compiled program");
+    let _ = fs::remove_file(&executable);
+    assert!(!output.status.success(), "{} didn't fail a check", name);
+    let status = output.status.code().expect("Program was killed by a signal");
+    return (status, String::from_utf8_lossy(&output.stderr).into_owned());
+}
+
+// Compiles a program in tests/programs that should be rejected, returning
+// the error.
+fn compile_error(name: &str) -> String {
+    let (output, executable) = compile(name);
+    let _ = fs::remove_file(&executable);
+    assert!(!output.status.success(), "Compiled {}, which should be an error", name);
+    return String::from_utf8_lossy(&output.stderr).into_owned();
+}
+
+// A non-distinct alias is interchangeable with its datatype, including
+// behind pointers, while a distinct one only matches itself
+#[test]
+fn type_alias() {
+    assert_eq!(run("type_alias"), 7);
+    assert!(compile_error("distinct_alias_error").contains("Datatypes don't match in initialisation of n"));
+    assert!(compile_error("alias_cycle_error").contains("Alias A is defined in terms of itself"));
+}
+
+#[test]
+fn nested_function() {
+    assert_eq!(run("nested_function"), 17);
+}
+
+// Consts and global initialisers are evaluated at compile time, in the
+// datatype they're declared with
+#[test]
+fn constant_evaluation() {
+    assert_eq!(run("constants"), 130);
+    assert!(compile_error("const_overflow_error").contains("Overflow in constant expression, 128 doesn't fit in Primitive(i8)"));
+    assert!(compile_error("const_division_error").contains("Division by zero in constant expression"));
+    assert!(compile_error("const_cycle_error").contains("Const A is defined in terms of itself"));
+}
+
+#[test]
+fn sizeof_expression() {
+    assert_eq!(run("sizeof_expression"), 7);
+}
+
+// Deferred statements run in reverse order at every exit from their block
+#[test]
+fn defer_order() {
+    assert_eq!(run_libc("defer_order"), "\
+early: inner
+early: outer
+loop: continue
+loop: deferred
+loop: break
+loop: deferred
+main: first
+main: last
+");
+}
+
+#[test]
+fn struct_argument() {
+    assert_eq!(run("struct_argument"), 1);
+}
+
+#[test]
+fn member_assignment() {
+    assert_eq!(run("member_assignment"), 7);
+    assert!(compile_error("member_assignment_error").contains("v is read before being assigned"));
+}
+
+#[test]
+fn pointer_datatype() {
+    assert!(compile_error("pointer_datatype_error").contains("Datatypes don't match in initialisation of p"));
+}
+
+// && and || only evaluate their right side when the left doesn't decide the
+// result, so it can dereference a pointer checked on the left
+#[test]
+fn short_circuit() {
+    assert_eq!(run_libc("short_circuit"), "\
+guard: 0 0 1
+missing: 1 1 0
+order: 1 4 6 7 8 10
+");
+    assert!(compile_error("condition_error").contains("Condition must be a bool"));
+}
+
+#[test]
+fn lexer_error() {
+    assert!(compile_error("lexer_error").contains("tests/programs/lexer_error.z:3:15: Unknown escape sequence \\q"));
+}
+
+#[test]
+fn invalid_digit() {
+    assert!(compile_error("invalid_digit_error").contains("invalid_digit_error.z:2:14: Invalid digit 2 in base 2 number literal"));
+}
+
+// Expressions of literals alone are evaluated at compile time, as the
+// datatype they're assigned, returned or cast to
+#[test]
+fn literal_expressions() {
+    assert_eq!(run("literal_expressions"), 194);
+    assert!(compile_error("literal_overflow_error").contains("Overflow in constant expression, 300 doesn't fit in Primitive(u8)"));
+}
+
+// f32 and f64 arithmetic, passing and comparisons, where any comparison with
+// NaN is false except !=
+#[test]
+fn float_arithmetic() {
+    assert_eq!(run_libc("float_arithmetic"), "\
+f64: 5.5 9.5 -15 -3.75 -7.5
+f32: 4.5 1.25
+call: 2.75
+compare: 0 1 1 0 1 0
+nan: 0 1 0 0 0
+branch: unordered
+");
+}
+
+// Wrapping, signed and unsigned compares, and extension to the width printf
+// reads, for each integer primitive
+#[test]
+fn integer_widths() {
+    assert_eq!(run_libc("integer_widths"), "\
+i8: -128 127 44 1 0
+i16: -32768 32767 24464 1 0
+i32: -2147483648 2147483647 1410065408 1 0
+i64: -9223372036854775808 9223372036854775807 7766279631452241920 1 0
+u8: 0 255 88 1 0
+u16: 0 65535 24464 1 0
+u32: 0 4294967295 1410065408 1 0
+u64: 0 18446744073709551615 7766279631452241920 1 0
+");
+}
+
+#[test]
+fn integer_operators() {
+    assert_eq!(run_libc("integer_operators"), "\
+i8: -3 -1 -56 -1
+i8: 0 -5 -4 32
+u8: 124 1 200 31
+u8: 0 251 124 0
+i64: -2333333333 -1 3298534883328 -1 0
+u64: 1844674407370955161 5 18014398509481983
+mask: 2
+");
+}
+
+#[test]
+fn integer_cast() {
+    assert_eq!(run_libc("integer_cast"), "\
+-5 250 -5 4294967291
+44 44 -1 4294967295
+250 A
+");
+    assert!(run_checked("shift_check").contains("shift_check.z:4:12: Shift amount out of range"));
+    assert!(run_checked("cast_check").contains("cast_check.z:3:17: Cast value out of range"));
+}
+
+// Indexing past the end of a slice and dereferencing null write where it
+// happened to stderr, and exit with 101
+#[test]
+fn safety_checks() {
+    let (status, message) = run_checked_with("bounds_check", &["--safety-checks"]);
+    assert_eq!(status, 101);
+    assert!(message.ends_with("tests/programs/bounds_check.z:3:18: Index out of bounds\n"));
+    let (status, message) = run_checked_with("null_check", &["--safety-checks"]);
+    assert_eq!(status, 101);
+    assert!(message.ends_with("tests/programs/null_check.z:6:13: Dereferencing a null pointer\n"));
+}
+
+// Without libc, _start passes argc and argv to main, and exits with what it
+// returns
+#[test]
+fn exit_status() {
+    assert_eq!(run_with("exit_status", &[], &[], &["a", "b", "c"]), 42);
+    assert_eq!(run_with("exit_status", &[], &[], &["zed"]), 45);
+}
+
+// Arguments beyond the registers of their class are passed on the stack, to
+// both C and functions in the program
+#[test]
+fn stack_arguments() {
+    assert_eq!(run_libc("stack_arguments"), "\
+ints: -1 -2 -3 -4 5 6 7 8 -9
+ints: 1 2 3 4 5 6 7 8 9
+floats: 1.0 2.0 3.0 4.0 5.0 6.0 7.0 8.0 9.0 10.0 11
+pairs: 84
+");
+    assert!(compile_error("extern_struct_error").contains("extern_struct_error.z:9:10: Argument 1 in call to plot is a struct or slice"));
+}
+
+// Exported functions and the structs they use are declared for C, with the
+// layout checked by static assertions the C compiler accepts
+#[test]
+fn c_header() {
+    let (output, header) = compile_with("c_header", &["--emit=c-header"], &[]);
+    assert!(output.status.success(), "Failed to emit c_header:\n{}",
+            String::from_utf8_lossy(&output.stderr));
+    let content = fs::read_to_string(&header).expect("Failed to read the header");
+    let expected = fs::read_to_string(format!("{}/tests/programs/c_header.h", env!("CARGO_MANIFEST_DIR")))
+        .expect("Failed to read the expected header");
+    // The first line names the input, which is an absolute path here
+    assert_eq!(content.split_once('\n').unwrap().1, expected);
+    let status = Command::new("cc")
+        .args(["-fsyntax-only", "-Wall", "-Wextra", "-pedantic", "-std=c11", "-x", "c"])
+        .arg(&header)
+        .status()
+        .expect("Failed to run the C compiler");
+    let _ = fs::remove_file(&header);
+    assert!(status.success(), "C compiler rejected the header");
+}
+
+// Imports a header in tests/headers, checking the declarations match the
+// .z file next to it, and that they compile. Returns the warnings.
+fn import_header(name: &str) -> String {
+    let directory = format!("{}/tests/headers", env!("CARGO_MANIFEST_DIR"));
+    let module = env::temp_dir().join(format!("z-test-{}.z", name));
+    let output = Command::new(env!("CARGO_BIN_EXE_compiler"))
+        .arg("--import-c-header")
+        .arg(format!("{}/{}.h", directory, name))
+        .arg(&module)
+        .output()
+        .expect("Failed to run the compiler");
+    assert!(output.status.success(), "Failed to import {}:\n{}",
+            name, String::from_utf8_lossy(&output.stderr));
+    let content = fs::read_to_string(&module).expect("Failed to read the imported module");
+    let expected = fs::read_to_string(format!("{}/{}.z", directory, name))
+        .expect("Failed to read the expected module");
+    assert_eq!(content, expected);
+
+    let assembly = env::temp_dir().join(format!("z-test-{}.s", name));
+    let compiled = Command::new(env!("CARGO_BIN_EXE_compiler"))
+        .arg("--emit=asm")
+        .arg(&module)
+        .arg(&assembly)
+        .output()
+        .expect("Failed to run the compiler");
+    let _ = fs::remove_file(&module);
+    let _ = fs::remove_file(&assembly);
+    assert!(compiled.status.success(), "Failed to compile the module imported from {}:\n{}",
+            name, String::from_utf8_lossy(&compiled.stderr));
+    return String::from_utf8_lossy(&output.stdout).into_owned();
+}
+
+// Declarations after an extern "C" block or one that can't be imported are
+// still found, and functions passing structs by value are skipped
+#[test]
+fn import_header_guard() {
+    let warnings = import_header("guard");
+    assert!(warnings.contains("argument q of get is a struct passed by value"));
+    assert!(warnings.contains("make_point returns a struct by value"));
+    assert!(warnings.contains("next is defined in the header"));
+}
+
+// Constants, typedefs of structs and functions, enums, and unions, which can
+// only be pointed to
+#[test]
+fn import_header_types() {
+    let warnings = import_header("types");
+    assert!(warnings.contains("#define NAME: literal can't be used in a constant"));
+    assert!(warnings.contains("union value isn't imported, it can only be pointed to"));
+}
+
+// The allocators in std/allocator.z, used through new and their function
+// pointers
+#[test]
+fn allocators() {
+    assert_eq!(run_libc_with("allocators", &["std/allocator.z"]), "\
+mmap: 3 4
+moved: 3 4
+bump: 7 16 1
+reset: 1
+grown: 1
+pool: 2 1 1
+");
+}
+
+// Without libc, pages come from the mmap system call. Freeing or growing a
+// null pointer doesn't do arithmetic on it, so passes the overflow checks.
+#[test]
+fn allocators_syscall() {
+    assert_eq!(run_with("allocators_syscall", &["--overflow-checks"], &["std/allocator.z"], &[]), 42);
+}
+
+// Arguments after ... are promoted like in C: f32 to f64, and narrow
+// integers extended to 32 bits by their signedness
+#[test]
+fn variadic_promotion() {
+    assert_eq!(run_libc("variadic_promotion"), "\
+ints: -5 250 -300 65000 z 1
+floats: 1.25 2.50 -0.5 2.5
+literals: -7 5000000000 3000000000 1.5 q
+snprintf: -5-1.2-250 10
+");
+    assert!(compile_error("variadic_struct_error").contains("variadic_struct_error.z:10:24: Argument 2 in call to printf is a struct or slice"));
+}
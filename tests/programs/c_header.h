@@ -0,0 +1,43 @@
+#ifndef Z_TEST_C_HEADER
+#define Z_TEST_C_HEADER
+
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+
+struct Vector;
+struct Body;
+
+struct Vector {
+    float x; // offset 0
+    float y; // offset 4
+};
+_Static_assert(sizeof(struct Vector) == 8, "Size of struct Vector doesn't match");
+_Static_assert(_Alignof(struct Vector) == 4, "Alignment of struct Vector doesn't match");
+_Static_assert(offsetof(struct Vector, x) == 0, "Offset of Vector.x doesn't match");
+_Static_assert(offsetof(struct Vector, y) == 4, "Offset of Vector.y doesn't match");
+
+struct Body {
+    struct Vector position; // offset 0
+    double mass; // offset 8
+    bool alive; // offset 16
+    char tag; // offset 17
+    struct Body *next; // offset 24
+    const char *name; // offset 32
+    bool (*step)(struct Body *, double); // offset 40
+};
+_Static_assert(sizeof(struct Body) == 48, "Size of struct Body doesn't match");
+_Static_assert(_Alignof(struct Body) == 8, "Alignment of struct Body doesn't match");
+_Static_assert(offsetof(struct Body, position) == 0, "Offset of Body.position doesn't match");
+_Static_assert(offsetof(struct Body, mass) == 8, "Offset of Body.mass doesn't match");
+_Static_assert(offsetof(struct Body, alive) == 16, "Offset of Body.alive doesn't match");
+_Static_assert(offsetof(struct Body, tag) == 17, "Offset of Body.tag doesn't match");
+_Static_assert(offsetof(struct Body, next) == 24, "Offset of Body.next doesn't match");
+_Static_assert(offsetof(struct Body, name) == 32, "Offset of Body.name doesn't match");
+_Static_assert(offsetof(struct Body, step) == 40, "Offset of Body.step doesn't match");
+
+double body_mass(const struct Body *body);
+void body_move(struct Body *body, const struct Vector *by, float scale);
+struct Body *body_find(struct Body *first, char tag, int32_t *count);
+
+#endif